@@ -1,11 +1,24 @@
-use crate::audio_recorder::{AudioRecorder, AudioSource};
-use crate::config::AppConfig;
+use crate::audio_recorder::{AudioRecorder, AudioSource, RecordingFinish};
+use crate::config::{AppConfig, DeviceProfile, MAX_INPUT_GAIN_DB, MIN_INPUT_GAIN_DB};
+use crate::encoder::RecordingFormat;
+use crate::groq_request::{TranscriptSegment, TranscriptionOptions};
+use crate::mic_check::{MicCheck, MicCheckState, MIC_CHECK_SECS};
+use crate::pipe::{PipeFormat, MAX_PIPE_CHANNELS};
+use crate::player::{format_time, Player, PLAYBACK_SPEEDS};
+use crate::prompt_context::PromptSource;
+use crate::quality::AudioQuality;
+use crate::recovery::RecoveredRecording;
+use crate::replay::{ReplayCapture, REPLAY_MINUTES};
+use crate::routing::{select_route, JobContext, Route};
 use crate::shortcut_detector::{
     is_accessibility_trusted, HotkeyBindings, HotkeyCommand, HotkeyRuntime,
 };
+use crate::tracks::TrackLayout;
 use crate::transcription_history::{TranscriptionHistory, TranscriptionRecord};
+use crate::translation::{Translation, TranslationLayout};
 use chrono::Local;
 use eframe::egui;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
@@ -18,6 +31,7 @@ const MIC_GRAPH_SAMPLE_INTERVAL: Duration = Duration::from_millis(16);
 const WINDOW_NORMAL_SIZE: [f32; 2] = [620.0, 480.0];
 const WINDOW_RECORDING_SIZE: [f32; 2] = [420.0, 130.0];
 const WINDOW_RECORDING_MARGIN: f32 = 16.0;
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(3);
 
 pub struct WgoApp {
     recorder: Arc<Mutex<AudioRecorder>>,
@@ -30,9 +44,14 @@ pub struct WgoApp {
     selected_audio_source: AudioSource,
     pending_toggle_shortcut: String,
     pending_show_shortcut: String,
+    pending_translate_shortcut: String,
+    pending_replay_shortcut: String,
     pending_hold_key: String,
     recording_target: Option<ShortcutTarget>,
     recording_by_hold: bool,
+    /// Target language when the current recording was started with the
+    /// translate shortcut.
+    hotkey_translation: Option<String>,
     hotkey_rx: Receiver<HotkeyCommand>,
     ui_event_rx: Receiver<UiEvent>,
     ui_event_tx: mpsc::Sender<UiEvent>,
@@ -44,6 +63,23 @@ pub struct WgoApp {
     status_line: String,
     last_transcription: String,
     last_failed_audio_path: Option<String>,
+    /// Recordings interrupted by a crash, repaired on launch.
+    recovered_recordings: Vec<RecoveredRecording>,
+    /// Desktop replay buffer, open while enabled in the settings.
+    replay: Option<ReplayCapture>,
+    /// Last input failure during the current recording, shown with the controls.
+    stream_warning: Option<String>,
+    /// Levels of recordings waiting for their transcription, by audio path.
+    pending_quality: HashMap<String, AudioQuality>,
+    /// Record-and-playback microphone check in progress.
+    mic_check: Option<MicCheck>,
+    mic_check_result: Option<AudioQuality>,
+    /// History recording open in the built-in player.
+    player: Option<Player>,
+    player_segments: Vec<TranscriptSegment>,
+    /// Recording being decoded for the player.
+    player_loading: Option<String>,
+    last_failed_track_layout: TrackLayout,
     update_state: UpdateState,
     history: TranscriptionHistory,
     was_recording: bool,
@@ -62,6 +98,8 @@ enum AppTab {
 enum ShortcutTarget {
     Toggle,
     ShowWindow,
+    Translate,
+    Replay,
     HoldKey,
 }
 
@@ -70,10 +108,40 @@ enum UiEvent {
         audio_path: String,
         text: String,
         markdown_path: Option<PathBuf>,
+        route: Route,
+        suspicious: Option<String>,
+        translation: Option<Translation>,
+        translation_error: Option<String>,
+        track_layout: TrackLayout,
+        segments: Vec<TranscriptSegment>,
     },
     TranscriptionFailed {
         audio_path: String,
         error: String,
+        track_layout: TrackLayout,
+    },
+    SegmentReady {
+        audio_path: String,
+        track_layout: TrackLayout,
+        /// Translation asked for by the recording the segment belongs to.
+        translate_to: Option<String>,
+    },
+    /// A stopped recording was encoded to `audio_path`.
+    RecordingFinished {
+        wav_path: String,
+        audio_path: String,
+    },
+    ReplaySaved {
+        audio_path: String,
+    },
+    ReplayFailed(String),
+    PlayerLoaded {
+        audio_path: String,
+        result: Result<(Vec<f32>, u32), String>,
+    },
+    DevicesChanged {
+        microphones: Vec<String>,
+        desktop_devices: Vec<String>,
     },
     UpdateAvailable {
         version: String,
@@ -105,14 +173,29 @@ enum UpdateState {
 impl WgoApp {
     pub fn new(hotkey_rx: Receiver<HotkeyCommand>, hotkey_runtime: HotkeyRuntime) -> Self {
         let config = AppConfig::load();
+        // Audio streams stay on the UI thread, like the recorder that owns them.
+        #[allow(clippy::arc_with_non_send_sync)]
         let recorder = Arc::new(Mutex::new(AudioRecorder::new()));
 
         if let Ok(mut rec) = recorder.lock() {
             rec.set_device_name(config.microphone_name.clone());
             rec.set_desktop_device_name(config.desktop_device_name.clone());
             rec.set_audio_source(config.audio_source.clone());
+            rec.set_target_sample_rate(config.recording_sample_rate);
+            rec.set_mix_gains(config.mic_mix_gain, config.desktop_mix_gain);
+            rec.set_track_layout(config.track_layout);
+            rec.set_trim_silence(config.vad_trim_silence);
+            rec.set_dsp_settings(config.dsp);
+            rec.set_recording_format(config.recording_format);
+            rec.set_segment_limits(config.segment_max_mb, config.segment_max_minutes);
+            rec.set_device_profiles(config.device_profiles.clone());
+            rec.set_recordings_dir(config.recordings_dir_path());
+            rec.set_pipe_settings(config.pipe_input.clone());
+            rec.set_preroll_secs(config.preroll_secs);
         }
 
+        let recovered_recordings =
+            crate::recovery::recover_recordings(&config.recordings_dir_path());
         let microphones = AudioRecorder::list_input_devices().unwrap_or_default();
         let desktop_devices = AudioRecorder::list_desktop_input_devices().unwrap_or_default();
 
@@ -121,8 +204,12 @@ impl WgoApp {
         let history = TranscriptionHistory::load();
 
         spawn_update_check(ui_event_tx.clone());
+        spawn_device_watcher(
+            ui_event_tx.clone(),
+            (microphones.clone(), desktop_devices.clone()),
+        );
 
-        Self {
+        let mut app = Self {
             recorder,
             hotkey_runtime,
             selected_microphone: config.microphone_name.clone(),
@@ -130,9 +217,12 @@ impl WgoApp {
             selected_audio_source: config.audio_source.clone(),
             pending_toggle_shortcut: config.toggle_shortcut.clone(),
             pending_show_shortcut: config.show_window_shortcut.clone(),
+            pending_translate_shortcut: config.translate_shortcut.clone(),
+            pending_replay_shortcut: config.replay_shortcut.clone(),
             pending_hold_key: config.hold_to_record_key.clone().unwrap_or_default(),
             recording_target: None,
             recording_by_hold: false,
+            hotkey_translation: None,
             config,
             microphones,
             desktop_devices,
@@ -147,28 +237,197 @@ impl WgoApp {
             status_line: "Ready".to_string(),
             last_transcription: String::new(),
             last_failed_audio_path: None,
+            recovered_recordings,
+            replay: None,
+            stream_warning: None,
+            pending_quality: HashMap::new(),
+            mic_check: None,
+            mic_check_result: None,
+            player: None,
+            player_segments: Vec::new(),
+            player_loading: None,
+            last_failed_track_layout: TrackLayout::Mixed,
             update_state: UpdateState::Checking,
             history,
             was_recording: false,
             history_search: String::new(),
             last_audio_path: None,
+        };
+        app.sync_replay();
+        app
+    }
+
+    /// Opens, reopens or closes the replay buffer to match the settings.
+    fn sync_replay(&mut self) {
+        let minutes = self.config.replay_buffer_minutes;
+        let device_name = &self.config.desktop_device_name;
+        if minutes == 0 {
+            self.replay = None;
+            return;
+        }
+        if self
+            .replay
+            .as_ref()
+            .is_some_and(|r| r.matches(device_name, minutes))
+        {
+            return;
+        }
+        // Close the old stream before opening the device again.
+        self.replay = None;
+        let Ok(backend) = self.recorder.lock().map(|rec| rec.backend()) else {
+            return;
+        };
+        match ReplayCapture::start(backend.as_ref(), device_name.clone(), minutes) {
+            Ok(replay) => self.replay = Some(replay),
+            Err(err) => self.status_line = format!("Replay buffer unavailable: {err}"),
         }
     }
 
-    fn start_transcription_job(&self, audio_path: String) {
-        let cfg = self.config.clone();
+    /// Saves the last `minutes` of desktop audio and transcribes them.
+    fn save_replay(&mut self, minutes: u64) {
+        let Some(replay) = self.replay.as_ref() else {
+            self.status_line = "The replay buffer is off. Turn it on in Settings.".to_string();
+            return;
+        };
+        if !has_non_empty_api_key(&self.config) {
+            self.active_tab = AppTab::Settings;
+            self.status_line =
+                "Cannot transcribe without a Groq API key. Add one in Settings.".to_string();
+            return;
+        }
+
+        let samples = replay.snapshot(minutes);
+        let dir = match self.config.ensure_recordings_dir() {
+            Ok(dir) => dir,
+            Err(err) => {
+                self.status_line = format!("Failed to prepare recordings directory: {err}");
+                return;
+            }
+        };
+        self.status_line = format!("Saving the last {minutes} min of desktop audio...");
         let ui_tx = self.ui_event_tx.clone();
+        std::thread::spawn(move || {
+            let event = match crate::replay::save(&samples, &dir) {
+                Ok(path) => UiEvent::ReplaySaved {
+                    audio_path: path.to_string_lossy().to_string(),
+                },
+                Err(err) => UiEvent::ReplayFailed(err),
+            };
+            let _ = ui_tx.send(event);
+        });
+    }
 
-        std::thread::spawn(
-            move || match crate::groq_request::transcribe_audio(&audio_path) {
-                Ok(text) => {
-                    crate::utils::copy_to_clipboard(&text);
+    /// `translate_to` overrides the active profile's translation language.
+    /// Split track layouts are transcribed per track and labelled by speaker.
+    /// A just-stopped recording is `finish`ed first.
+    fn start_transcription_job(
+        &self,
+        audio_path: String,
+        translate_to: Option<String>,
+        track_layout: TrackLayout,
+        finish: Option<RecordingFinish>,
+    ) {
+        let cfg = self.config.clone();
+        let translate_to = translate_to.or_else(|| cfg.profile_translation_language());
+        let ui_tx = self.ui_event_tx.clone();
 
-                    let md_path = match save_transcription_markdown(&cfg, &audio_path, &text) {
-                        Ok(path) => Some(path),
-                        Err(err) => {
-                            eprintln!("Markdown save failed: {err}");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let prompt = crate::prompt_context::build_prompt(&cfg, &self.history, now);
+
+        std::thread::spawn(move || {
+            let audio_path = match finish {
+                Some(finish) => {
+                    let finished = finish.apply(&audio_path);
+                    if finished != audio_path {
+                        let _ = ui_tx.send(UiEvent::RecordingFinished {
+                            wav_path: audio_path,
+                            audio_path: finished.clone(),
+                        });
+                    }
+                    finished
+                }
+                None => audio_path,
+            };
+            // Reading the duration may probe the whole file, so it stays off
+            // the UI thread.
+            let ctx = JobContext::for_audio(&cfg, &audio_path);
+            let route = select_route(&cfg.routing_rules, &ctx, Route::default_for(&cfg));
+            println!("Routing {audio_path} to {}", route.describe());
+            let options = TranscriptionOptions {
+                route,
+                language: ctx.language,
+                prompt,
+            };
+            let result = if track_layout.is_split() {
+                crate::tracks::transcribe_tracks(
+                    &audio_path,
+                    track_layout,
+                    &options,
+                    cfg.suppress_hallucinations,
+                    cfg.compress_pauses,
+                )
+            } else {
+                crate::pauses::transcribe(&audio_path, &options, cfg.compress_pauses)
+            };
+            match result {
+                Ok(transcript) => {
+                    let suspicious = if cfg.suppress_hallucinations {
+                        // Split tracks were already checked one by one.
+                        let energy = if track_layout.is_split() {
                             None
+                        } else {
+                            crate::hallucination::audio_energy(Path::new(&audio_path))
+                        };
+                        crate::hallucination::assess(&transcript, energy)
+                    } else {
+                        None
+                    };
+                    let mut text = transcript.text;
+                    let mut segments = transcript.segments;
+                    if suspicious.is_none() && cfg.inverse_text_normalization_enabled() {
+                        let language = options.language.clone().or(transcript.language);
+                        if let Some(language) = language {
+                            text = crate::text_normalization::normalize(&text, &language);
+                            for segment in &mut segments {
+                                segment.text =
+                                    crate::text_normalization::normalize(&segment.text, &language);
+                            }
+                        }
+                    }
+
+                    let mut translation = None;
+                    let mut translation_error = None;
+                    if let (None, Some(target)) = (&suspicious, &translate_to) {
+                        match crate::translation::translate(&cfg, &text, target) {
+                            Ok(t) => translation = Some(t),
+                            Err(err) => {
+                                eprintln!("Translation failed: {err}");
+                                translation_error = Some(err);
+                            }
+                        }
+                    }
+
+                    // Suspicious output is kept in history for review but never
+                    // pasted or exported.
+                    let md_path = if suspicious.is_some() {
+                        None
+                    } else {
+                        let pasted = translation.as_ref().map_or(text.as_str(), |t| &t.text);
+                        crate::utils::copy_to_clipboard(pasted);
+                        match save_transcription_markdown(
+                            &cfg,
+                            &audio_path,
+                            &text,
+                            translation.as_ref(),
+                        ) {
+                            Ok(path) => Some(path),
+                            Err(err) => {
+                                eprintln!("Markdown save failed: {err}");
+                                None
+                            }
                         }
                     };
 
@@ -176,16 +435,23 @@ impl WgoApp {
                         audio_path,
                         text,
                         markdown_path: md_path,
+                        route: options.route,
+                        suspicious,
+                        translation,
+                        translation_error,
+                        track_layout,
+                        segments,
                     });
                 }
                 Err(err) => {
                     let _ = ui_tx.send(UiEvent::TranscriptionFailed {
                         audio_path,
                         error: format!("Transcription error: {err}"),
+                        track_layout,
                     });
                 }
-            },
-        );
+            }
+        });
     }
 
     fn sample_mic_graph_if_due(&mut self) {
@@ -228,7 +494,22 @@ impl WgoApp {
                         self.start_recording(ctx);
                     }
                 }
+                HotkeyCommand::ToggleTranslatedRecording => {
+                    if self.is_recording() {
+                        self.stop_recording(ctx);
+                    } else {
+                        let language = self.config.translate_shortcut_language.trim().to_string();
+                        self.start_recording(ctx);
+                        if self.is_recording() {
+                            self.hotkey_translation = Some(language);
+                        }
+                    }
+                }
                 HotkeyCommand::ShowWindow => self.bring_to_front(ctx),
+                HotkeyCommand::SaveReplay => {
+                    let minutes = self.config.replay_buffer_minutes;
+                    self.save_replay(minutes);
+                }
                 HotkeyCommand::StartHoldRecording => {
                     if !self.is_recording() {
                         self.recording_by_hold = true;
@@ -287,6 +568,8 @@ impl WgoApp {
             match target {
                 ShortcutTarget::Toggle => self.pending_toggle_shortcut = shortcut,
                 ShortcutTarget::ShowWindow => self.pending_show_shortcut = shortcut,
+                ShortcutTarget::Translate => self.pending_translate_shortcut = shortcut,
+                ShortcutTarget::Replay => self.pending_replay_shortcut = shortcut,
                 ShortcutTarget::HoldKey => {} // handled via rdev callback
             }
             if !matches!(target, ShortcutTarget::HoldKey) {
@@ -304,10 +587,20 @@ impl WgoApp {
                     audio_path,
                     text,
                     markdown_path,
+                    route,
+                    suspicious,
+                    translation,
+                    translation_error,
+                    track_layout,
+                    segments,
                 } => {
-                    self.last_transcription = text.clone();
                     self.last_failed_audio_path = None;
                     self.last_audio_path = Some(audio_path.clone());
+                    if suspicious.is_none() {
+                        self.last_transcription = translation
+                            .as_ref()
+                            .map_or_else(|| text.clone(), |t| t.text.clone());
+                    }
 
                     let timestamp = SystemTime::now()
                         .duration_since(UNIX_EPOCH)
@@ -321,8 +614,22 @@ impl WgoApp {
                         transcription: text,
                         timestamp,
                         audio_path: Some(audio_path.clone()),
+                        route: Some(route),
+                        suspicious: suspicious.clone(),
+                        translation_language: translation.as_ref().map(|t| t.language.clone()),
+                        translation: translation.map(|t| t.text),
+                        track_layout,
+                        quality: self.pending_quality.remove(&audio_path),
+                        segments,
                     });
 
+                    if let Some(reason) = suspicious {
+                        self.status_line = format!(
+                            "Suppressed likely hallucination ({reason}). Review it in History."
+                        );
+                        continue;
+                    }
+
                     self.status_line = match markdown_path {
                         Some(path) => format!(
                             "Audio: {} \nMarkdown:{}",
@@ -331,11 +638,61 @@ impl WgoApp {
                         ),
                         None => format!("Transcribed {}", audio_path),
                     };
+                    if let Some(err) = translation_error {
+                        self.status_line = format!("{err}. Copied the original instead.");
+                    }
                 }
-                UiEvent::TranscriptionFailed { audio_path, error } => {
+                UiEvent::TranscriptionFailed {
+                    audio_path,
+                    error,
+                    track_layout,
+                } => {
                     self.last_failed_audio_path = Some(audio_path.clone());
+                    self.last_failed_track_layout = track_layout;
                     self.status_line = format!("{error} | You can retry for {audio_path}");
                 }
+                UiEvent::SegmentReady {
+                    audio_path,
+                    track_layout,
+                    translate_to,
+                } => {
+                    self.status_line = format!("Transcribing segment {audio_path}...");
+                    self.start_transcription_job(audio_path, translate_to, track_layout, None);
+                }
+                UiEvent::RecordingFinished {
+                    wav_path,
+                    audio_path,
+                } => {
+                    if let Some(quality) = self.pending_quality.remove(&wav_path) {
+                        self.pending_quality.insert(audio_path, quality);
+                    }
+                }
+                UiEvent::ReplaySaved { audio_path } => {
+                    self.status_line = format!("Transcribing replay {audio_path}...");
+                    self.start_transcription_job(audio_path, None, TrackLayout::Mixed, None);
+                }
+                UiEvent::ReplayFailed(err) => self.status_line = err,
+                UiEvent::PlayerLoaded { audio_path, result } => {
+                    if self.player_loading.as_deref() != Some(audio_path.as_str()) {
+                        continue;
+                    }
+                    self.player_loading = None;
+                    match result.and_then(|(samples, rate)| Player::new(audio_path, samples, rate))
+                    {
+                        Ok(player) => {
+                            player.play();
+                            self.player = Some(player);
+                        }
+                        Err(err) => self.status_line = err,
+                    }
+                }
+                UiEvent::DevicesChanged {
+                    microphones,
+                    desktop_devices,
+                } => {
+                    self.microphones = microphones;
+                    self.desktop_devices = desktop_devices;
+                }
                 UiEvent::UpdateAvailable {
                     version,
                     html_url,
@@ -477,6 +834,8 @@ impl WgoApp {
         self.config.audio_source = self.selected_audio_source.clone();
         self.config.toggle_shortcut = self.pending_toggle_shortcut.clone();
         self.config.show_window_shortcut = self.pending_show_shortcut.clone();
+        self.config.translate_shortcut = self.pending_translate_shortcut.trim().to_string();
+        self.config.replay_shortcut = self.pending_replay_shortcut.trim().to_string();
         self.config.hold_to_record_key = if self.pending_hold_key.trim().is_empty() {
             None
         } else {
@@ -486,7 +845,19 @@ impl WgoApp {
             rec.set_device_name(self.config.microphone_name.clone());
             rec.set_desktop_device_name(self.config.desktop_device_name.clone());
             rec.set_audio_source(self.config.audio_source.clone());
+            rec.set_target_sample_rate(self.config.recording_sample_rate);
+            rec.set_mix_gains(self.config.mic_mix_gain, self.config.desktop_mix_gain);
+            rec.set_track_layout(self.config.track_layout);
+            rec.set_trim_silence(self.config.vad_trim_silence);
+            rec.set_dsp_settings(self.config.dsp);
+            rec.set_recording_format(self.config.recording_format);
+            rec.set_segment_limits(self.config.segment_max_mb, self.config.segment_max_minutes);
+            rec.set_device_profiles(self.config.device_profiles.clone());
+            rec.set_recordings_dir(self.config.recordings_dir_path());
+            rec.set_pipe_settings(self.config.pipe_input.clone());
+            rec.set_preroll_secs(self.config.preroll_secs);
         }
+        self.sync_replay();
 
         match self.config.save() {
             Ok(()) => {
@@ -494,6 +865,8 @@ impl WgoApp {
                     self.config.toggle_shortcut.clone(),
                     self.config.show_window_shortcut.clone(),
                     self.config.hold_to_record_key.clone(),
+                    self.config.translate_shortcut.clone(),
+                    self.config.replay_shortcut.clone(),
                 ));
                 self.status_line = "Settings saved and hotkeys updated".to_string();
             }
@@ -526,6 +899,8 @@ impl WgoApp {
         match start_result {
             Ok(path) => {
                 self.status_line = format!("Recording started: {path}");
+                self.stream_warning = None;
+                self.mic_check = None;
                 self.enter_recording_mode(ctx);
             }
             Err(err) => {
@@ -561,6 +936,76 @@ impl WgoApp {
         }
     }
 
+    fn start_mic_check(&mut self) {
+        if let Err(err) = self.sync_recorder_audio_selection() {
+            self.status_line = err;
+            return;
+        }
+        let result = match self.recorder.lock() {
+            Ok(recorder) => recorder.start_mic_check(),
+            Err(_) => {
+                self.status_line = "Failed to lock recorder".to_string();
+                return;
+            }
+        };
+        match result {
+            Ok(check) => {
+                self.mic_check = Some(check);
+                self.mic_check_result = None;
+                self.status_line = "Microphone check: speak as you normally would".to_string();
+            }
+            Err(err) => self.status_line = err,
+        }
+    }
+
+    fn poll_mic_check(&mut self) {
+        let Some(check) = self.mic_check.as_mut() else {
+            return;
+        };
+        match check.poll() {
+            Ok(MicCheckState::Finished(quality)) => {
+                let issues = quality.issues();
+                self.status_line = if issues.is_empty() {
+                    "Microphone check: sounds good".to_string()
+                } else {
+                    format!("Microphone check found: {}", issues.join(", "))
+                };
+                self.mic_check_result = Some(quality);
+                self.mic_check = None;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                self.status_line = err;
+                self.mic_check = None;
+            }
+        }
+    }
+
+    fn mic_check_ui(&self, ui: &mut egui::Ui) {
+        if let Some(check) = &self.mic_check {
+            let text = match check.recorded_secs() {
+                Some(secs) => format!(
+                    "Recording… {:.0} s left. Speak as you normally would.",
+                    (MIC_CHECK_SECS - secs).max(0.0).ceil()
+                ),
+                None => "Playing back what will be sent for transcription…".to_string(),
+            };
+            ui.small(text);
+        } else if let Some(quality) = self.mic_check_result {
+            let issues = quality.issues();
+            let label = if issues.is_empty() {
+                ui.small("Microphone check: sounds good")
+            } else {
+                ui.label(
+                    egui::RichText::new(format!("Microphone check found: {}", issues.join(", ")))
+                        .small()
+                        .color(egui::Color32::from_rgb(240, 180, 60)),
+                )
+            };
+            label.on_hover_text(quality.describe());
+        }
+    }
+
     fn request_microphone_permission(&mut self) {
         if self.is_recording() {
             self.status_line =
@@ -629,10 +1074,43 @@ impl WgoApp {
         }
     }
 
+    /// Encodes and transcribes segments a long recording has closed so far,
+    /// while the recording carries on in the next one.
+    fn queue_finished_segments(&mut self) {
+        let Ok(recorder) = self.recorder.lock() else {
+            return;
+        };
+        let segments = recorder.take_finished_segments();
+        let finish = RecordingFinish {
+            trim_silence: false,
+            ..recorder.recording_finish()
+        };
+        let track_layout = finish.track_layout;
+        drop(recorder);
+
+        for wav_path in segments {
+            let ui_tx = self.ui_event_tx.clone();
+            let translate_to = self.hotkey_translation.clone();
+            std::thread::spawn(move || {
+                let audio_path = finish.apply(&wav_path);
+                let _ = ui_tx.send(UiEvent::SegmentReady {
+                    audio_path,
+                    track_layout,
+                    translate_to,
+                });
+            });
+        }
+    }
+
     fn stop_recording(&mut self, ctx: &egui::Context) {
-        let filename = match self.recorder.lock() {
+        self.queue_finished_segments();
+        let (filename, finish, quality) = match self.recorder.lock() {
             Ok(mut recorder) => match recorder.stop_recording() {
-                Ok(Some(path)) => path,
+                Ok(Some(path)) => (
+                    path,
+                    recorder.recording_finish(),
+                    recorder.recording_quality(),
+                ),
                 Ok(None) => {
                     self.status_line = "No active recording".to_string();
                     return;
@@ -654,10 +1132,25 @@ impl WgoApp {
         }
 
         self.status_line = format!("Recording stopped. Transcribing {}...", filename);
-        self.start_transcription_job(filename);
+        if let Some(quality) = quality {
+            let issues = quality.issues();
+            if !issues.is_empty() {
+                self.status_line = format!(
+                    "Recording stopped (poor audio: {}). Transcribing {}...",
+                    issues.join(", "),
+                    filename
+                );
+            }
+            self.pending_quality.insert(filename.clone(), quality);
+        }
+        let translate_to = self.hotkey_translation.take();
+        self.start_transcription_job(filename, translate_to, finish.track_layout, Some(finish));
     }
 
+    /// Discards the segment being recorded; segments that already closed
+    /// are transcribed as usual.
     fn cancel_recording(&mut self, ctx: &egui::Context) {
+        self.queue_finished_segments();
         let filename = match self.recorder.lock() {
             Ok(mut recorder) => match recorder.stop_recording() {
                 Ok(Some(path)) => path,
@@ -677,7 +1170,12 @@ impl WgoApp {
         };
 
         self.exit_recording_mode(ctx);
+        self.hotkey_translation = None;
 
+        let desktop_track = crate::tracks::desktop_track_path(Path::new(&filename));
+        if desktop_track.exists() {
+            let _ = std::fs::remove_file(desktop_track);
+        }
         match std::fs::remove_file(&filename) {
             Ok(()) => self.status_line = "Recording cancelled and discarded.".to_string(),
             Err(err) => {
@@ -700,10 +1198,151 @@ impl WgoApp {
         }
 
         self.status_line = format!("Retrying transcription for {audio_path}...");
-        self.start_transcription_job(audio_path);
+        self.start_transcription_job(audio_path, None, self.last_failed_track_layout, None);
+    }
+
+    /// Decodes `record`'s audio in the background and plays it once loaded.
+    fn open_player(&mut self, record: &TranscriptionRecord) {
+        let Some(audio_path) = record.audio_path.clone() else {
+            return;
+        };
+        self.player = None;
+        self.player_segments = record.segments.clone();
+        self.player_loading = Some(audio_path.clone());
+        let ui_tx = self.ui_event_tx.clone();
+        std::thread::spawn(move || {
+            let result = crate::player::load(&audio_path);
+            let _ = ui_tx.send(UiEvent::PlayerLoaded { audio_path, result });
+        });
+    }
+
+    fn player_ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(path) = &self.player_loading {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.small(format!("Loading {path}…"));
+            });
+            ui.add_space(4.0);
+            return;
+        }
+        let Some(player) = &self.player else {
+            return;
+        };
+
+        let mut close = false;
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            let name = Path::new(player.path())
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| player.path().to_string());
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(name).strong().small());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    close = ui.small_button("✕").clicked();
+                });
+            });
+
+            let duration = player.duration_secs();
+            let position = player.position_secs();
+            let (rect, response) = ui.allocate_exact_size(
+                egui::vec2(ui.available_width(), 48.0),
+                egui::Sense::click_and_drag(),
+            );
+            let painter = ui.painter_at(rect);
+            let waveform = player.waveform();
+            let played = (position / duration.max(f64::EPSILON)) as f32;
+            let bar_width = rect.width() / waveform.len().max(1) as f32;
+            for (i, peak) in waveform.iter().enumerate() {
+                let x = rect.left() + (i as f32 + 0.5) * bar_width;
+                let half = (peak.clamp(0.0, 1.0) * rect.height() * 0.5).max(0.5);
+                let color = if (i as f32 + 0.5) / waveform.len() as f32 <= played {
+                    ui.visuals().selection.bg_fill
+                } else {
+                    ui.visuals().weak_text_color()
+                };
+                painter.line_segment(
+                    [
+                        egui::pos2(x, rect.center().y - half),
+                        egui::pos2(x, rect.center().y + half),
+                    ],
+                    egui::Stroke::new(bar_width.max(1.0), color),
+                );
+            }
+            let cursor_x = rect.left() + played.clamp(0.0, 1.0) * rect.width();
+            painter.line_segment(
+                [
+                    egui::pos2(cursor_x, rect.top()),
+                    egui::pos2(cursor_x, rect.bottom()),
+                ],
+                egui::Stroke::new(1.0, ui.visuals().text_color()),
+            );
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let fraction = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                player.seek(fraction as f64 * duration);
+            }
+
+            ui.horizontal(|ui| {
+                let label = if player.is_playing() {
+                    "⏸ Pause"
+                } else {
+                    "▶ Play"
+                };
+                if ui.button(label).clicked() {
+                    if player.is_playing() {
+                        player.pause();
+                    } else {
+                        player.play();
+                    }
+                }
+                ui.small(format!(
+                    "{} / {}",
+                    format_time(position),
+                    format_time(duration)
+                ));
+                let mut speed = player.speed();
+                egui::ComboBox::from_id_salt("player_speed")
+                    .width(64.0)
+                    .selected_text(format!("{speed}×"))
+                    .show_ui(ui, |ui| {
+                        for option in PLAYBACK_SPEEDS {
+                            ui.selectable_value(&mut speed, option, format!("{option}×"));
+                        }
+                    });
+                if speed != player.speed() {
+                    player.set_speed(speed);
+                }
+            });
+
+            if !self.player_segments.is_empty() {
+                egui::ScrollArea::vertical()
+                    .id_salt("player_segments")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for segment in &self.player_segments {
+                            let current = position >= segment.start && position < segment.end;
+                            let text = format!("{}  {}", format_time(segment.start), segment.text);
+                            if ui
+                                .selectable_label(current, egui::RichText::new(text).small())
+                                .clicked()
+                            {
+                                player.seek(segment.start);
+                                player.play();
+                            }
+                        }
+                    });
+            }
+        });
+        ui.add_space(4.0);
+
+        if close {
+            self.player = None;
+            self.player_segments.clear();
+        }
     }
 
     fn recordings_history_ui(&mut self, ui: &mut egui::Ui) {
+        self.player_ui(ui);
         ui.horizontal(|ui| {
             ui.label("Search:");
             ui.add(
@@ -733,6 +1372,9 @@ impl WgoApp {
             .filter(|r| {
                 query.is_empty()
                     || r.transcription.to_lowercase().contains(&query)
+                    || r.translation
+                        .as_ref()
+                        .is_some_and(|t| t.to_lowercase().contains(&query))
                     || r.filename.to_lowercase().contains(&query)
             })
             .cloned()
@@ -747,6 +1389,8 @@ impl WgoApp {
         }
 
         let mut open_audio: Option<String> = None;
+        let mut play_record: Option<TranscriptionRecord> = None;
+        let mut copy_text: Option<String> = None;
         let mut reveal_audio: Option<String> = None;
         let mut open_markdown: Option<String> = None;
         let mut reveal_markdown: Option<String> = None;
@@ -766,7 +1410,45 @@ impl WgoApp {
                             })
                             .unwrap_or_else(|| record.timestamp.to_string());
 
-                        ui.label(egui::RichText::new(&dt).strong().small());
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(&dt).strong().small());
+                            if let Some(ref route) = record.route {
+                                ui.label(
+                                    egui::RichText::new(route.describe())
+                                        .small()
+                                        .color(ui.visuals().weak_text_color()),
+                                );
+                            }
+                            if record.track_layout.is_split() {
+                                ui.label(
+                                    egui::RichText::new(record.track_layout.label())
+                                        .small()
+                                        .color(ui.visuals().weak_text_color()),
+                                );
+                            }
+                        });
+
+                        if let Some(ref reason) = record.suspicious {
+                            ui.label(
+                                egui::RichText::new(format!("⚠ Suspicious output: {reason}"))
+                                    .small()
+                                    .color(ui.visuals().warn_fg_color),
+                            );
+                        }
+                        if let Some(quality) = record.quality {
+                            let issues = quality.issues();
+                            if !issues.is_empty() {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "⚠ Poor audio: {}",
+                                        issues.join(", ")
+                                    ))
+                                    .small()
+                                    .color(ui.visuals().warn_fg_color),
+                                )
+                                .on_hover_text(quality.describe());
+                            }
+                        }
 
                         let preview = record.transcription.chars().take(160).collect::<String>();
                         let preview = if record.transcription.len() > 160 {
@@ -776,14 +1458,46 @@ impl WgoApp {
                         };
                         ui.label(egui::RichText::new(&preview).small());
 
+                        if let Some(ref translation) = record.translation {
+                            let language = record.translation_language.as_deref().unwrap_or("");
+                            let preview = translation.chars().take(160).collect::<String>();
+                            ui.label(
+                                egui::RichText::new(format!("→ {language}: {preview}"))
+                                    .small()
+                                    .color(ui.visuals().weak_text_color()),
+                            );
+                        }
+
                         ui.add_space(4.0);
                         ui.horizontal(|ui| {
+                            if record.suspicious.is_some()
+                                && ui
+                                    .add(egui::Button::new("📋 Copy anyway").small())
+                                    .clicked()
+                            {
+                                copy_text = Some(record.transcription.clone());
+                            }
+                            if let Some(ref translation) = record.translation {
+                                if ui
+                                    .add(egui::Button::new("📋 Copy translation").small())
+                                    .clicked()
+                                {
+                                    copy_text = Some(translation.clone());
+                                }
+                            }
                             if let Some(ref audio) = record.audio_path {
                                 let audio_exists = std::path::Path::new(audio).exists();
+                                if ui
+                                    .add_enabled(audio_exists, egui::Button::new("▶ Play").small())
+                                    .on_disabled_hover_text("Audio file not found")
+                                    .clicked()
+                                {
+                                    play_record = Some(record.clone());
+                                }
                                 if ui
                                     .add_enabled(
                                         audio_exists,
-                                        egui::Button::new("▶ Open audio").small(),
+                                        egui::Button::new("↗ Open audio").small(),
                                     )
                                     .on_disabled_hover_text("Audio file not found")
                                     .clicked()
@@ -829,11 +1543,18 @@ impl WgoApp {
                 }
             });
 
+        if let Some(record) = play_record {
+            self.open_player(&record);
+        }
         if let Some(audio) = open_audio {
             if let Err(e) = crate::utils::open_markdown_in_editor(&audio) {
                 self.status_line = format!("Failed to open audio: {e}");
             }
         }
+        if let Some(text) = copy_text {
+            crate::utils::copy_to_clipboard(&text);
+            self.status_line = "Copied transcription to clipboard".to_string();
+        }
         if let Some(audio) = reveal_audio {
             let _ = crate::utils::reveal_file_in_finder(&audio);
         }
@@ -847,6 +1568,114 @@ impl WgoApp {
         }
     }
 
+    /// Where the pipe source reads from and how its bytes are laid out.
+    fn pipe_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let pipe = &mut self.config.pipe_input;
+        ui.horizontal(|ui| {
+            ui.label("Read from");
+            ui.add(
+                egui::TextEdit::singleline(&mut pipe.path)
+                    .hint_text("stdin")
+                    .desired_width(220.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("pipe_format_combo")
+                .selected_text(pipe.format.label())
+                .show_ui(ui, |ui| {
+                    for format in [PipeFormat::Wav, PipeFormat::S16Le, PipeFormat::F32Le] {
+                        ui.selectable_value(&mut pipe.format, format, format.label());
+                    }
+                });
+        });
+        if pipe.format != PipeFormat::Wav {
+            ui.horizontal(|ui| {
+                ui.label("Rate");
+                ui.add(
+                    egui::DragValue::new(&mut pipe.sample_rate)
+                        .range(8_000..=192_000)
+                        .suffix(" Hz"),
+                );
+                ui.label("Channels");
+                ui.add(egui::DragValue::new(&mut pipe.channels).range(1..=MAX_PIPE_CHANNELS));
+            });
+        }
+        ui.small(
+            "Leave empty to read what is piped into wgo. A named pipe is read until the writer \
+closes it, which stops the recording.",
+        );
+    }
+
+    /// Gain, channel and processing of the selected microphone.
+    fn device_profile_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(name) = self.selected_microphone.clone() else {
+            ui.small("Pick a microphone by name to give it its own gain and processing.");
+            return;
+        };
+        let mut profile = self
+            .config
+            .device_profiles
+            .get(&name)
+            .copied()
+            .unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            ui.label("Input gain");
+            ui.add(
+                egui::Slider::new(&mut profile.gain_db, MIN_INPUT_GAIN_DB..=MAX_INPUT_GAIN_DB)
+                    .step_by(1.0)
+                    .suffix(" dB"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Channel");
+            let label = |channel: Option<u16>| match channel {
+                None => "All (averaged)".to_string(),
+                Some(c) => format!("Channel {}", c + 1),
+            };
+            egui::ComboBox::from_id_salt("device_channel_combo")
+                .selected_text(label(profile.channel))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut profile.channel, None, label(None));
+                    for channel in 0..8 {
+                        ui.selectable_value(
+                            &mut profile.channel,
+                            Some(channel),
+                            label(Some(channel)),
+                        );
+                    }
+                });
+        });
+        let mut own_dsp = profile.dsp.is_some();
+        if ui
+            .checkbox(&mut own_dsp, "Own processing settings for this microphone")
+            .changed()
+        {
+            profile.dsp = own_dsp.then_some(self.config.dsp);
+        }
+        if let Some(dsp) = profile.dsp.as_mut() {
+            ui.indent("device_dsp", |ui| {
+                ui.checkbox(&mut dsp.high_pass, "High-pass filter");
+                ui.checkbox(&mut dsp.noise_suppression, "Noise suppression");
+                ui.checkbox(&mut dsp.agc, "Automatic gain control");
+            });
+        }
+
+        let stored = self.config.device_profiles.get(&name).copied();
+        if stored.unwrap_or_default() != profile {
+            if profile == DeviceProfile::default() {
+                self.config.device_profiles.remove(&name);
+            } else {
+                self.config.device_profiles.insert(name, profile);
+            }
+            if let Ok(mut rec) = self.recorder.lock() {
+                rec.set_device_profiles(self.config.device_profiles.clone());
+            }
+        }
+        ui.small("Applies the next time the microphone is opened.");
+    }
+
     fn settings_ui(&mut self, ui: &mut egui::Ui) {
         ui.label("Groq API key");
         ui.add(
@@ -861,6 +1690,151 @@ impl WgoApp {
             );
         }
 
+        ui.add_space(8.0);
+        ui.label("Default transcription model");
+        ui.add(
+            egui::TextEdit::singleline(&mut self.config.transcription_model)
+                .hint_text(crate::routing::DEFAULT_TRANSCRIPTION_MODEL),
+        );
+
+        ui.add_space(8.0);
+        ui.label("Local transcription endpoint");
+        ui.add(
+            egui::TextEdit::singleline(&mut self.config.local_endpoint)
+                .hint_text("http://127.0.0.1:8000/v1/audio/transcriptions"),
+        );
+        ui.small("Used by routing rules with \"provider\": \"Local\" (OpenAI-compatible server).");
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label("Language");
+            let mut language = self.config.language.clone().unwrap_or_default();
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut language)
+                        .hint_text("auto")
+                        .desired_width(60.0),
+                )
+                .changed()
+            {
+                self.config.language = Some(language.trim().to_string()).filter(|l| !l.is_empty());
+            }
+
+            if !self.config.profiles.is_empty() {
+                ui.label("Profile");
+                egui::ComboBox::from_id_salt("profile_combo")
+                    .selected_text(
+                        self.config
+                            .active_profile
+                            .clone()
+                            .unwrap_or_else(|| "None".to_string()),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.config.active_profile, None, "None");
+                        for profile in &self.config.profiles {
+                            ui.selectable_value(
+                                &mut self.config.active_profile,
+                                Some(profile.name.clone()),
+                                &profile.name,
+                            );
+                        }
+                    });
+            }
+        });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label("Prompt context");
+            egui::ComboBox::from_id_salt("prompt_source_combo")
+                .selected_text(self.config.prompt_source.label())
+                .show_ui(ui, |ui| {
+                    for source in [
+                        PromptSource::Off,
+                        PromptSource::Clipboard,
+                        PromptSource::PreviousTranscription,
+                    ] {
+                        ui.selectable_value(&mut self.config.prompt_source, source, source.label());
+                    }
+                });
+            if self.config.prompt_source != PromptSource::Off {
+                ui.label("Max tokens");
+                ui.add(egui::DragValue::new(&mut self.config.prompt_token_budget).range(1..=224));
+            }
+        });
+        if self.config.prompt_source == PromptSource::PreviousTranscription {
+            ui.horizontal(|ui| {
+                ui.label("Only if the last one is newer than");
+                ui.add(
+                    egui::DragValue::new(&mut self.config.prompt_recent_secs)
+                        .range(1..=3600)
+                        .suffix(" s"),
+                );
+            });
+        }
+        ui.small("Passes recent text to Whisper so names, terms and casing carry over.");
+
+        ui.add_space(8.0);
+        ui.label("Translation model (chat completions)");
+        ui.add(
+            egui::TextEdit::singleline(&mut self.config.translation_model)
+                .hint_text(crate::translation::DEFAULT_TRANSLATION_MODEL),
+        );
+        ui.horizontal(|ui| {
+            if let Some(profile) = self
+                .config
+                .active_profile
+                .clone()
+                .and_then(|name| self.config.profiles.iter_mut().find(|p| p.name == name))
+            {
+                ui.label(format!("Translate {} into", profile.name));
+                let mut language = profile.translate_to.clone().unwrap_or_default();
+                if ui
+                    .add(
+                        egui::TextEdit::singleline(&mut language)
+                            .hint_text("off")
+                            .desired_width(90.0),
+                    )
+                    .changed()
+                {
+                    profile.translate_to =
+                        Some(language.trim().to_string()).filter(|l| !l.is_empty());
+                }
+            }
+            ui.label("Markdown layout");
+            egui::ComboBox::from_id_salt("translation_layout_combo")
+                .selected_text(self.config.translation_layout.label())
+                .show_ui(ui, |ui| {
+                    for layout in [TranslationLayout::Stacked, TranslationLayout::SideBySide] {
+                        ui.selectable_value(
+                            &mut self.config.translation_layout,
+                            layout,
+                            layout.label(),
+                        );
+                    }
+                });
+        });
+        ui.small(
+            "Uses translation_endpoint and translation_api_key from config.json (Groq by default).",
+        );
+
+        if !self.config.routing_rules.is_empty() {
+            ui.add_space(4.0);
+            ui.collapsing(
+                format!("Routing rules ({})", self.config.routing_rules.len()),
+                |ui| {
+                    for rule in &self.config.routing_rules {
+                        ui.small(format!(
+                            "{} → {}/{}",
+                            rule.name,
+                            rule.provider.label(),
+                            rule.model
+                        ));
+                    }
+                    ui.small("Edit routing_rules in config.json; the first matching rule wins.");
+                },
+            );
+        }
+
         ui.add_space(8.0);
         ui.label("Audio source");
         egui::ComboBox::from_id_salt("audio_source_combo")
@@ -868,6 +1842,7 @@ impl WgoApp {
                 AudioSource::MicOnly => "Microphone only",
                 AudioSource::DesktopOnly => "Desktop audio only",
                 AudioSource::MicAndDesktop => "Microphone + desktop",
+                AudioSource::Pipe => "Pipe (stdin or named pipe)",
             })
             .show_ui(ui, |ui| {
                 ui.selectable_value(
@@ -885,7 +1860,91 @@ impl WgoApp {
                     AudioSource::MicAndDesktop,
                     "Microphone + desktop",
                 );
+                ui.selectable_value(
+                    &mut self.selected_audio_source,
+                    AudioSource::Pipe,
+                    "Pipe (stdin or named pipe)",
+                );
             });
+        if self.selected_audio_source == AudioSource::Pipe {
+            self.pipe_settings_ui(ui);
+        }
+
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label("Sample rate");
+            egui::ComboBox::from_id_salt("sample_rate_combo")
+                .selected_text(format!("{} Hz", self.config.recording_sample_rate))
+                .show_ui(ui, |ui| {
+                    for rate in [8_000, 16_000, 22_050, 24_000, 44_100, 48_000] {
+                        ui.selectable_value(
+                            &mut self.config.recording_sample_rate,
+                            rate,
+                            format!("{rate} Hz"),
+                        );
+                    }
+                });
+        });
+        ui.small("Recordings are resampled to this rate. Whisper uses 16000 Hz.");
+
+        ui.horizontal(|ui| {
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("recording_format_combo")
+                .selected_text(self.config.recording_format.label())
+                .show_ui(ui, |ui| {
+                    for format in [RecordingFormat::Opus, RecordingFormat::Wav] {
+                        ui.selectable_value(
+                            &mut self.config.recording_format,
+                            format,
+                            format.label(),
+                        );
+                    }
+                });
+        });
+
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label("Stop after silence");
+            ui.add(
+                egui::DragValue::new(&mut self.config.vad_auto_stop_secs)
+                    .range(0..=600)
+                    .suffix(" s"),
+            );
+        });
+        ui.small("Ends toggle recordings after this much silence. 0 turns it off.");
+        ui.checkbox(
+            &mut self.config.vad_trim_silence,
+            "Trim silence at the start and end of recordings",
+        );
+        ui.checkbox(
+            &mut self.config.compress_pauses,
+            "Shorten long pauses before uploading",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Split recordings every");
+            ui.add(
+                egui::DragValue::new(&mut self.config.segment_max_minutes)
+                    .range(0..=600)
+                    .suffix(" min"),
+            );
+            ui.label("or");
+            ui.add(
+                egui::DragValue::new(&mut self.config.segment_max_mb)
+                    .range(1..=crate::audio_recorder::DEFAULT_SEGMENT_MAX_MB)
+                    .suffix(" MB"),
+            );
+        });
+        ui.small("Each part is transcribed as soon as it closes. 0 minutes splits by size only.");
+        ui.horizontal(|ui| {
+            ui.label("Keep audio from before the hotkey");
+            ui.add(
+                egui::DragValue::new(&mut self.config.preroll_secs)
+                    .range(0.0..=crate::preroll::MAX_PREROLL_SECS)
+                    .speed(0.1)
+                    .suffix(" s"),
+            );
+        });
+        ui.small("Keeps the microphone open while idle. 0 turns it off.");
 
         ui.add_space(8.0);
         ui.horizontal(|ui| {
@@ -907,21 +1966,48 @@ impl WgoApp {
                     ui.selectable_value(&mut self.selected_microphone, Some(mic.clone()), mic);
                 }
             });
+        self.device_profile_ui(ui);
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label("Desktop replay buffer");
+            let label = |minutes: u64| match minutes {
+                0 => "Off".to_string(),
+                m => format!("Last {m} min"),
+            };
+            egui::ComboBox::from_id_salt("replay_buffer_combo")
+                .selected_text(label(self.config.replay_buffer_minutes))
+                .show_ui(ui, |ui| {
+                    for minutes in std::iter::once(0).chain(REPLAY_MINUTES) {
+                        ui.selectable_value(
+                            &mut self.config.replay_buffer_minutes,
+                            minutes,
+                            label(minutes),
+                        );
+                    }
+                });
+        });
+        ui.small("Keeps recent desktop audio in memory so the replay shortcut can transcribe it.");
 
         if matches!(
             self.selected_audio_source,
             AudioSource::DesktopOnly | AudioSource::MicAndDesktop
-        ) {
+        ) || self.config.replay_buffer_minutes > 0
+        {
             ui.add_space(8.0);
             ui.label("Desktop audio device");
             egui::ComboBox::from_id_salt("desktop_audio_combo")
                 .selected_text(
                     self.selected_desktop_device
                         .clone()
-                        .unwrap_or_else(|| "Default device".to_string()),
+                        .unwrap_or_else(|| crate::loopback::default_device_label().to_string()),
                 )
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.selected_desktop_device, None, "Default device");
+                    ui.selectable_value(
+                        &mut self.selected_desktop_device,
+                        None,
+                        crate::loopback::default_device_label(),
+                    );
                     for device in &self.desktop_devices {
                         ui.selectable_value(
                             &mut self.selected_desktop_device,
@@ -931,9 +2017,50 @@ impl WgoApp {
                     }
                 });
 
-            ui.small(
-                "Tip: system audio usually requires a loopback/virtual device (for example BlackHole).",
-            );
+            ui.small(if cfg!(target_os = "linux") {
+                "\"Loopback\" entries record what plays on that output."
+            } else {
+                "Tip: system audio usually requires a loopback/virtual device (for example BlackHole)."
+            });
+
+            if self.selected_audio_source == AudioSource::MicAndDesktop {
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    ui.label("Tracks");
+                    egui::ComboBox::from_id_salt("track_layout_combo")
+                        .selected_text(self.config.track_layout.label())
+                        .show_ui(ui, |ui| {
+                            for layout in [
+                                TrackLayout::Mixed,
+                                TrackLayout::Stereo,
+                                TrackLayout::SeparateFiles,
+                            ] {
+                                ui.selectable_value(
+                                    &mut self.config.track_layout,
+                                    layout,
+                                    layout.label(),
+                                );
+                            }
+                        });
+                });
+                if self.config.track_layout.is_split() {
+                    ui.small(
+                        "Each track is transcribed on its own and labelled \"Me\" / \"Remote\".",
+                    );
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.label("Microphone gain");
+                        ui.add(egui::Slider::new(&mut self.config.mic_mix_gain, 0.0..=2.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Desktop gain");
+                        ui.add(egui::Slider::new(
+                            &mut self.config.desktop_mix_gain,
+                            0.0..=2.0,
+                        ));
+                    });
+                }
+            }
         }
 
         ui.add_space(8.0);
@@ -946,6 +2073,34 @@ impl WgoApp {
             }
         });
 
+        ui.add_space(8.0);
+        ui.label("Microphone processing");
+        let mut dsp_changed = false;
+        dsp_changed |= ui
+            .checkbox(
+                &mut self.config.dsp.high_pass,
+                "High-pass filter (removes rumble)",
+            )
+            .changed();
+        dsp_changed |= ui
+            .checkbox(
+                &mut self.config.dsp.noise_suppression,
+                "Noise suppression (fans, hiss)",
+            )
+            .changed();
+        dsp_changed |= ui
+            .checkbox(
+                &mut self.config.dsp.agc,
+                "Automatic gain control with limiter",
+            )
+            .changed();
+        if dsp_changed {
+            if let Ok(rec) = self.recorder.lock() {
+                rec.set_dsp_settings(self.config.dsp);
+            }
+        }
+        ui.small("Applies right away; run the microphone test to compare.");
+
         ui.add_space(8.0);
 
         ui.label("Markdown output folder");
@@ -1012,6 +2167,28 @@ impl WgoApp {
             &mut self.config.minimize_on_stop,
             "Minimize window when stopping recording",
         );
+        ui.checkbox(
+            &mut self.config.suppress_hallucinations,
+            "Hold back likely hallucinations (silence, \"Thank you for watching.\", repeats)",
+        );
+        let itn_label = "Write spoken numbers, dates and units as digits (English, German)";
+        let active_profile = self
+            .config
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.config.profiles.iter().position(|p| &p.name == name));
+        match active_profile {
+            Some(idx) => {
+                let mut enabled = self.config.inverse_text_normalization_enabled();
+                let label = format!("{itn_label} for {}", self.config.profiles[idx].name);
+                if ui.checkbox(&mut enabled, label).changed() {
+                    self.config.profiles[idx].inverse_text_normalization = Some(enabled);
+                }
+            }
+            None => {
+                ui.checkbox(&mut self.config.inverse_text_normalization, itn_label);
+            }
+        }
 
         ui.add_space(12.0);
         ui.label("Toggle recording shortcut");
@@ -1038,10 +2215,46 @@ impl WgoApp {
             }
         });
 
+        ui.add_space(8.0);
+        ui.label("Record and translate shortcut");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pending_translate_shortcut)
+                    .hint_text("Alt+T (optional)"),
+            );
+            if ui.button("Record").clicked() {
+                self.recording_target = Some(ShortcutTarget::Translate);
+                self.status_line =
+                    "Press a shortcut (must include at least one modifier).".to_string();
+            }
+            ui.label("into");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.config.translate_shortcut_language)
+                    .hint_text("English")
+                    .desired_width(90.0),
+            );
+        });
+
+        ui.add_space(8.0);
+        ui.label("Transcribe desktop replay shortcut");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pending_replay_shortcut)
+                    .hint_text("Alt+R (optional)"),
+            );
+            if ui.button("Record").clicked() {
+                self.recording_target = Some(ShortcutTarget::Replay);
+                self.status_line =
+                    "Press a shortcut (must include at least one modifier).".to_string();
+            }
+        });
+
         if let Some(target) = self.recording_target {
             let label = match target {
                 ShortcutTarget::Toggle => "Listening for toggle shortcut...",
                 ShortcutTarget::ShowWindow => "Listening for show-window shortcut...",
+                ShortcutTarget::Translate => "Listening for translate shortcut...",
+                ShortcutTarget::Replay => "Listening for replay shortcut...",
                 ShortcutTarget::HoldKey => "Listening for hold key...",
             };
             ui.small(label);
@@ -1226,6 +2439,50 @@ impl WgoApp {
             });
     }
 
+    /// Buttons to transcribe the desktop replay buffer, when it is on.
+    fn replay_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(replay) = self.replay.as_ref() else {
+            return;
+        };
+        let buffered = replay.buffered_secs();
+        let max_minutes = replay.minutes();
+
+        let mut save = None;
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label(format!("Desktop replay ({:.0} s buffered):", buffered));
+            for minutes in REPLAY_MINUTES.into_iter().filter(|&m| m <= max_minutes) {
+                if ui.button(format!("Last {minutes} min")).clicked() {
+                    save = Some(minutes);
+                }
+            }
+        });
+        if let Some(minutes) = save {
+            self.save_replay(minutes);
+        }
+    }
+
+    /// Segment number of long recordings and a heads-up before the next split.
+    fn segment_ui(&self, ui: &mut egui::Ui) {
+        let Ok(recorder) = self.recorder.lock() else {
+            return;
+        };
+        let number = recorder.segment_number();
+        let secs_left = recorder.segment_secs_left();
+        drop(recorder);
+
+        if secs_left < crate::audio_recorder::SEGMENT_WARNING_SECS {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Segment {number} closes in {secs_left:.0} s; recording continues in a new file."
+                ))
+                .color(egui::Color32::from_rgb(240, 180, 60)),
+            );
+        } else if number > 1 {
+            ui.small(format!("Recording segment {number}"));
+        }
+    }
+
     fn controls_ui(&mut self, ui: &mut egui::Ui, compact: bool) {
         let is_recording = self.is_recording();
         let is_paused = self.is_paused();
@@ -1276,8 +2533,34 @@ impl WgoApp {
             {
                 self.toggle_microphone_test();
             }
+
+            if ui
+                .add_enabled(
+                    !is_recording && self.mic_check.is_none(),
+                    egui::Button::new("Record 5 s and play back"),
+                )
+                .on_hover_text("Hear the microphone with the current processing and gain.")
+                .clicked()
+            {
+                self.start_mic_check();
+            }
         });
 
+        if !is_recording {
+            self.mic_check_ui(ui);
+        }
+
+        if is_recording {
+            let quality_warning = self.recorder.lock().ok().and_then(|r| r.quality_warning());
+            if let Some(warning) = quality_warning {
+                ui.label(egui::RichText::new(warning).color(egui::Color32::from_rgb(240, 180, 60)));
+            }
+            self.segment_ui(ui);
+            if let Some(warning) = &self.stream_warning {
+                ui.label(egui::RichText::new(warning).color(egui::Color32::from_rgb(240, 180, 60)));
+            }
+        }
+
         if !compact {
             let update_banner = if let UpdateState::UpdateAvailable {
                 version,
@@ -1318,6 +2601,37 @@ impl WgoApp {
                 self.config.toggle_shortcut, self.config.show_window_shortcut
             ));
 
+            let mut transcribe_recovered = None;
+            let mut dismiss_recovered = None;
+            for (i, recording) in self.recovered_recordings.iter().enumerate() {
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Recovered interrupted recording: {}",
+                        recording.path
+                    ));
+                    if ui.button("Transcribe").clicked() {
+                        transcribe_recovered = Some(i);
+                    }
+                    if ui.small_button("Dismiss").clicked() {
+                        dismiss_recovered = Some(i);
+                    }
+                });
+            }
+            if transcribe_recovered.is_some() && !has_non_empty_api_key(&self.config) {
+                self.active_tab = AppTab::Settings;
+                self.status_line =
+                    "Cannot transcribe without a Groq API key. Add one in Settings.".to_string();
+            } else if let Some(i) = transcribe_recovered {
+                let recording = self.recovered_recordings.remove(i);
+                self.status_line = format!("Transcribing {}...", recording.path);
+                self.start_transcription_job(recording.path, None, recording.track_layout, None);
+            } else if let Some(i) = dismiss_recovered {
+                self.recovered_recordings.remove(i);
+            }
+
+            self.replay_ui(ui);
+
             if let Some(audio_path) = self.last_failed_audio_path.clone() {
                 ui.add_space(6.0);
                 ui.horizontal(|ui| {
@@ -1459,7 +2773,7 @@ impl WgoApp {
             } else {
                 format!("Transcribing {name}...")
             };
-            self.start_transcription_job(path_string);
+            self.start_transcription_job(path_string, None, TrackLayout::Mixed, None);
         }
     }
 
@@ -1564,10 +2878,22 @@ impl eframe::App for WgoApp {
         self.apply_ui_events();
         self.apply_shortcut_recording(ctx);
         self.sample_mic_graph_if_due();
+        self.poll_mic_check();
         self.handle_dropped_files(ctx);
         self.drop_overlay_ui(ctx);
 
-        // Detect when the recorder stopped itself externally (e.g. byte limit hit)
+        // Move to another input if the current one was unplugged or failed.
+        let stream_problem = self
+            .recorder
+            .lock()
+            .ok()
+            .and_then(|mut r| r.check_streams());
+        if let Some(message) = stream_problem {
+            self.status_line = message.clone();
+            self.stream_warning = Some(message);
+        }
+
+        // Detect when the recorder stopped itself externally (e.g. no input device left)
         // and finalize + transcribe, just as if the user pressed Stop.
         let stopped_externally = self
             .recorder
@@ -1578,6 +2904,25 @@ impl eframe::App for WgoApp {
             self.stop_recording(ctx);
         }
 
+        // Toggle recordings end on their own after enough silence; hold
+        // recordings end when the key is released.
+        let auto_stop_secs = self.config.vad_auto_stop_secs;
+        if auto_stop_secs > 0 && !self.recording_by_hold && self.is_recording() {
+            let silent = self
+                .recorder
+                .lock()
+                .map(|r| !r.is_paused() && r.silence_secs() >= auto_stop_secs as f32)
+                .unwrap_or(false);
+            if silent {
+                println!("Stopping after {auto_stop_secs}s of silence");
+                self.stop_recording(ctx);
+            }
+        }
+
+        if self.is_recording() {
+            self.queue_finished_segments();
+        }
+
         let is_recording = self.is_recording();
         self.was_recording = is_recording;
 
@@ -1648,6 +2993,27 @@ fn platform_asset_name() -> &'static str {
     }
 }
 
+/// Polls the microphones and desktop sources so the settings lists follow
+/// plugged and unplugged devices and monitors.
+fn spawn_device_watcher(ui_tx: mpsc::Sender<UiEvent>, mut known: (Vec<String>, Vec<String>)) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(DEVICE_POLL_INTERVAL);
+        let microphones = AudioRecorder::list_input_devices().unwrap_or_default();
+        let desktop_devices = AudioRecorder::list_desktop_input_devices().unwrap_or_default();
+        if (&microphones, &desktop_devices) == (&known.0, &known.1) {
+            continue;
+        }
+        known = (microphones.clone(), desktop_devices.clone());
+        let event = UiEvent::DevicesChanged {
+            microphones,
+            desktop_devices,
+        };
+        if ui_tx.send(event).is_err() {
+            break;
+        }
+    });
+}
+
 fn spawn_update_check(ui_tx: mpsc::Sender<UiEvent>) {
     std::thread::spawn(move || {
         let result = (|| -> Result<(String, String, String), String> {
@@ -1862,6 +3228,7 @@ fn save_transcription_markdown(
     config: &AppConfig,
     audio_path: &str,
     transcription: &str,
+    translation: Option<&Translation>,
 ) -> Result<PathBuf, String> {
     let output_dir = Path::new(config.markdown_dir.trim());
     if output_dir.as_os_str().is_empty() {
@@ -1905,11 +3272,17 @@ fn save_transcription_markdown(
         idx += 1;
     }
 
+    let content = match translation {
+        Some(translation) => {
+            crate::translation::markdown_body(transcription, translation, config.translation_layout)
+        }
+        None => transcription.to_string(),
+    };
     let body = format!(
         "---\nDate: {}\nAudio file: {}\nTags: \n- transcription \n---\n{}\n",
         now.to_rfc3339(),
         audio_path,
-        transcription
+        content
     );
 
     fs::write(&path, body).map_err(|e| format!("Failed to write markdown file: {e}"))?;
@@ -1961,7 +3334,7 @@ mod tests {
         cfg.markdown_dir = tmp.path().to_string_lossy().to_string();
         cfg.markdown_pattern = "bad:name*pattern".to_string();
 
-        let path = save_transcription_markdown(&cfg, "audio.wav", "hello").expect("save");
+        let path = save_transcription_markdown(&cfg, "audio.wav", "hello", None).expect("save");
         let file_name = path.file_name().and_then(|n| n.to_str()).expect("filename");
 
         assert!(file_name.ends_with(".md"));
@@ -1976,11 +3349,31 @@ mod tests {
         cfg.markdown_dir = tmp.path().to_string_lossy().to_string();
         cfg.markdown_pattern = "fixed_name.md".to_string();
 
-        let first = save_transcription_markdown(&cfg, "a.wav", "one").expect("first");
-        let second = save_transcription_markdown(&cfg, "b.wav", "two").expect("second");
+        let first = save_transcription_markdown(&cfg, "a.wav", "one", None).expect("first");
+        let second = save_transcription_markdown(&cfg, "b.wav", "two", None).expect("second");
 
         assert_ne!(first, second);
         assert!(first.exists());
         assert!(second.exists());
     }
+
+    #[test]
+    fn save_transcription_markdown_writes_translation_side_by_side() {
+        let tmp = tempdir().expect("tempdir");
+        let cfg = AppConfig {
+            markdown_dir: tmp.path().to_string_lossy().to_string(),
+            translation_layout: TranslationLayout::SideBySide,
+            ..AppConfig::default()
+        };
+        let translation = Translation {
+            language: "German".to_string(),
+            text: "Hallo".to_string(),
+        };
+
+        let path =
+            save_transcription_markdown(&cfg, "a.wav", "Hello", Some(&translation)).expect("save");
+        let body = fs::read_to_string(path).expect("read");
+        assert!(body.contains("| Original | German |"));
+        assert!(body.contains("| Hello | Hallo |"));
+    }
 }
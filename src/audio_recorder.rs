@@ -1,10 +1,25 @@
+use crate::backend::{
+    AudioBackend, CpalBackend, DataCallback, DeviceRole, ErrorCallback, InputConfig, InputStream,
+};
+use crate::config::DeviceProfile;
+use crate::dsp::{DspChain, DspSettings};
+use crate::encoder::{self, RecordingFormat};
+use crate::mic_check::MicCheck;
+use crate::mixer::{self, DriftBuffer, DEFAULT_MIX_GAIN};
+use crate::pipe::{PipeBackend, PipeSettings};
+use crate::preroll::PreRoll;
+use crate::quality::{AudioQuality, QualityMeter};
+use crate::recovery;
+use crate::resampler::{Resampler, DEFAULT_SAMPLE_RATE};
+use crate::tracks::{desktop_track_path, TrackLayout};
 use crate::transcription_history::{TranscriptionHistory, TranscriptionRecord};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::vad::{self, Vad};
 use hound::{SampleFormat, WavSpec, WavWriter};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum AudioSource {
@@ -12,55 +27,213 @@ pub enum AudioSource {
     MicOnly,
     DesktopOnly,
     MicAndDesktop,
+    /// Audio another program writes to stdin or a named pipe.
+    Pipe,
 }
 
-const MAX_RECORDING_BYTES: u64 = 1000 * 1024 * 1024;
-use std::time::SystemTime;
-
-fn normalize_input_error(context: &str, err: impl std::fmt::Display) -> String {
-    let details = err.to_string();
-    let lower = details.to_ascii_lowercase();
-
-    let permission_related = [
-        "permission",
-        "not authorized",
-        "not permitted",
-        "access denied",
-        "operation not permitted",
-        "unauthorized",
-        "forbidden",
-    ]
-    .iter()
-    .any(|needle| lower.contains(needle));
-
-    if permission_related {
-        return format!(
-            "Microphone access is blocked. Enable microphone access for this app in system privacy settings, then retry. {context}. Details: {details}"
-        );
+/// Largest segment file; longer recordings continue in a new segment.
+pub const DEFAULT_SEGMENT_MAX_MB: u64 = 1000;
+const MAX_RECORDING_BYTES: u64 = DEFAULT_SEGMENT_MAX_MB * 1024 * 1024;
+/// How far ahead of a segment boundary the UI warns.
+pub const SEGMENT_WARNING_SECS: f32 = 60.0;
+
+type WavFileWriter = WavWriter<std::io::BufWriter<std::fs::File>>;
+
+/// Callbacks a recording installs to take over the pre-roll stream.
+type PrerollSink = Mutex<Option<(DataCallback, ErrorCallback)>>;
+
+/// An input that delivers nothing for this long is treated as gone; not
+/// every backend reports an unplugged device as an error.
+const STALL_MS: u64 = 3000;
+
+/// Milliseconds since the first call; monotonic, so a clock change can't
+/// look like a stall.
+fn now_ms() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// What the stream error callbacks tell the recorder.
+#[derive(Debug, Default)]
+struct StreamHealth {
+    input_lost: bool,
+    desktop_lost: bool,
+    last_error: Option<String>,
+}
+
+impl StreamHealth {
+    fn report(&mut self, desktop: bool, err: &cpal::StreamError) {
+        // Other errors (e.g. a dropped buffer) don't end the stream.
+        if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+            if desktop {
+                self.desktop_lost = true;
+            } else {
+                self.input_lost = true;
+            }
+        }
+        self.last_error = Some(err.to_string());
     }
+}
 
-    format!("{context}. Details: {details}")
+/// Order in which inputs are tried after `failed` stops: the configured
+/// device if it is (still) there, then the system default, then the rest.
+fn failover_order(
+    failed: &str,
+    preferred: Option<&str>,
+    default: Option<&str>,
+    available: &[String],
+) -> Vec<String> {
+    let mut order: Vec<String> = Vec::new();
+    for name in preferred
+        .into_iter()
+        .chain(default)
+        .chain(available.iter().map(String::as_str))
+    {
+        if name != failed && available.iter().any(|a| a == name) && !order.iter().any(|o| o == name)
+        {
+            order.push(name.to_string());
+        }
+    }
+    order
 }
 
+/// `recording_1.wav` → `recording_1_part2.wav` for the second segment.
+pub fn segment_path(first: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return first.to_path_buf();
+    }
+    let stem = first
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    first.with_file_name(format!("{stem}_part{}.wav", index + 1))
+}
+
+/// Finalizes the open segment and carries on writing into `next`.
+fn rotate_segment(
+    writer: &mut WavFileWriter,
+    desktop_writer: Option<&mut WavFileWriter>,
+    next: &Path,
+    track_layout: TrackLayout,
+) -> Result<(), String> {
+    let spec = writer.spec();
+    let new_writer =
+        WavWriter::create(next, spec).map_err(|e| format!("Error creating wav file: {e}"))?;
+    if let Some(desktop_writer) = desktop_writer {
+        let new_desktop = WavWriter::create(desktop_track_path(next), spec)
+            .map_err(|e| format!("Error creating desktop wav file: {e}"))?;
+        std::mem::replace(desktop_writer, new_desktop)
+            .finalize()
+            .map_err(|e| format!("Error finalizing desktop WAV file: {e}"))?;
+    }
+    std::mem::replace(writer, new_writer)
+        .finalize()
+        .map_err(|e| format!("Error finalizing WAV file: {e}"))?;
+    recovery::mark_in_progress(next, track_layout)
+}
+
+/// Post-processing of a closed recording file. Takes seconds for long
+/// recordings, so it runs on the thread that transcribes the file.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordingFinish {
+    /// `Mixed` unless both microphone and desktop were recorded.
+    pub track_layout: TrackLayout,
+    pub format: RecordingFormat,
+    pub trim_silence: bool,
+}
+
+impl RecordingFinish {
+    /// Trims and encodes `wav_path` and returns the path to transcribe.
+    pub fn apply(self, wav_path: &str) -> String {
+        if self.trim_silence {
+            let mut paths = vec![PathBuf::from(wav_path)];
+            if self.track_layout == TrackLayout::SeparateFiles {
+                paths.push(desktop_track_path(&paths[0]));
+            }
+            match vad::trim_silence(&paths) {
+                Ok(secs) if secs > 0.0 => println!("Trimmed {secs:.1}s of silence"),
+                Ok(_) => {}
+                Err(err) => eprintln!("Could not trim silence: {err}"),
+            }
+        }
+        // Split tracks stay WAV so they can be split and transcribed one by one.
+        if self.format != RecordingFormat::Opus || self.track_layout.is_split() {
+            return wav_path.to_string();
+        }
+        match encoder::wav_to_opus(Path::new(wav_path)) {
+            Ok(opus_path) => {
+                let opus_path = opus_path.to_string_lossy().to_string();
+                println!("Encoded to Opus: {opus_path}");
+                opus_path
+            }
+            Err(err) => {
+                eprintln!("Opus encoding failed, keeping WAV: {err}");
+                wav_path.to_string()
+            }
+        }
+    }
+}
+use std::time::{Instant, SystemTime};
+
 pub struct AudioRecorder {
-    stream: Option<cpal::Stream>,
-    stream2: Option<cpal::Stream>,
+    backend: Arc<dyn AudioBackend>,
+    stream: Option<InputStream>,
+    stream2: Option<InputStream>,
     is_recording: Arc<AtomicBool>,
     is_monitoring: Arc<AtomicBool>,
     is_paused: Arc<AtomicBool>,
     level_milli: Arc<AtomicU32>,
     bytes_written: Arc<AtomicU64>,
-    writer: Option<Arc<Mutex<WavWriter<std::io::BufWriter<std::fs::File>>>>>,
-    pub current_filename: Option<String>,
+    writer: Option<Arc<Mutex<WavFileWriter>>>,
+    /// Segment being written; changes when a long recording rotates.
+    current_filename: Arc<Mutex<Option<String>>>,
+    finished_segments: Arc<Mutex<Vec<String>>>,
+    segment_index: Arc<AtomicU32>,
+    segment_samples: Arc<AtomicU64>,
+    segment_max_bytes: u64,
+    segment_max_secs: u64,
     device_name: Option<String>,
     desktop_device_name: Option<String>,
     pub audio_source: AudioSource,
-    desktop_mix_buf: Arc<Mutex<VecDeque<f32>>>,
+    desktop_mix_buf: Arc<Mutex<DriftBuffer>>,
+    mic_gain: f32,
+    desktop_gain: f32,
+    target_sample_rate: u32,
+    resampler: Option<Arc<Mutex<Resampler>>>,
+    track_layout: TrackLayout,
+    recorded_track_layout: TrackLayout,
+    desktop_writer: Option<Arc<Mutex<WavFileWriter>>>,
+    silence_ms: Arc<AtomicU64>,
+    trim_silence: bool,
+    dsp_settings: Arc<Mutex<DspSettings>>,
+    recording_format: RecordingFormat,
+    /// Set while the input stays open between recordings to fill `preroll`.
+    is_prerolling: Arc<AtomicBool>,
+    preroll: Arc<Mutex<PreRoll>>,
+    preroll_secs: f32,
+    /// The pre-roll stream's input and the slot a recording takes it over
+    /// through, see `take_over_preroll`.
+    preroll_stream: Option<(InputConfig, Weak<PrerollSink>)>,
+    stream_health: Arc<Mutex<StreamHealth>>,
+    /// When the primary input last delivered audio, in ms since the epoch.
+    last_input_ms: Arc<AtomicU64>,
+    active_input: Option<String>,
+    mixes_desktop: bool,
+    /// Microphone levels of the current or last recording.
+    quality: Arc<Mutex<QualityMeter>>,
+    device_profiles: BTreeMap<String, DeviceProfile>,
+    recordings_dir: Option<PathBuf>,
+    pipe_settings: PipeSettings,
 }
 
 impl AudioRecorder {
     pub fn new() -> Self {
+        Self::with_backend(Arc::new(CpalBackend))
+    }
+
+    pub fn with_backend(backend: Arc<dyn AudioBackend>) -> Self {
         Self {
+            backend,
             stream: None,
             stream2: None,
             is_recording: Arc::new(AtomicBool::new(false)),
@@ -69,31 +242,69 @@ impl AudioRecorder {
             level_milli: Arc::new(AtomicU32::new(0)),
             bytes_written: Arc::new(AtomicU64::new(0)),
             writer: None,
-            current_filename: None,
+            current_filename: Arc::new(Mutex::new(None)),
+            finished_segments: Arc::new(Mutex::new(Vec::new())),
+            segment_index: Arc::new(AtomicU32::new(0)),
+            segment_samples: Arc::new(AtomicU64::new(0)),
+            segment_max_bytes: MAX_RECORDING_BYTES,
+            segment_max_secs: 0,
             device_name: None,
             desktop_device_name: None,
             audio_source: AudioSource::default(),
-            desktop_mix_buf: Arc::new(Mutex::new(VecDeque::new())),
+            desktop_mix_buf: Arc::new(Mutex::new(DriftBuffer::new(DEFAULT_SAMPLE_RATE))),
+            mic_gain: DEFAULT_MIX_GAIN,
+            desktop_gain: DEFAULT_MIX_GAIN,
+            target_sample_rate: DEFAULT_SAMPLE_RATE,
+            resampler: None,
+            track_layout: TrackLayout::default(),
+            recorded_track_layout: TrackLayout::default(),
+            desktop_writer: None,
+            silence_ms: Arc::new(AtomicU64::new(0)),
+            trim_silence: false,
+            dsp_settings: Arc::new(Mutex::new(DspSettings::default())),
+            recording_format: RecordingFormat::default(),
+            is_prerolling: Arc::new(AtomicBool::new(false)),
+            preroll: Arc::new(Mutex::new(PreRoll::new(0.0, DEFAULT_SAMPLE_RATE))),
+            preroll_secs: 0.0,
+            preroll_stream: None,
+            stream_health: Arc::new(Mutex::new(StreamHealth::default())),
+            last_input_ms: Arc::new(AtomicU64::new(0)),
+            active_input: None,
+            mixes_desktop: false,
+            quality: Arc::new(Mutex::new(QualityMeter::new(DEFAULT_SAMPLE_RATE))),
+            device_profiles: BTreeMap::new(),
+            recordings_dir: None,
+            pipe_settings: PipeSettings::default(),
         }
     }
 
-    pub fn list_input_devices() -> Result<Vec<String>, String> {
-        let host = cpal::default_host();
-        let devices: Vec<_> = host
-            .input_devices()
-            .map_err(|e| normalize_input_error("Failed to enumerate input devices", e))?
-            .collect();
+    /// The backend inputs are opened through, for captures outside recordings.
+    pub fn backend(&self) -> Arc<dyn AudioBackend> {
+        self.backend.clone()
+    }
 
-        if devices.is_empty() {
-            return Err("No input devices available".to_string());
+    /// Where the recorded (or, with desktop audio, mixed-into) input comes from.
+    fn input_backend(&self) -> Arc<dyn AudioBackend> {
+        match self.audio_source {
+            AudioSource::Pipe => Arc::new(PipeBackend::new(self.pipe_settings.clone())),
+            _ => self.backend.clone(),
         }
+    }
 
-        let names = devices.into_iter().filter_map(|d| d.name().ok()).collect();
-        Ok(names)
+    pub fn list_input_devices() -> Result<Vec<String>, String> {
+        CpalBackend.input_device_names()
     }
 
+    /// Monitor sources first (labelled with [`crate::loopback::LOOPBACK_PREFIX`]),
+    /// then every input, since loopback drivers show up as inputs too.
     pub fn list_desktop_input_devices() -> Result<Vec<String>, String> {
-        Self::list_input_devices()
+        let mut names = CpalBackend.monitor_names();
+        match Self::list_input_devices() {
+            Ok(inputs) => names.extend(inputs),
+            Err(err) if names.is_empty() => return Err(err),
+            Err(_) => {}
+        }
+        Ok(names)
     }
 
     pub fn set_device_name(&mut self, device_name: Option<String>) {
@@ -108,6 +319,176 @@ impl AudioRecorder {
         self.audio_source = source;
     }
 
+    /// Gains applied when mixing microphone and desktop audio.
+    pub fn set_mix_gains(&mut self, mic_gain: f32, desktop_gain: f32) {
+        self.mic_gain = mic_gain;
+        self.desktop_gain = desktop_gain;
+    }
+
+    pub fn set_track_layout(&mut self, layout: TrackLayout) {
+        self.track_layout = layout;
+    }
+
+    /// How to finish the WAV file `stop_recording` returned.
+    pub fn recording_finish(&self) -> RecordingFinish {
+        RecordingFinish {
+            track_layout: self.recorded_track_layout,
+            format: self.recording_format,
+            // Trimming would cut into the seam with the previous segment.
+            trim_silence: self.trim_silence && self.segment_index.load(Ordering::Relaxed) == 0,
+        }
+    }
+
+    pub fn set_target_sample_rate(&mut self, rate: u32) {
+        self.target_sample_rate = if rate == 0 { DEFAULT_SAMPLE_RATE } else { rate };
+    }
+
+    /// Microphone processing stages. Takes effect immediately, also in a
+    /// running microphone test.
+    pub fn set_dsp_settings(&self, settings: DspSettings) {
+        if let Ok(mut current) = self.dsp_settings.lock() {
+            *current = settings;
+        }
+    }
+
+    fn current_dsp_settings(&self) -> DspSettings {
+        self.dsp_settings.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    /// Takes effect the next time a device is opened.
+    pub fn set_device_profiles(&mut self, profiles: BTreeMap<String, DeviceProfile>) {
+        self.device_profiles = profiles;
+    }
+
+    /// The profile of the device called `name`, so a fallback device gets its
+    /// own settings rather than those of the configured one.
+    pub fn device_profile(&self, name: &str) -> DeviceProfile {
+        self.device_profiles.get(name).copied().unwrap_or_default()
+    }
+
+    /// Source and format of the `Pipe` audio source.
+    pub fn set_pipe_settings(&mut self, settings: PipeSettings) {
+        self.pipe_settings = settings;
+    }
+
+    /// Where new recordings are written; the configured folder if unset.
+    pub fn set_recordings_dir(&mut self, dir: PathBuf) {
+        self.recordings_dir = Some(dir);
+    }
+
+    /// Size and length at which a recording moves on to a new segment file.
+    /// A size of 0 means the largest allowed; a length of 0 means no limit.
+    pub fn set_segment_limits(&mut self, max_mb: u64, max_minutes: u64) {
+        self.segment_max_bytes = if max_mb == 0 {
+            MAX_RECORDING_BYTES
+        } else {
+            (max_mb * 1024 * 1024).min(MAX_RECORDING_BYTES)
+        };
+        self.segment_max_secs = max_minutes * 60;
+    }
+
+    /// Segments of the running recording that were closed since the last call.
+    pub fn take_finished_segments(&self) -> Vec<String> {
+        self.finished_segments
+            .lock()
+            .map(|mut s| std::mem::take(&mut *s))
+            .unwrap_or_default()
+    }
+
+    /// 1-based number of the segment being written.
+    pub fn segment_number(&self) -> u32 {
+        self.segment_index.load(Ordering::Relaxed) + 1
+    }
+
+    /// Seconds until the current segment closes.
+    pub fn segment_secs_left(&self) -> f32 {
+        let rate = self.target_sample_rate.max(1) as f32;
+        let tracks = if self.recorded_track_layout.is_split() {
+            2.0
+        } else {
+            1.0
+        };
+        let bytes_left = self
+            .segment_max_bytes
+            .saturating_sub(self.bytes_written.load(Ordering::Relaxed));
+        let by_size = bytes_left as f32 / (rate * 2.0 * tracks);
+        if self.segment_max_secs == 0 {
+            return by_size;
+        }
+        let elapsed = self.segment_samples.load(Ordering::Relaxed) as f32 / rate;
+        by_size.min((self.segment_max_secs as f32 - elapsed).max(0.0))
+    }
+
+    pub fn set_recording_format(&mut self, format: RecordingFormat) {
+        self.recording_format = format;
+    }
+
+    /// Keeps the microphone open between recordings and starts each recording
+    /// with the last `secs` seconds before it. 0 closes the input again.
+    /// Also reopens the input, so call it after changing the device.
+    pub fn set_preroll_secs(&mut self, secs: f32) {
+        self.preroll_secs = secs.clamp(0.0, crate::preroll::MAX_PREROLL_SECS);
+        if self.is_recording() {
+            // Picked up when the recording stops.
+            return;
+        }
+        self.stop_preroll();
+        if let Err(err) = self.start_preroll() {
+            eprintln!("Pre-roll unavailable: {err}");
+        }
+    }
+
+    pub fn is_prerolling(&self) -> bool {
+        self.is_prerolling.load(Ordering::SeqCst)
+    }
+
+    /// Desktop-only and piped recordings have no microphone to keep open.
+    fn start_preroll(&mut self) -> Result<(), String> {
+        if self.preroll_secs <= 0.0
+            || self.is_recording()
+            || self.is_prerolling()
+            || matches!(
+                self.audio_source,
+                AudioSource::DesktopOnly | AudioSource::Pipe
+            )
+        {
+            return Ok(());
+        }
+        if self.stream.is_none() {
+            self.open_monitor_stream()?;
+        } else if let Ok(mut preroll) = self.preroll.lock() {
+            // The microphone test's stream; only the length may have changed.
+            *preroll = PreRoll::new(self.preroll_secs, preroll.sample_rate());
+        }
+        self.is_prerolling.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Closes the pre-roll input unless the microphone test still uses it,
+    /// and returns what it captured.
+    fn stop_preroll(&mut self) -> Vec<f32> {
+        if !self.is_prerolling.swap(false, Ordering::SeqCst) {
+            return Vec::new();
+        }
+        if !self.is_monitoring() {
+            self.stream = None;
+        }
+        self.preroll
+            .lock()
+            .map(|mut p| p.take())
+            .unwrap_or_default()
+    }
+
+    /// Cut leading and trailing silence from the file when recording stops.
+    pub fn set_trim_silence(&mut self, trim: bool) {
+        self.trim_silence = trim;
+    }
+
+    /// How long the current recording has been silent, paused time excluded.
+    pub fn silence_secs(&self) -> f32 {
+        self.silence_ms.load(Ordering::Relaxed) as f32 / 1000.0
+    }
+
     pub fn is_recording(&self) -> bool {
         self.is_recording.load(Ordering::SeqCst)
     }
@@ -129,75 +510,6 @@ impl AudioRecorder {
         (clamped * i16::MAX as f32) as i16
     }
 
-    fn i16_from_u16(sample: u16) -> i16 {
-        (sample as i32 - i16::MAX as i32 - 1) as i16
-    }
-
-    fn get_named_input_device(
-        &self,
-        name: &Option<String>,
-        role: &str,
-    ) -> Result<cpal::Device, String> {
-        let host = cpal::default_host();
-
-        if let Some(device_name) = name {
-            let found_device =
-                host.input_devices().ok().and_then(|devices| {
-                    devices
-                        .filter_map(|d| {
-                            d.name().ok().and_then(|name| {
-                                if name == *device_name {
-                                    Some(d)
-                                } else {
-                                    None
-                                }
-                            })
-                        })
-                        .next()
-                });
-
-            match found_device {
-                Some(device) => {
-                    println!("Using selected {role} device: {device_name}");
-                    Ok(device)
-                }
-                None => {
-                    eprintln!("Selected {role} device '{device_name}' not found, using default");
-                    host.default_input_device()
-                        .ok_or_else(|| "No input device available".to_string())
-                }
-            }
-        } else {
-            match host.default_input_device() {
-                Some(device) => {
-                    if let Ok(name) = device.name() {
-                        println!("Using default {role} device: {name}");
-                    }
-                    Ok(device)
-                }
-                None => Err("No input device available".to_string()),
-            }
-        }
-    }
-
-    fn get_recording_devices(&self) -> Result<(cpal::Device, Option<cpal::Device>), String> {
-        match self.audio_source {
-            AudioSource::MicOnly => {
-                let mic = self.get_named_input_device(&self.device_name, "microphone")?;
-                Ok((mic, None))
-            }
-            AudioSource::DesktopOnly => {
-                let desktop = self.get_named_input_device(&self.desktop_device_name, "desktop")?;
-                Ok((desktop, None))
-            }
-            AudioSource::MicAndDesktop => {
-                let mic = self.get_named_input_device(&self.device_name, "microphone")?;
-                let desktop = self.get_named_input_device(&self.desktop_device_name, "desktop")?;
-                Ok((mic, Some(desktop)))
-            }
-        }
-    }
-
     pub fn start_recording(&mut self) -> Result<String, String> {
         if self.is_recording.load(Ordering::SeqCst) {
             return Err("Recording is already running".to_string());
@@ -206,53 +518,67 @@ impl AudioRecorder {
         if self.is_monitoring() {
             self.stop_monitoring()?;
         }
-
-        let (device, desktop_device) = self.get_recording_devices()?;
-
-        let supported = match device.default_input_config() {
-            Ok(config) => {
-                println!(
-                    "Audio config: {} channels, {} Hz, {:?}",
-                    config.channels(),
-                    config.sample_rate().0,
-                    config.sample_format()
-                );
-                config
-            }
-            Err(err) => {
-                return Err(normalize_input_error(
-                    "Failed to get default microphone configuration",
-                    err,
-                ));
+        let preroll_rate = self.preroll.lock().map(|p| p.sample_rate()).unwrap_or(0);
+
+        // The file is always written at `target_sample_rate`. Devices that can't
+        // capture at that rate run at their default and are resampled.
+        let target_rate = self.target_sample_rate;
+        // Fail before the file is created when an input, such as the loopback
+        // source, is missing.
+        let mut microphone = match self.audio_source {
+            AudioSource::DesktopOnly => None,
+            _ => Some(self.input_backend().input_config(
+                DeviceRole::Microphone,
+                &self.device_name,
+                Some(target_rate),
+            )?),
+        };
+        // A pre-roll on the same microphone keeps running and becomes the
+        // recording's stream, so nothing is lost while a device reopens.
+        let handoff = microphone
+            .as_ref()
+            .and_then(|input| self.preroll_sink(input));
+        let preroll = match &handoff {
+            Some((input, _)) => {
+                microphone = Some(input.clone());
+                self.preroll
+                    .lock()
+                    .map(|mut p| p.take())
+                    .unwrap_or_default()
             }
+            None => self.stop_preroll(),
         };
-
-        // Try to get a 16kHz config — Whisper works best at 16kHz and it keeps files small.
-        // If the device doesn't support it, fall back to the default rate.
-        const TARGET_RATE: cpal::SampleRate = cpal::SampleRate(8000);
-        let stream_config = {
-            let found_16k = device.supported_input_configs().ok().and_then(|mut cfgs| {
-                cfgs.find(|c| {
-                    c.min_sample_rate() <= TARGET_RATE && c.max_sample_rate() >= TARGET_RATE
-                })
-                .map(|c| c.with_sample_rate(TARGET_RATE).config())
-            });
-            found_16k.unwrap_or_else(|| supported.config())
+        let desktop = match self.audio_source {
+            AudioSource::MicOnly | AudioSource::Pipe => None,
+            _ => Some(self.backend.input_config(
+                DeviceRole::Desktop,
+                &self.desktop_device_name,
+                Some(target_rate),
+            )?),
         };
-        let sample_format = supported.sample_format();
-        let num_input_channels = stream_config.channels as usize;
         let should_mix_desktop = matches!(self.audio_source, AudioSource::MicAndDesktop);
 
+        let track_layout = if should_mix_desktop {
+            self.track_layout
+        } else {
+            TrackLayout::Mixed
+        };
+        self.recorded_track_layout = track_layout;
+        self.mixes_desktop = should_mix_desktop;
+
         if should_mix_desktop {
-            if let Ok(mut q) = self.desktop_mix_buf.lock() {
-                q.clear();
-            }
+            self.desktop_mix_buf = Arc::new(Mutex::new(DriftBuffer::new(target_rate)));
         }
 
-        // Always write mono — halves (or more) the file size vs stereo.
+        // Mono unless mic and desktop go to separate channels — halves (or more)
+        // the file size vs stereo.
         let spec = WavSpec {
-            channels: 1,
-            sample_rate: stream_config.sample_rate.0,
+            channels: if track_layout == TrackLayout::Stereo {
+                2
+            } else {
+                1
+            },
+            sample_rate: target_rate,
             bits_per_sample: 16,
             sample_format: SampleFormat::Int,
         };
@@ -263,11 +589,17 @@ impl AudioRecorder {
             .as_secs();
 
         let filename = format!("recording_{}.wav", timestamp);
-        let recordings_dir = crate::config::AppConfig::load()
-            .ensure_recordings_dir()
-            .map_err(|e| format!("Failed to prepare recordings directory: {e}"))?;
+        let recordings_dir = match &self.recordings_dir {
+            Some(dir) => std::fs::create_dir_all(dir)
+                .map(|_| dir.clone())
+                .map_err(|e| e.to_string()),
+            None => crate::config::AppConfig::load().ensure_recordings_dir(),
+        }
+        .map_err(|e| format!("Failed to prepare recordings directory: {e}"))?;
         let file_path = recordings_dir.join(&filename);
-        self.current_filename = Some(file_path.to_string_lossy().to_string());
+        if let Ok(mut current) = self.current_filename.lock() {
+            *current = Some(file_path.to_string_lossy().to_string());
+        }
 
         let writer = match WavWriter::create(&file_path, spec) {
             Ok(writer) => Arc::new(Mutex::new(writer)),
@@ -276,326 +608,543 @@ impl AudioRecorder {
             }
         };
 
+        let desktop_writer = if track_layout == TrackLayout::SeparateFiles {
+            let path = desktop_track_path(&file_path);
+            let writer = WavWriter::create(&path, spec)
+                .map_err(|err| format!("Error creating desktop wav file: {err}"))?;
+            Some(Arc::new(Mutex::new(writer)))
+        } else {
+            None
+        };
+        if let Err(err) = recovery::mark_in_progress(&file_path, track_layout) {
+            eprintln!("{err}");
+        }
+
         self.writer = Some(writer.clone());
+        self.desktop_writer = desktop_writer.clone();
         self.bytes_written.store(0, Ordering::SeqCst);
+        self.segment_samples.store(0, Ordering::Relaxed);
+        self.segment_index.store(0, Ordering::Relaxed);
+        if let Ok(mut finished) = self.finished_segments.lock() {
+            finished.clear();
+        }
+        let profile = microphone
+            .as_ref()
+            .map(|input| self.device_profile(&input.device_name))
+            .unwrap_or_default();
+        let mut dsp = DspChain::new(
+            target_rate,
+            profile.dsp.unwrap_or_else(|| self.current_dsp_settings()),
+        );
+        if !preroll.is_empty() {
+            let frames = self.write_preroll(
+                &preroll,
+                preroll_rate,
+                &mut dsp,
+                &writer,
+                desktop_writer.as_deref(),
+                should_mix_desktop,
+            );
+            let tracks = if track_layout.is_split() { 2 } else { 1 };
+            self.bytes_written
+                .store((frames * 2 * tracks) as u64, Ordering::Relaxed);
+            self.segment_samples.store(frames as u64, Ordering::Relaxed);
+            println!(
+                "Prepended {:.1}s of pre-roll",
+                frames as f32 / target_rate as f32
+            );
+        }
+        self.silence_ms.store(0, Ordering::Relaxed);
+        self.reset_stream_health();
+        if let Ok(mut quality) = self.quality.lock() {
+            *quality = QualityMeter::new(target_rate);
+        }
+
+        let primary = microphone
+            .as_ref()
+            .or(desktop.as_ref())
+            .ok_or_else(|| "No input selected".to_string())?;
+        self.is_recording.store(true, Ordering::SeqCst);
+        self.is_monitoring.store(false, Ordering::SeqCst);
+        self.is_paused.store(false, Ordering::SeqCst);
+        let started = match handoff {
+            Some((_, sink)) => self.take_over_preroll(primary, dsp, &sink),
+            None => self
+                .open_input_stream(primary, dsp)
+                .map(|stream| self.stream = Some(stream)),
+        }
+        .and_then(|()| match desktop.as_ref().filter(|_| should_mix_desktop) {
+            Some(input) => self.open_desktop_stream(input).map(Some),
+            None => Ok(None),
+        });
+        match started {
+            Ok(desktop_stream) => self.stream2 = desktop_stream,
+            Err(err) => {
+                self.is_recording.store(false, Ordering::SeqCst);
+                self.stream = None;
+                return Err(err);
+            }
+        }
+
+        println!("Recording started: {}", filename);
+        println!("Speak now...");
+        Ok(file_path.to_string_lossy().to_string())
+    }
+
+    /// Live warning about the microphone level, while recording.
+    pub fn quality_warning(&self) -> Option<&'static str> {
+        self.quality.lock().ok().and_then(|q| q.live_warning())
+    }
+
+    /// Levels of the running or most recent recording.
+    pub fn recording_quality(&self) -> Option<AudioQuality> {
+        self.quality.lock().ok().and_then(|q| q.summary())
+    }
+
+    fn reset_stream_health(&self) {
+        if let Ok(mut health) = self.stream_health.lock() {
+            *health = StreamHealth::default();
+        }
+    }
+
+    /// Checks the inputs of a running recording and moves a failed one to
+    /// another device, keeping the file. Returns a message for the user when
+    /// something went wrong. Stops the recording if no input works.
+    pub fn check_streams(&mut self) -> Option<String> {
+        if !self.is_recording() {
+            return None;
+        }
+        let (input_lost, desktop_lost, error) = match self.stream_health.lock() {
+            Ok(mut health) => (
+                std::mem::take(&mut health.input_lost),
+                std::mem::take(&mut health.desktop_lost),
+                health.last_error.take(),
+            ),
+            Err(_) => return None,
+        };
+        // A pipe may go quiet for a while; it reports when it is closed.
+        let stalled = self.audio_source != AudioSource::Pipe
+            && now_ms().saturating_sub(self.last_input_ms.load(Ordering::Relaxed)) > STALL_MS;
+
+        if input_lost || stalled {
+            let reason = error.unwrap_or_else(|| "no audio for a few seconds".to_string());
+            return Some(self.fail_over_input(&reason));
+        }
+        if desktop_lost {
+            let reason = error.unwrap_or_default();
+            return Some(self.reopen_desktop(&reason));
+        }
+        error.map(|err| format!("Audio stream error: {err}"))
+    }
+
+    fn fail_over_input(&mut self, reason: &str) -> String {
+        let failed = self.active_input.take().unwrap_or_default();
+        self.stream = None;
+        eprintln!("Input '{failed}' failed: {reason}");
+        if self.audio_source == AudioSource::Pipe {
+            self.is_recording.store(false, Ordering::SeqCst);
+            return format!("'{failed}' was closed. Recording stopped.");
+        }
+
+        // Desktop-only recordings move to another loopback source, never to
+        // a microphone.
+        let (role, preferred, default, available) = if self.audio_source == AudioSource::DesktopOnly
+        {
+            (
+                DeviceRole::Desktop,
+                self.desktop_device_name.as_deref(),
+                None,
+                self.backend.monitor_names(),
+            )
+        } else {
+            (
+                DeviceRole::Microphone,
+                self.device_name.as_deref(),
+                self.backend.default_input_name(),
+                self.backend.input_device_names().unwrap_or_default(),
+            )
+        };
+        for name in failover_order(&failed, preferred, default.as_deref(), &available) {
+            let opened = self
+                .backend
+                .input_config(role, &Some(name.clone()), Some(self.target_sample_rate))
+                .and_then(|input| {
+                    let dsp = DspChain::new(self.target_sample_rate, self.current_dsp_settings());
+                    self.open_input_stream(&input, dsp)
+                });
+            match opened {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    return format!(
+                        "'{failed}' stopped ({reason}). Recording continues on '{name}'."
+                    );
+                }
+                Err(err) => eprintln!("Could not switch to '{name}': {err}"),
+            }
+        }
+
+        // The app finalizes and transcribes what was recorded so far.
+        self.is_recording.store(false, Ordering::SeqCst);
+        format!("'{failed}' stopped ({reason}) and no other input works. Recording stopped.")
+    }
+
+    fn reopen_desktop(&mut self, reason: &str) -> String {
+        self.stream2 = None;
+        let reopened = self
+            .backend
+            .input_config(
+                DeviceRole::Desktop,
+                &self.desktop_device_name,
+                Some(self.target_sample_rate),
+            )
+            .and_then(|input| self.open_desktop_stream(&input));
+        match reopened {
+            Ok(stream) => {
+                self.stream2 = Some(stream);
+                format!("Desktop audio was interrupted ({reason}) and has been reopened.")
+            }
+            Err(err) => {
+                eprintln!("Could not reopen desktop audio: {err}");
+                format!(
+                    "Desktop audio stopped ({reason}). Recording continues with the microphone only."
+                )
+            }
+        }
+    }
+
+    /// Opens the primary input of a recording, writing into the open file.
+    /// Also used to continue on another device after the first one failed.
+    fn open_input_stream(
+        &mut self,
+        input: &InputConfig,
+        dsp: DspChain,
+    ) -> Result<InputStream, String> {
+        let shaping = self.device_profile(&input.device_name).shaping();
+        let (on_data, on_error) = self.recording_callbacks(input, dsp)?;
+        let stream = self
+            .input_backend()
+            .open_input(input, shaping, on_data, on_error)?;
+        self.last_input_ms.store(now_ms(), Ordering::Relaxed);
+        self.active_input = Some(input.device_name.clone());
+        Ok(stream)
+    }
+
+    /// Hands the running pre-roll stream to the recording instead of opening
+    /// the microphone again. What reached the pre-roll after it was written
+    /// to the file goes through the recording first.
+    fn take_over_preroll(
+        &mut self,
+        input: &InputConfig,
+        dsp: DspChain,
+        sink: &PrerollSink,
+    ) -> Result<(), String> {
+        let (mut on_data, on_error) = self.recording_callbacks(input, dsp)?;
+        let mut sink = sink
+            .lock()
+            .map_err(|_| "The pre-roll stream failed".to_string())?;
+        let rest = self
+            .preroll
+            .lock()
+            .map(|mut p| p.take())
+            .unwrap_or_default();
+        on_data(&rest);
+        *sink = Some((on_data, on_error));
+        self.is_prerolling.store(false, Ordering::SeqCst);
+        self.last_input_ms.store(now_ms(), Ordering::Relaxed);
+        self.active_input = Some(input.device_name.clone());
+        Ok(())
+    }
+
+    /// The slot of a pre-roll stream running on `microphone`, with the input
+    /// it was opened with.
+    fn preroll_sink(&self, microphone: &InputConfig) -> Option<(InputConfig, Arc<PrerollSink>)> {
+        if !self.is_prerolling() {
+            return None;
+        }
+        let (input, sink) = self.preroll_stream.as_ref()?;
+        if input.device_name != microphone.device_name {
+            return None;
+        }
+        Some((input.clone(), sink.upgrade()?))
+    }
+
+    /// Callbacks that resample, process and write the primary input.
+    fn recording_callbacks(
+        &mut self,
+        input: &InputConfig,
+        mut dsp: DspChain,
+    ) -> Result<(DataCallback, ErrorCallback), String> {
+        let Some(writer) = self.writer.clone() else {
+            return Err("No recording file is open".to_string());
+        };
+        let desktop_writer = self.desktop_writer.clone();
+        let target_rate = self.target_sample_rate;
+        let track_layout = self.recorded_track_layout;
+        let should_mix_desktop = self.mixes_desktop;
+
+        let profile = self.device_profile(&input.device_name);
+        if profile != DeviceProfile::default() {
+            println!(
+                "Device profile: {:+.1} dB, channel {:?}",
+                profile.gain_db, profile.channel
+            );
+        }
+
+        let segment_samples = self.segment_samples.clone();
+        let segment_index = self.segment_index.clone();
+        let finished_segments = self.finished_segments.clone();
+        let current_filename = self.current_filename.clone();
+        let segment_max_bytes = self.segment_max_bytes;
+        let segment_max_samples = self.segment_max_secs * target_rate as u64;
         let is_recording = self.is_recording.clone();
         let is_monitoring = self.is_monitoring.clone();
         let is_paused = self.is_paused.clone();
         let level_milli = self.level_milli.clone();
         let bytes_written = self.bytes_written.clone();
         let desktop_mix_buf = self.desktop_mix_buf.clone();
-
-        let stream = match sample_format {
-            cpal::SampleFormat::F32 => {
-                let writer_clone = writer.clone();
-                let is_recording_clone = is_recording.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                let bytes_written_clone = bytes_written.clone();
-                let desktop_mix_buf_clone = desktop_mix_buf.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[f32], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
-                            }
-                            if bytes_written_clone.load(Ordering::Relaxed) >= MAX_RECORDING_BYTES {
-                                is_recording_clone.store(false, Ordering::SeqCst);
-                                eprintln!(
-                                    "Recording stopped: reached {MAX_RECORDING_BYTES} byte limit"
-                                );
-                                return;
-                            }
-                            if let Ok(mut writer) = writer_clone.lock() {
-                                let mut peak = 0.0f32;
-                                for frame in data.chunks(num_input_channels) {
-                                    let mic = frame.iter().sum::<f32>() / frame.len() as f32;
-                                    let desktop = if should_mix_desktop {
-                                        desktop_mix_buf_clone
-                                            .lock()
-                                            .ok()
-                                            .and_then(|mut q| q.pop_front())
-                                            .unwrap_or(0.0)
-                                    } else {
-                                        0.0
-                                    };
-                                    let mono = if should_mix_desktop {
-                                        ((mic + desktop) * 0.5).clamp(-1.0, 1.0)
-                                    } else {
-                                        mic
-                                    };
-                                    peak = peak.max(mono.abs());
-                                    let _ = writer.write_sample(Self::i16_from_f32(mono));
-                                }
-                                bytes_written_clone.fetch_add(
-                                    (data.len() / num_input_channels * 2) as u64,
-                                    Ordering::Relaxed,
-                                );
-                                let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                                level_milli_clone.store(scaled, Ordering::SeqCst);
-                            }
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            cpal::SampleFormat::I16 => {
-                let writer_clone = writer.clone();
-                let is_recording_clone = is_recording.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                let bytes_written_clone = bytes_written.clone();
-                let desktop_mix_buf_clone = desktop_mix_buf.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[i16], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
-                            }
-                            if bytes_written_clone.load(Ordering::Relaxed) >= MAX_RECORDING_BYTES {
-                                is_recording_clone.store(false, Ordering::SeqCst);
-                                eprintln!(
-                                    "Recording stopped: reached {MAX_RECORDING_BYTES} byte limit"
-                                );
-                                return;
-                            }
-                            if let Ok(mut writer) = writer_clone.lock() {
-                                let mut peak = 0.0f32;
-                                for frame in data.chunks(num_input_channels) {
-                                    let mic = frame
-                                        .iter()
-                                        .map(|&s| s as f32 / i16::MAX as f32)
-                                        .sum::<f32>()
-                                        / frame.len() as f32;
-                                    let desktop = if should_mix_desktop {
-                                        desktop_mix_buf_clone
-                                            .lock()
-                                            .ok()
-                                            .and_then(|mut q| q.pop_front())
-                                            .unwrap_or(0.0)
-                                    } else {
-                                        0.0
-                                    };
-                                    let mono = if should_mix_desktop {
-                                        ((mic + desktop) * 0.5).clamp(-1.0, 1.0)
-                                    } else {
-                                        mic
-                                    };
-                                    peak = peak.max(mono.abs());
-                                    let _ = writer.write_sample(Self::i16_from_f32(mono));
-                                }
-                                bytes_written_clone.fetch_add(
-                                    (data.len() / num_input_channels * 2) as u64,
-                                    Ordering::Relaxed,
-                                );
-                                let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                                level_milli_clone.store(scaled, Ordering::SeqCst);
-                            }
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            cpal::SampleFormat::U16 => {
-                let writer_clone = writer.clone();
-                let is_recording_clone = is_recording.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                let bytes_written_clone = bytes_written.clone();
-                let desktop_mix_buf_clone = desktop_mix_buf.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[u16], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
+        let silence_ms = self.silence_ms.clone();
+        let health = self.stream_health.clone();
+        let last_input_ms = self.last_input_ms.clone();
+        let quality = self.quality.clone();
+
+        let resampler = Arc::new(Mutex::new(Resampler::new(input.sample_rate, target_rate)));
+        self.resampler = Some(resampler.clone());
+        let mut resampled = Vec::new();
+        let mut desktop = Vec::new();
+        let mut vad = Vad::new(target_rate);
+        let mut voice = Vec::new();
+        let mut unflushed = 0usize;
+        let dsp_settings = self.dsp_settings.clone();
+        let (mic_gain, desktop_gain) = (self.mic_gain, self.desktop_gain);
+        Ok((
+            Box::new(move |data: &[f32]| {
+                last_input_ms.store(now_ms(), Ordering::Relaxed);
+                let recording = is_recording.load(Ordering::SeqCst);
+                let monitoring = is_monitoring.load(Ordering::SeqCst);
+                if (!recording && !monitoring) || (recording && is_paused.load(Ordering::SeqCst)) {
+                    return;
+                }
+                resampled.clear();
+                if let Ok(mut resampler) = resampler.lock() {
+                    resampler.process(data, &mut resampled);
+                }
+                // Levels of what the microphone picked up; gain and noise
+                // suppression would hide a quiet or noisy input.
+                if recording {
+                    if let Ok(mut quality) = quality.lock() {
+                        quality.process(&resampled);
+                    }
+                }
+                if let Ok(settings) = dsp_settings.lock() {
+                    dsp.set_settings(profile.dsp.unwrap_or(*settings));
+                }
+                dsp.process(&mut resampled);
+                if should_mix_desktop {
+                    desktop.clear();
+                    if let Ok(mut buffer) = desktop_mix_buf.lock() {
+                        buffer.pull(resampled.len(), &mut desktop);
+                    }
+                    desktop.resize(resampled.len(), 0.0);
+                }
+                if let Ok(mut writer) = writer.lock() {
+                    let mut desktop_writer = desktop_writer.as_ref().and_then(|w| w.lock().ok());
+                    let mut peak = 0.0f32;
+                    for (i, &mic) in resampled.iter().enumerate() {
+                        match track_layout {
+                            TrackLayout::Mixed => {
+                                let mono = if should_mix_desktop {
+                                    mixer::mix(mic, desktop[i], mic_gain, desktop_gain)
+                                } else {
+                                    mic
+                                };
+                                peak = peak.max(mono.abs());
+                                let _ = writer.write_sample(Self::i16_from_f32(mono));
                             }
-                            if bytes_written_clone.load(Ordering::Relaxed) >= MAX_RECORDING_BYTES {
-                                is_recording_clone.store(false, Ordering::SeqCst);
-                                eprintln!(
-                                    "Recording stopped: reached {MAX_RECORDING_BYTES} byte limit"
-                                );
-                                return;
+                            TrackLayout::Stereo => {
+                                peak = peak.max(mic.abs()).max(desktop[i].abs());
+                                let _ = writer.write_sample(Self::i16_from_f32(mic));
+                                let _ = writer.write_sample(Self::i16_from_f32(desktop[i]));
                             }
-                            if let Ok(mut writer) = writer_clone.lock() {
-                                let mut peak = 0.0f32;
-                                for frame in data.chunks(num_input_channels) {
-                                    let mic = frame
-                                        .iter()
-                                        .map(|&s| Self::i16_from_u16(s) as f32 / i16::MAX as f32)
-                                        .sum::<f32>()
-                                        / frame.len() as f32;
-                                    let desktop = if should_mix_desktop {
-                                        desktop_mix_buf_clone
-                                            .lock()
-                                            .ok()
-                                            .and_then(|mut q| q.pop_front())
-                                            .unwrap_or(0.0)
-                                    } else {
-                                        0.0
-                                    };
-                                    let mono = if should_mix_desktop {
-                                        ((mic + desktop) * 0.5).clamp(-1.0, 1.0)
-                                    } else {
-                                        mic
-                                    };
-                                    peak = peak.max(mono.abs());
-                                    let _ = writer.write_sample(Self::i16_from_f32(mono));
+                            TrackLayout::SeparateFiles => {
+                                peak = peak.max(mic.abs()).max(desktop[i].abs());
+                                let _ = writer.write_sample(Self::i16_from_f32(mic));
+                                if let Some(desktop_writer) = desktop_writer.as_mut() {
+                                    let _ =
+                                        desktop_writer.write_sample(Self::i16_from_f32(desktop[i]));
                                 }
-                                bytes_written_clone.fetch_add(
-                                    (data.len() / num_input_channels * 2) as u64,
-                                    Ordering::Relaxed,
-                                );
-                                let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                                level_milli_clone.store(scaled, Ordering::SeqCst);
                             }
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            other => return Err(format!("Unsupported sample format: {other:?}")),
-        };
-
-        if let Err(err) = stream.play() {
-            return Err(normalize_input_error(
-                "Failed to start microphone stream",
-                err,
-            ));
-        }
-
-        let desktop_stream = if should_mix_desktop {
-            let Some(desktop_device) = desktop_device else {
-                return Err("Desktop audio source requires a desktop input device".to_string());
-            };
-
-            let desktop_supported = desktop_device.default_input_config().map_err(|err| {
-                normalize_input_error("Failed to get default desktop audio configuration", err)
-            })?;
-
-            let desktop_sample_rate = stream_config.sample_rate;
-            let desktop_stream_config = desktop_device
-                .supported_input_configs()
-                .ok()
-                .and_then(|mut cfgs| {
-                    cfgs.find(|c| {
-                        c.min_sample_rate() <= desktop_sample_rate
-                            && c.max_sample_rate() >= desktop_sample_rate
-                    })
-                    .map(|c| c.with_sample_rate(desktop_sample_rate).config())
-                })
-                .unwrap_or_else(|| desktop_supported.config());
-            let desktop_channels = desktop_stream_config.channels as usize;
-            let desktop_mix_buf_clone = self.desktop_mix_buf.clone();
-
-            let stream2 = match desktop_supported.sample_format() {
-                cpal::SampleFormat::F32 => desktop_device
-                    .build_input_stream(
-                        &desktop_stream_config,
-                        move |data: &[f32], _| {
-                            if let Ok(mut q) = desktop_mix_buf_clone.lock() {
-                                for frame in data.chunks(desktop_channels) {
-                                    let mono = frame.iter().sum::<f32>() / frame.len() as f32;
-                                    if q.len() >= 32768 {
-                                        q.pop_front();
-                                    }
-                                    q.push_back(mono.clamp(-1.0, 1.0));
+                        }
+                    }
+                    voice.clear();
+                    voice.extend(resampled.iter().enumerate().map(|(i, &mic)| {
+                        if should_mix_desktop {
+                            mic + desktop[i]
+                        } else {
+                            mic
+                        }
+                    }));
+                    vad.process(&voice);
+                    silence_ms.store((vad.silence_secs() * 1000.0) as u64, Ordering::Relaxed);
+                    let tracks = if track_layout.is_split() { 2 } else { 1 };
+                    bytes_written
+                        .fetch_add((resampled.len() * 2 * tracks) as u64, Ordering::Relaxed);
+                    // Keep the header current so a crash loses at most a second.
+                    unflushed += resampled.len();
+                    if unflushed >= target_rate as usize {
+                        unflushed = 0;
+                        let _ = writer.flush();
+                        if let Some(desktop_writer) = desktop_writer.as_mut() {
+                            let _ = desktop_writer.flush();
+                        }
+                    }
+                    let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
+                    level_milli.store(scaled, Ordering::SeqCst);
+
+                    let samples = segment_samples
+                        .fetch_add(resampled.len() as u64, Ordering::Relaxed)
+                        + resampled.len() as u64;
+                    let full = bytes_written.load(Ordering::Relaxed) >= segment_max_bytes
+                        || (segment_max_samples > 0 && samples >= segment_max_samples);
+                    if full && recording {
+                        let index = segment_index.load(Ordering::Relaxed) + 1;
+                        let Some(closed) = current_filename.lock().ok().and_then(|c| c.clone())
+                        else {
+                            return;
+                        };
+                        let next = segment_path(&Self::first_segment(&closed), index);
+                        match rotate_segment(
+                            &mut writer,
+                            desktop_writer.as_deref_mut(),
+                            &next,
+                            track_layout,
+                        ) {
+                            Ok(()) => {
+                                recovery::mark_finished(Path::new(&closed));
+                                if let Ok(mut current) = current_filename.lock() {
+                                    *current = Some(next.to_string_lossy().to_string());
                                 }
-                            }
-                        },
-                        move |err| eprintln!("Desktop stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| normalize_input_error("Failed to open desktop audio stream", e))?,
-                cpal::SampleFormat::I16 => desktop_device
-                    .build_input_stream(
-                        &desktop_stream_config,
-                        move |data: &[i16], _| {
-                            if let Ok(mut q) = desktop_mix_buf_clone.lock() {
-                                for frame in data.chunks(desktop_channels) {
-                                    let mono = frame
-                                        .iter()
-                                        .map(|&s| s as f32 / i16::MAX as f32)
-                                        .sum::<f32>()
-                                        / frame.len() as f32;
-                                    if q.len() >= 32768 {
-                                        q.pop_front();
-                                    }
-                                    q.push_back(mono.clamp(-1.0, 1.0));
+                                if let Ok(mut finished) = finished_segments.lock() {
+                                    finished.push(closed);
                                 }
+                                segment_index.store(index, Ordering::Relaxed);
+                                bytes_written.store(0, Ordering::Relaxed);
+                                segment_samples.store(0, Ordering::Relaxed);
+                                unflushed = 0;
                             }
-                        },
-                        move |err| eprintln!("Desktop stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| normalize_input_error("Failed to open desktop audio stream", e))?,
-                cpal::SampleFormat::U16 => desktop_device
-                    .build_input_stream(
-                        &desktop_stream_config,
-                        move |data: &[u16], _| {
-                            if let Ok(mut q) = desktop_mix_buf_clone.lock() {
-                                for frame in data.chunks(desktop_channels) {
-                                    let mono = frame
-                                        .iter()
-                                        .map(|&s| Self::i16_from_u16(s) as f32 / i16::MAX as f32)
-                                        .sum::<f32>()
-                                        / frame.len() as f32;
-                                    if q.len() >= 32768 {
-                                        q.pop_front();
-                                    }
-                                    q.push_back(mono.clamp(-1.0, 1.0));
-                                }
+                            Err(err) => {
+                                // Fall back to stopping; the app finalizes
+                                // what was written so far.
+                                eprintln!("Could not start a new segment: {err}");
+                                is_recording.store(false, Ordering::SeqCst);
                             }
-                        },
-                        move |err| eprintln!("Desktop stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| normalize_input_error("Failed to open desktop audio stream", e))?,
-                other => {
-                    return Err(format!("Unsupported desktop sample format: {other:?}"));
+                        }
+                    }
+                }
+            }),
+            Box::new(move |err| {
+                eprintln!("Stream error: {}", err);
+                if let Ok(mut health) = health.lock() {
+                    health.report(false, &err);
                 }
-            };
+            }),
+        ))
+    }
 
-            stream2
-                .play()
-                .map_err(|err| normalize_input_error("Failed to start desktop stream", err))?;
-            Some(stream2)
-        } else {
-            None
-        };
+    /// Opens the desktop input that is mixed into, or recorded next to, the
+    /// microphone.
+    fn open_desktop_stream(&self, input: &InputConfig) -> Result<InputStream, String> {
+        let mut desktop_resampler = Resampler::new(input.sample_rate, self.target_sample_rate);
+        let mut resampled = Vec::new();
+        let desktop_mix_buf = self.desktop_mix_buf.clone();
+        let health = self.stream_health.clone();
+        self.backend.open_input(
+            input,
+            self.device_profile(&input.device_name).shaping(),
+            Box::new(move |data: &[f32]| {
+                resampled.clear();
+                desktop_resampler.process(data, &mut resampled);
+                if let Ok(mut buffer) = desktop_mix_buf.lock() {
+                    buffer.push(&resampled);
+                }
+            }),
+            Box::new(move |err| {
+                eprintln!("Desktop stream error: {}", err);
+                if let Ok(mut health) = health.lock() {
+                    health.report(true, &err);
+                }
+            }),
+        )
+    }
 
-        self.is_recording.store(true, Ordering::SeqCst);
-        self.is_monitoring.store(false, Ordering::SeqCst);
-        self.is_paused.store(false, Ordering::SeqCst);
-        self.stream = Some(stream);
-        self.stream2 = desktop_stream;
+    /// Writes the pre-roll at the start of a new recording, resampled and
+    /// processed like the live input. Returns the number of frames written.
+    fn write_preroll(
+        &self,
+        samples: &[f32],
+        rate: u32,
+        dsp: &mut DspChain,
+        writer: &Mutex<WavFileWriter>,
+        desktop_writer: Option<&Mutex<WavFileWriter>>,
+        mix_desktop: bool,
+    ) -> usize {
+        let mut mic = Vec::new();
+        let mut resampler = Resampler::new(rate, self.target_sample_rate);
+        resampler.process(samples, &mut mic);
+        resampler.finish(&mut mic);
+        dsp.process(&mut mic);
+
+        let Ok(mut writer) = writer.lock() else {
+            return 0;
+        };
+        let mut desktop_writer = desktop_writer.and_then(|w| w.lock().ok());
+        for &sample in &mic {
+            // There is no desktop audio from before the recording.
+            match self.recorded_track_layout {
+                TrackLayout::Mixed => {
+                    let mono = if mix_desktop {
+                        mixer::mix(sample, 0.0, self.mic_gain, self.desktop_gain)
+                    } else {
+                        sample
+                    };
+                    let _ = writer.write_sample(Self::i16_from_f32(mono));
+                }
+                TrackLayout::Stereo => {
+                    let _ = writer.write_sample(Self::i16_from_f32(sample));
+                    let _ = writer.write_sample(0i16);
+                }
+                TrackLayout::SeparateFiles => {
+                    let _ = writer.write_sample(Self::i16_from_f32(sample));
+                    if let Some(desktop_writer) = desktop_writer.as_mut() {
+                        let _ = desktop_writer.write_sample(0i16);
+                    }
+                }
+            }
+        }
+        mic.len()
+    }
 
-        println!("Recording started: {}", filename);
-        println!("Speak now...");
-        Ok(file_path.to_string_lossy().to_string())
+    /// Starts a record-and-playback check of the microphone with the current
+    /// rate, processing and mix gain.
+    pub fn start_mic_check(&self) -> Result<MicCheck, String> {
+        let gain = match (&self.audio_source, self.track_layout) {
+            (AudioSource::DesktopOnly | AudioSource::Pipe, _) => {
+                return Err("Choose a source with a microphone to check it.".to_string())
+            }
+            (AudioSource::MicAndDesktop, TrackLayout::Mixed) => self.mic_gain,
+            _ => 1.0,
+        };
+        let input = self
+            .backend
+            .input_config(DeviceRole::Microphone, &self.device_name, None)?;
+        let profile = self.device_profile(&input.device_name);
+        MicCheck::start(
+            self.backend.as_ref(),
+            &input,
+            self.target_sample_rate,
+            profile.dsp.unwrap_or_else(|| self.current_dsp_settings()),
+            profile.shaping(),
+            gain,
+        )
     }
 
     pub fn start_monitoring(&mut self) -> Result<(), String> {
@@ -607,128 +1156,95 @@ impl AudioRecorder {
             return Ok(());
         }
 
-        let device = self.get_named_input_device(&self.device_name, "microphone")?;
+        // A running pre-roll already has the microphone open.
+        if !self.is_prerolling() || self.stream.is_none() {
+            self.open_monitor_stream()?;
+        }
 
-        let supported = device.default_input_config().map_err(|err| {
-            normalize_input_error("Failed to get default microphone configuration", err)
-        })?;
-        let stream_config = supported.config();
-        let sample_format = supported.sample_format();
+        self.stream2 = None;
+        self.writer = None;
+        self.resampler = None;
+        self.desktop_writer = None;
+        self.clear_current_filename();
+        self.is_paused.store(false, Ordering::SeqCst);
+        self.is_recording.store(false, Ordering::SeqCst);
+        self.is_monitoring.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Opens the microphone for the microphone test and the pre-roll. The
+    /// pre-roll only copies samples; levels and processing run for the test.
+    /// A recording can take the stream over through its sink.
+    fn open_monitor_stream(&mut self) -> Result<(), String> {
+        let input = self
+            .backend
+            .input_config(DeviceRole::Microphone, &self.device_name, None)?;
 
+        if let Ok(mut preroll) = self.preroll.lock() {
+            *preroll = PreRoll::new(self.preroll_secs, input.sample_rate);
+        }
+        let preroll = self.preroll.clone();
+        let is_prerolling = self.is_prerolling.clone();
         let is_recording = self.is_recording.clone();
         let is_monitoring = self.is_monitoring.clone();
         let is_paused = self.is_paused.clone();
         let level_milli = self.level_milli.clone();
+        let dsp_settings = self.dsp_settings.clone();
+        let profile = self.device_profile(&input.device_name);
+        let mut dsp = DspChain::new(
+            input.sample_rate,
+            profile.dsp.unwrap_or_else(|| self.current_dsp_settings()),
+        );
+        let mut processed = Vec::new();
+        let sink = Arc::new(PrerollSink::default());
+        let error_sink = sink.clone();
+        self.preroll_stream = Some((input.clone(), Arc::downgrade(&sink)));
+
+        let stream = self.backend.open_input(
+            &input,
+            profile.shaping(),
+            Box::new(move |data: &[f32]| {
+                // Held while pushing so no block falls between the pre-roll
+                // and a recording taking over.
+                let Ok(mut sink) = sink.lock() else {
+                    return;
+                };
+                if let Some((on_data, _)) = sink.as_mut() {
+                    on_data(data);
+                    return;
+                }
+                if is_prerolling.load(Ordering::SeqCst) {
+                    if let Ok(mut preroll) = preroll.lock() {
+                        preroll.push(data);
+                    }
+                }
+                drop(sink);
+                let recording = is_recording.load(Ordering::SeqCst);
+                let monitoring = is_monitoring.load(Ordering::SeqCst);
+                if (!recording && !monitoring) || (recording && is_paused.load(Ordering::SeqCst)) {
+                    return;
+                }
 
-        let stream = match sample_format {
-            cpal::SampleFormat::F32 => {
-                let is_recording_clone = is_recording.clone();
-                let is_monitoring_clone = is_monitoring.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[f32], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring_clone.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
-                            }
-
-                            let mut peak = 0.0f32;
-                            for &sample in data {
-                                peak = peak.max(sample.abs());
-                            }
-                            let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                            level_milli_clone.store(scaled, Ordering::SeqCst);
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            cpal::SampleFormat::I16 => {
-                let is_recording_clone = is_recording.clone();
-                let is_monitoring_clone = is_monitoring.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[i16], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring_clone.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
-                            }
-
-                            let mut peak = 0.0f32;
-                            for &sample in data {
-                                peak = peak.max((sample as f32 / i16::MAX as f32).abs());
-                            }
-                            let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                            level_milli_clone.store(scaled, Ordering::SeqCst);
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            cpal::SampleFormat::U16 => {
-                let is_recording_clone = is_recording.clone();
-                let is_monitoring_clone = is_monitoring.clone();
-                let is_paused_clone = is_paused.clone();
-                let level_milli_clone = level_milli.clone();
-                device
-                    .build_input_stream(
-                        &stream_config,
-                        move |data: &[u16], _| {
-                            let recording = is_recording_clone.load(Ordering::SeqCst);
-                            let monitoring = is_monitoring_clone.load(Ordering::SeqCst);
-                            if (!recording && !monitoring)
-                                || (recording && is_paused_clone.load(Ordering::SeqCst))
-                            {
-                                return;
-                            }
-
-                            let mut peak = 0.0f32;
-                            for &sample in data {
-                                let i16_sample = Self::i16_from_u16(sample);
-                                peak = peak.max((i16_sample as f32 / i16::MAX as f32).abs());
-                            }
-                            let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
-                            level_milli_clone.store(scaled, Ordering::SeqCst);
-                        },
-                        move |err| eprintln!("Stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| {
-                        normalize_input_error("Failed to open microphone input stream", e)
-                    })?
-            }
-            other => return Err(format!("Unsupported sample format: {other:?}")),
-        };
-
-        stream
-            .play()
-            .map_err(|err| normalize_input_error("Failed to start microphone stream", err))?;
+                processed.clear();
+                processed.extend_from_slice(data);
+                if let Ok(settings) = dsp_settings.lock() {
+                    dsp.set_settings(profile.dsp.unwrap_or(*settings));
+                }
+                dsp.process(&mut processed);
+                let peak = processed.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                let scaled = (peak.clamp(0.0, 1.0) * 1000.0).round() as u32;
+                level_milli.store(scaled, Ordering::SeqCst);
+            }),
+            Box::new(move |err| {
+                let mut sink = error_sink.lock().ok();
+                match sink.as_mut().and_then(|sink| sink.as_mut()) {
+                    Some((_, on_error)) => on_error(err),
+                    None => eprintln!("Stream error: {}", err),
+                }
+            }),
+        )?;
 
         self.stream = Some(stream);
-        self.stream2 = None;
-        self.writer = None;
-        self.current_filename = None;
-        self.is_paused.store(false, Ordering::SeqCst);
-        self.is_recording.store(false, Ordering::SeqCst);
-        self.is_monitoring.store(true, Ordering::SeqCst);
         Ok(())
     }
 
@@ -740,13 +1256,19 @@ impl AudioRecorder {
         self.is_monitoring.store(false, Ordering::SeqCst);
         self.is_paused.store(false, Ordering::SeqCst);
         self.level_milli.store(0, Ordering::SeqCst);
+        if self.is_prerolling() {
+            // The pre-roll keeps the stream open.
+            return Ok(());
+        }
 
         std::thread::sleep(std::time::Duration::from_millis(50));
 
         self.stream = None;
         self.stream2 = None;
         self.writer = None;
-        self.current_filename = None;
+        self.resampler = None;
+        self.desktop_writer = None;
+        self.clear_current_filename();
         Ok(())
     }
 
@@ -766,12 +1288,14 @@ impl AudioRecorder {
         Ok(())
     }
 
-    /// Returns true if recording was stopped externally (e.g. byte limit hit) and
+    /// Returns true if recording was stopped externally (e.g. no input device left) and
     /// the app should call stop_recording() to finalize and transcribe.
     pub fn was_stopped_externally(&self) -> bool {
         !self.is_recording.load(Ordering::SeqCst) && self.writer.is_some()
     }
 
+    /// Closes the WAV file and returns its path. Trimming and encoding it is
+    /// left to [`RecordingFinish`], off the UI thread.
     pub fn stop_recording(&mut self) -> Result<Option<String>, String> {
         let recording_active = self.is_recording.load(Ordering::SeqCst);
         let has_writer = self.writer.is_some();
@@ -789,11 +1313,40 @@ impl AudioRecorder {
 
         self.stream = None;
         self.stream2 = None;
-        if let Ok(mut q) = self.desktop_mix_buf.lock() {
-            q.clear();
+        if let Ok(mut buffer) = self.desktop_mix_buf.lock() {
+            buffer.clear();
         }
 
-        let completed_filename = self.current_filename.take();
+        let completed_filename = self.current_filename.lock().ok().and_then(|mut c| c.take());
+
+        // Write the few samples the resampler still holds back, padding the
+        // desktop track so both stay the same length.
+        if let (Some(resampler), Some(writer)) = (self.resampler.take(), self.writer.as_ref()) {
+            if let (Ok(mut resampler), Ok(mut writer)) = (resampler.lock(), writer.lock()) {
+                let mut tail = Vec::new();
+                resampler.finish(&mut tail);
+                let mut desktop_writer = self.desktop_writer.as_ref().and_then(|w| w.lock().ok());
+                for sample in tail {
+                    let _ = writer.write_sample(Self::i16_from_f32(sample));
+                    if self.recorded_track_layout == TrackLayout::Stereo {
+                        let _ = writer.write_sample(0i16);
+                    }
+                    if let Some(desktop_writer) = desktop_writer.as_mut() {
+                        let _ = desktop_writer.write_sample(0i16);
+                    }
+                }
+            }
+        }
+
+        if let Some(desktop_writer) = self.desktop_writer.take() {
+            let finalized = Arc::try_unwrap(desktop_writer)
+                .map_err(|_| "desktop track is still in use".to_string())
+                .and_then(|w| w.into_inner().map_err(|e| e.to_string()))
+                .and_then(|w| w.finalize().map_err(|e| e.to_string()));
+            if let Err(err) = finalized {
+                return Err(format!("Error finalizing desktop WAV file: {err}"));
+            }
+        }
 
         if let Some(writer_arc) = self.writer.take() {
             match Arc::try_unwrap(writer_arc) {
@@ -803,6 +1356,9 @@ impl AudioRecorder {
                             return Err(format!("Error finalizing WAV file: {err}"));
                         } else {
                             println!("Recording saved successfully");
+                            if let Some(path) = completed_filename.as_ref() {
+                                recovery::mark_finished(std::path::Path::new(path));
+                            }
                         }
                     }
                     Err(err) => {
@@ -818,35 +1374,81 @@ impl AudioRecorder {
             }
         }
 
-        // Convert WAV to M4A (AAC) to drastically reduce file size.
-        // afconvert is built into macOS; Groq accepts m4a.
-        if let Some(ref wav_path) = completed_filename {
-            let m4a_path = wav_path.replace(".wav", ".m4a");
-            let status = std::process::Command::new("afconvert")
-                .args([
-                    "-f", "m4af", "-d", "aac", "-b", "32000", "-c", "1", wav_path, &m4a_path,
-                ])
-                .status();
-            match status {
-                Ok(s) if s.success() => {
-                    let _ = std::fs::remove_file(wav_path);
-                    println!("Converted to M4A: {}", m4a_path);
-                    return Ok(Some(m4a_path));
-                }
-                Ok(s) => eprintln!("afconvert failed with status: {}", s),
-                Err(e) => eprintln!("afconvert not available: {}", e),
-            }
+        if let Err(err) = self.start_preroll() {
+            eprintln!("Pre-roll unavailable: {err}");
         }
 
         Ok(completed_filename)
     }
+
+    fn clear_current_filename(&self) {
+        if let Ok(mut current) = self.current_filename.lock() {
+            *current = None;
+        }
+    }
+
+    /// The first segment's path, from which later segments are named.
+    fn first_segment(path: &str) -> PathBuf {
+        let path = Path::new(path);
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match stem.rsplit_once("_part") {
+            Some((base, n)) if n.parse::<u32>().is_ok() => {
+                path.with_file_name(format!("{base}.wav"))
+            }
+            _ => path.to_path_buf(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synthetic::{Signal, SyntheticBackend};
     use std::sync::atomic::Ordering;
 
+    #[test]
+    fn failover_prefers_configured_then_default() {
+        let available = [
+            "Built-in".to_string(),
+            "USB".to_string(),
+            "Webcam".to_string(),
+        ];
+        assert_eq!(
+            failover_order("USB", Some("USB"), Some("Built-in"), &available),
+            vec!["Built-in", "Webcam"]
+        );
+        // A configured device that came back is tried first.
+        assert_eq!(
+            failover_order("Webcam", Some("USB"), Some("Built-in"), &available),
+            vec!["USB", "Built-in"]
+        );
+        // Unplugged devices are not listed anymore and are skipped.
+        assert_eq!(
+            failover_order("USB", Some("USB"), Some("USB"), &available[..1]),
+            vec!["Built-in"]
+        );
+    }
+
+    #[test]
+    fn only_a_missing_device_counts_as_lost() {
+        let mut health = StreamHealth::default();
+        health.report(
+            false,
+            &cpal::StreamError::BackendSpecific {
+                err: cpal::BackendSpecificError {
+                    description: "xrun".to_string(),
+                },
+            },
+        );
+        assert!(!health.input_lost);
+        assert!(health.last_error.is_some());
+        health.report(true, &cpal::StreamError::DeviceNotAvailable);
+        assert!(health.desktop_lost && !health.input_lost);
+    }
+
     #[test]
     fn i16_from_f32_maps_positive_one_to_max() {
         assert_eq!(AudioRecorder::i16_from_f32(1.0), i16::MAX);
@@ -878,26 +1480,6 @@ mod tests {
         assert_eq!(clamped, at_neg_one);
     }
 
-    #[test]
-    fn i16_from_u16_midpoint_maps_near_zero() {
-        // u16 midpoint is 32768; subtracting i16::MAX+1 (32768) = 0
-        let result = AudioRecorder::i16_from_u16(32768u16);
-        assert_eq!(result, 0);
-    }
-
-    #[test]
-    fn i16_from_u16_zero_maps_to_negative_max() {
-        // 0 - 32768 = -32768 which wraps to i16::MIN in i16
-        let result = AudioRecorder::i16_from_u16(0u16);
-        assert!(result < 0);
-    }
-
-    #[test]
-    fn i16_from_u16_max_maps_to_positive() {
-        let result = AudioRecorder::i16_from_u16(u16::MAX);
-        assert!(result > 0);
-    }
-
     // ── atomic state flags ───────────────────────────────────────────────────
 
     #[test]
@@ -959,6 +1541,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn segments_are_named_after_the_first_file() {
+        let first = Path::new("/rec/recording_7.wav");
+        assert_eq!(segment_path(first, 0), first);
+        let third = segment_path(first, 2);
+        assert_eq!(third, Path::new("/rec/recording_7_part3.wav"));
+        assert_eq!(
+            AudioRecorder::first_segment(&third.to_string_lossy()),
+            first
+        );
+    }
+
+    #[test]
+    fn segment_size_is_capped() {
+        let mut rec = AudioRecorder::new();
+        rec.set_segment_limits(5000, 0);
+        assert_eq!(rec.segment_max_bytes, MAX_RECORDING_BYTES);
+        rec.set_segment_limits(0, 0);
+        assert_eq!(rec.segment_max_bytes, MAX_RECORDING_BYTES);
+        rec.set_segment_limits(10, 30);
+        assert_eq!(rec.segment_max_bytes, 10 * 1024 * 1024);
+        assert_eq!(rec.segment_max_secs, 30 * 60);
+    }
+
+    #[test]
+    fn rotating_keeps_every_sample() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let first = tmp.path().join("recording_1.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&first, spec).unwrap();
+        let second = segment_path(&first, 1);
+        for i in 0..2_000 {
+            if i == 1_200 {
+                rotate_segment(&mut writer, None, &second, TrackLayout::Mixed).unwrap();
+            }
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        recovery::mark_finished(&second);
+
+        let read = |path: &Path| -> Vec<i16> {
+            hound::WavReader::open(path)
+                .unwrap()
+                .samples::<i16>()
+                .map(|s| s.unwrap())
+                .collect()
+        };
+        let joined = [read(&first), read(&second)].concat();
+        assert_eq!(joined, (0..2_000).map(|i| i as i16).collect::<Vec<_>>());
+    }
+
     // ── save_transcription builds a well-formed TranscriptionRecord ──────────
     // Rather than touching the file system (which races with parallel tests
     // via the XDG_DATA_HOME env var), we verify the record fields directly.
@@ -972,6 +1610,13 @@ mod tests {
                 .unwrap_or_default()
                 .as_secs(),
             audio_path: None,
+            route: None,
+            suspicious: None,
+            translation: None,
+            translation_language: None,
+            track_layout: TrackLayout::Mixed,
+            quality: None,
+            segments: Vec::new(),
         };
         assert_eq!(record.filename, "rec.wav");
         assert_eq!(record.transcription, "hello world");
@@ -999,4 +1644,323 @@ mod tests {
         rec.set_desktop_device_name(Some("BlackHole 2ch".to_string()));
         assert_eq!(rec.desktop_device_name, Some("BlackHole 2ch".to_string()));
     }
+
+    // ── end to end, on the synthetic backend ─────────────────────────────────
+
+    /// A recorder with a 440 Hz microphone at 48 kHz and a 1 kHz desktop
+    /// source at 44.1 kHz, writing WAV files into `dir`.
+    fn synthetic_recorder(dir: &Path) -> (Arc<SyntheticBackend>, AudioRecorder) {
+        let backend = Arc::new(SyntheticBackend::new());
+        backend.add_tone("Mic", 48_000, 440.0, 0.5);
+        backend.add_tone("Desktop", 44_100, 1_000.0, 0.5);
+        let mut rec = AudioRecorder::with_backend(backend.clone());
+        rec.set_recordings_dir(dir.to_path_buf());
+        rec.set_device_name(Some("Mic".to_string()));
+        rec.set_desktop_device_name(Some("Desktop".to_string()));
+        rec.set_recording_format(RecordingFormat::Wav);
+        (backend, rec)
+    }
+
+    /// Samples of each channel of a 16-bit WAV file.
+    fn read_channels(path: &str) -> (WavSpec, Vec<Vec<f32>>) {
+        let mut reader = hound::WavReader::open(path).expect("open recording");
+        let spec = reader.spec();
+        let mut channels = vec![Vec::new(); spec.channels as usize];
+        for (i, sample) in reader.samples::<i16>().enumerate() {
+            channels[i % spec.channels as usize].push(sample.unwrap() as f32 / i16::MAX as f32);
+        }
+        (spec, channels)
+    }
+
+    /// Amplitude of the `frequency` component of `samples`.
+    fn tone_level(samples: &[f32], rate: u32, frequency: f32) -> f32 {
+        let (mut re, mut im) = (0.0f64, 0.0f64);
+        for (i, &s) in samples.iter().enumerate() {
+            let phase = 2.0 * std::f64::consts::PI * frequency as f64 * i as f64 / rate as f64;
+            re += s as f64 * phase.cos();
+            im += s as f64 * phase.sin();
+        }
+        (2.0 * (re * re + im * im).sqrt() / samples.len() as f64) as f32
+    }
+
+    /// A tenth of a second from one second before the end, past any edges.
+    fn steady(samples: &[f32]) -> &[f32] {
+        let end = samples.len() - 16_000;
+        &samples[end - 1_600..end]
+    }
+
+    #[test]
+    fn paused_time_is_left_out_of_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.start_recording().unwrap();
+        assert!(rec.is_recording());
+        backend.advance(1.5);
+        rec.pause_recording().unwrap();
+        backend.advance(1.0);
+        rec.resume_recording().unwrap();
+        backend.advance(1.5);
+
+        let path = rec.stop_recording().unwrap().expect("recording path");
+        assert!(!rec.is_recording());
+        assert!(backend.open_streams().is_empty());
+        let (spec, channels) = read_channels(&path);
+        assert_eq!((spec.channels, spec.sample_rate), (1, 16_000));
+        let len = channels[0].len() as i64;
+        assert!((len - 48_000).abs() < 100, "{len} samples");
+        let level = tone_level(steady(&channels[0]), 16_000, 440.0);
+        assert!((level - 0.5).abs() < 0.02, "{level}");
+    }
+
+    #[test]
+    fn preroll_stream_carries_on_into_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        let ramp = |i: usize| (i % 1_000) as f32 / 1_000.0 - 0.5;
+        backend.add_device(
+            "Ramp",
+            16_000,
+            Signal::Samples((0..48_000).map(ramp).collect()),
+        );
+        rec.set_device_name(Some("Ramp".to_string()));
+        rec.set_preroll_secs(1.0);
+        backend.advance(1.5);
+        rec.start_recording().unwrap();
+        assert_eq!(backend.open_streams(), vec!["Ramp"]);
+        backend.advance(1.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        // A reopened device would start the ramp over after the pre-roll.
+        let samples = &read_channels(&path).1[0];
+        assert_eq!(samples.len(), 32_000);
+        for (i, &sample) in samples.iter().enumerate() {
+            assert!(
+                (sample - ramp(8_000 + i)).abs() < 1e-3,
+                "sample {i}: {sample}"
+            );
+        }
+    }
+
+    #[test]
+    fn quality_is_measured_before_gain_control() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        backend.add_tone("Quiet", 16_000, 440.0, 0.012);
+        rec.set_device_name(Some("Quiet".to_string()));
+        rec.set_dsp_settings(DspSettings {
+            agc: true,
+            ..DspSettings::default()
+        });
+        rec.start_recording().unwrap();
+        backend.advance(5.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let recorded = steady(&read_channels(&path).1[0]).to_vec();
+        let peak = recorded.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.05, "gain control left the recording at {peak}");
+        let quality = rec.recording_quality().expect("quality");
+        assert!(quality.issues().contains(&"very quiet"), "{quality:?}");
+    }
+
+    #[test]
+    fn stopping_leaves_encoding_to_the_finish_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_recording_format(RecordingFormat::Opus);
+        rec.start_recording().unwrap();
+        backend.advance(1.0);
+        let wav = rec.stop_recording().unwrap().unwrap();
+        assert!(wav.ends_with(".wav"), "{wav}");
+
+        let finish = rec.recording_finish();
+        assert_eq!(finish.format, RecordingFormat::Opus);
+        let encoded = finish.apply(&wav);
+        assert!(encoded.ends_with(".ogg"), "{encoded}");
+        assert!(Path::new(&encoded).exists());
+        assert!(!Path::new(&wav).exists());
+    }
+
+    #[test]
+    fn wav_input_is_recorded_sample_for_sample() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("speech.wav");
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&source, spec).unwrap();
+        let input: Vec<i16> = (0..16_000)
+            .map(|i| ((i * 37) % 20_000 - 10_000) as i16)
+            .collect();
+        for &sample in &input {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        backend.add_wav("File", &source).unwrap();
+        rec.set_device_name(Some("File".to_string()));
+        rec.start_recording().unwrap();
+        backend.advance(1.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let recorded: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(recorded.len(), input.len());
+        assert!(recorded
+            .iter()
+            .zip(&input)
+            .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1));
+    }
+
+    #[test]
+    fn mic_and_desktop_are_mixed_with_their_gains() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_audio_source(AudioSource::MicAndDesktop);
+        rec.set_mix_gains(0.8, 0.4);
+        rec.start_recording().unwrap();
+        assert_eq!(backend.open_streams(), vec!["Mic", "Desktop"]);
+        backend.advance(3.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let (spec, channels) = read_channels(&path);
+        assert_eq!(spec.channels, 1);
+        let mixed = steady(&channels[0]);
+        let mic = tone_level(mixed, 16_000, 440.0);
+        let desktop = tone_level(mixed, 16_000, 1_000.0);
+        assert!((mic - 0.4).abs() < 0.02, "mic {mic}");
+        assert!((desktop - 0.2).abs() < 0.02, "desktop {desktop}");
+    }
+
+    #[test]
+    fn stereo_layout_keeps_mic_left_and_desktop_right() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_audio_source(AudioSource::MicAndDesktop);
+        rec.set_track_layout(TrackLayout::Stereo);
+        rec.start_recording().unwrap();
+        backend.advance(3.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let (spec, channels) = read_channels(&path);
+        assert_eq!(spec.channels, 2);
+        let (left, right) = (steady(&channels[0]), steady(&channels[1]));
+        assert!((tone_level(left, 16_000, 440.0) - 0.5).abs() < 0.02);
+        assert!(tone_level(left, 16_000, 1_000.0) < 0.01);
+        assert!((tone_level(right, 16_000, 1_000.0) - 0.5).abs() < 0.02);
+        assert!(tone_level(right, 16_000, 440.0) < 0.01);
+    }
+
+    #[test]
+    fn full_segments_continue_in_a_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_segment_limits(1, 0);
+        rec.start_recording().unwrap();
+        backend.advance(40.0);
+        assert_eq!(rec.segment_number(), 2);
+        let finished = rec.take_finished_segments();
+        assert_eq!(finished.len(), 1);
+        let path = rec.stop_recording().unwrap().unwrap();
+        assert!(path.ends_with("_part2.wav"), "{path}");
+
+        // 1 MB of 16-bit samples, rounded up to the block that filled it.
+        let first = read_channels(&finished[0]).1[0].len();
+        assert!((524_288..524_288 + 160).contains(&first), "{first}");
+        let second = read_channels(&path).1[0].len();
+        assert!(((first + second) as i64 - 640_000).abs() < 100);
+    }
+
+    #[test]
+    fn unplugged_microphone_fails_over_and_keeps_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        backend.add_tone("USB", 16_000, 300.0, 0.5);
+        rec.set_device_name(Some("USB".to_string()));
+        rec.start_recording().unwrap();
+        backend.advance(1.0);
+        backend.unplug("USB");
+
+        let message = rec.check_streams().expect("failover message");
+        assert!(message.contains("continues on 'Mic'"), "{message}");
+        assert!(rec.is_recording());
+        backend.advance(2.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let samples = &read_channels(&path).1[0];
+        assert!(((samples.len() as i64) - 48_000).abs() < 100);
+        assert!(tone_level(&samples[1_600..3_200], 16_000, 300.0) > 0.45);
+        assert!(tone_level(steady(samples), 16_000, 440.0) > 0.45);
+    }
+
+    #[test]
+    fn unplugged_desktop_stops_a_desktop_only_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let (backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_audio_source(AudioSource::DesktopOnly);
+        rec.start_recording().unwrap();
+        backend.advance(1.0);
+        backend.unplug("Desktop");
+
+        let message = rec.check_streams().expect("failover message");
+        assert!(message.contains("Recording stopped"), "{message}");
+        assert!(!rec.is_recording());
+        assert!(backend.open_streams().is_empty());
+        backend.advance(1.0);
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let samples = &read_channels(&path).1[0];
+        assert!(((samples.len() as i64) - 16_000).abs() < 100);
+        let desktop = &samples[4_000..5_600];
+        assert!(tone_level(desktop, 16_000, 1_000.0) > 0.45);
+        assert!(tone_level(desktop, 16_000, 440.0) < 0.01);
+    }
+
+    #[test]
+    fn piped_audio_is_recorded_until_the_pipe_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.f32");
+        // One second of 48 kHz stereo with a tone on the left only.
+        let bytes: Vec<u8> = (0..48_000)
+            .flat_map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48_000.0;
+                [phase.sin() * 0.5, 0.0]
+            })
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        std::fs::write(&input, bytes).unwrap();
+
+        let (_backend, mut rec) = synthetic_recorder(dir.path());
+        rec.set_audio_source(AudioSource::Pipe);
+        rec.set_pipe_settings(PipeSettings {
+            path: input.to_string_lossy().to_string(),
+            format: crate::pipe::PipeFormat::F32Le,
+            sample_rate: 48_000,
+            channels: 2,
+        });
+        rec.start_recording().unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let message = loop {
+            if let Some(message) = rec.check_streams() {
+                break message;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the pipe never closed"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert!(message.contains("was closed"), "{message}");
+        assert!(rec.was_stopped_externally());
+        let path = rec.stop_recording().unwrap().unwrap();
+
+        let samples = &read_channels(&path).1[0];
+        assert!(((samples.len() as i64) - 16_000).abs() < 100);
+        // The two channels are averaged.
+        let level = tone_level(&samples[4_000..5_600], 16_000, 440.0);
+        assert!((level - 0.25).abs() < 0.02, "{level}");
+    }
 }
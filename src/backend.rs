@@ -0,0 +1,262 @@
+use crate::capture::{build_mono_input_stream, InputShaping};
+use crate::loopback;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::any::Any;
+
+/// Receives mono blocks from an input, on the backend's audio thread.
+pub type DataCallback = Box<dyn FnMut(&[f32]) + Send>;
+pub type ErrorCallback = Box<dyn FnMut(cpal::StreamError) + Send>;
+/// Keeps an input running until it is dropped.
+pub type InputStream = Box<dyn Any>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceRole {
+    Microphone,
+    /// Desktop devices may also be loopback monitor sources.
+    Desktop,
+}
+
+impl DeviceRole {
+    fn label(self) -> &'static str {
+        match self {
+            DeviceRole::Microphone => "microphone",
+            DeviceRole::Desktop => "desktop audio",
+        }
+    }
+}
+
+/// An input picked by [`AudioBackend::input_config`] and the rate it runs at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputConfig {
+    pub role: DeviceRole,
+    /// Name the device profile and failover refer to.
+    pub device_name: String,
+    pub sample_rate: u32,
+    /// Monitor source recorded with `parec` instead of a cpal device.
+    pub(crate) monitor: Option<String>,
+}
+
+/// Where recordings get their audio from. The recorder only opens inputs
+/// through this, so tests can run it without a sound card.
+pub trait AudioBackend: Send + Sync {
+    fn input_device_names(&self) -> Result<Vec<String>, String>;
+
+    fn default_input_name(&self) -> Option<String>;
+
+    /// Loopback sources that carry only desktop audio, never a microphone.
+    fn monitor_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Picks `name`, or the default input if it is unset or gone, running at
+    /// `preferred_rate` if the device supports it and at its own rate otherwise.
+    fn input_config(
+        &self,
+        role: DeviceRole,
+        name: &Option<String>,
+        preferred_rate: Option<u32>,
+    ) -> Result<InputConfig, String>;
+
+    /// Starts delivering mono audio at `config.sample_rate` to `on_data`.
+    fn open_input(
+        &self,
+        config: &InputConfig,
+        shaping: InputShaping,
+        on_data: DataCallback,
+        on_error: ErrorCallback,
+    ) -> Result<InputStream, String>;
+}
+
+pub fn normalize_input_error(context: &str, err: impl std::fmt::Display) -> String {
+    let details = err.to_string();
+    let lower = details.to_ascii_lowercase();
+
+    let permission_related = [
+        "permission",
+        "not authorized",
+        "not permitted",
+        "access denied",
+        "operation not permitted",
+        "unauthorized",
+        "forbidden",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle));
+
+    if permission_related {
+        return format!(
+            "Microphone access is blocked. Enable microphone access for this app in system privacy settings, then retry. {context}. Details: {details}"
+        );
+    }
+
+    format!("{context}. Details: {details}")
+}
+
+/// A config of `device` running at `rate`, or `fallback` if it has none.
+fn input_config_at_rate(
+    device: &cpal::Device,
+    rate: u32,
+    fallback: cpal::SupportedStreamConfig,
+) -> cpal::SupportedStreamConfig {
+    let rate = cpal::SampleRate(rate);
+    device
+        .supported_input_configs()
+        .ok()
+        .and_then(|mut cfgs| {
+            cfgs.find(|c| c.min_sample_rate() <= rate && c.max_sample_rate() >= rate)
+                .map(|c| c.with_sample_rate(rate))
+        })
+        .unwrap_or(fallback)
+}
+
+/// The system's audio devices.
+pub struct CpalBackend;
+
+impl CpalBackend {
+    fn find_device(name: &Option<String>, role: DeviceRole) -> Result<cpal::Device, String> {
+        let host = cpal::default_host();
+        let role = role.label();
+
+        if let Some(device_name) = name {
+            let found_device = host.input_devices().ok().and_then(|mut devices| {
+                devices.find(|d| d.name().is_ok_and(|name| name == *device_name))
+            });
+
+            match found_device {
+                Some(device) => {
+                    println!("Using selected {role} device: {device_name}");
+                    Ok(device)
+                }
+                None => {
+                    eprintln!("Selected {role} device '{device_name}' not found, using default");
+                    host.default_input_device()
+                        .ok_or_else(|| "No input device available".to_string())
+                }
+            }
+        } else {
+            match host.default_input_device() {
+                Some(device) => {
+                    if let Ok(name) = device.name() {
+                        println!("Using default {role} device: {name}");
+                    }
+                    Ok(device)
+                }
+                None => Err("No input device available".to_string()),
+            }
+        }
+    }
+
+    fn stream_config(
+        device: &cpal::Device,
+        role: DeviceRole,
+        rate: Option<u32>,
+    ) -> Result<cpal::SupportedStreamConfig, String> {
+        let supported = device.default_input_config().map_err(|err| {
+            normalize_input_error(
+                &format!("Failed to get default {} configuration", role.label()),
+                err,
+            )
+        })?;
+        Ok(match rate {
+            Some(rate) => input_config_at_rate(device, rate, supported),
+            None => supported,
+        })
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn input_device_names(&self) -> Result<Vec<String>, String> {
+        let devices: Vec<_> = cpal::default_host()
+            .input_devices()
+            .map_err(|e| normalize_input_error("Failed to enumerate input devices", e))?
+            .collect();
+
+        if devices.is_empty() {
+            return Err("No input devices available".to_string());
+        }
+        Ok(devices.into_iter().filter_map(|d| d.name().ok()).collect())
+    }
+
+    fn default_input_name(&self) -> Option<String> {
+        cpal::default_host()
+            .default_input_device()
+            .and_then(|d| d.name().ok())
+    }
+
+    fn monitor_names(&self) -> Vec<String> {
+        loopback::monitor_sources()
+            .iter()
+            .map(|s| s.label())
+            .collect()
+    }
+
+    fn input_config(
+        &self,
+        role: DeviceRole,
+        name: &Option<String>,
+        preferred_rate: Option<u32>,
+    ) -> Result<InputConfig, String> {
+        let monitor = match role {
+            DeviceRole::Desktop => loopback::resolve(name)?,
+            DeviceRole::Microphone => None,
+        };
+        let (device_name, sample_rate) = match &monitor {
+            Some(source) => (
+                name.clone().unwrap_or_else(|| source.clone()),
+                preferred_rate.unwrap_or(loopback::MONITOR_RATE),
+            ),
+            None => {
+                let device = Self::find_device(name, role)?;
+                let device_name = device
+                    .name()
+                    .ok()
+                    .or_else(|| name.clone())
+                    .unwrap_or_default();
+                let config = Self::stream_config(&device, role, preferred_rate)?;
+                println!(
+                    "Audio config: {} channels, {} Hz, {:?}",
+                    config.channels(),
+                    config.sample_rate().0,
+                    config.sample_format()
+                );
+                (device_name, config.sample_rate().0)
+            }
+        };
+        Ok(InputConfig {
+            role,
+            device_name,
+            sample_rate,
+            monitor,
+        })
+    }
+
+    fn open_input(
+        &self,
+        config: &InputConfig,
+        shaping: InputShaping,
+        on_data: DataCallback,
+        on_error: ErrorCallback,
+    ) -> Result<InputStream, String> {
+        let role = config.role;
+        if let Some(source) = &config.monitor {
+            return loopback::open_monitor(source, config.sample_rate, shaping, on_data, on_error);
+        }
+        let device = Self::find_device(&Some(config.device_name.clone()), role)?;
+        let supported = Self::stream_config(&device, role, Some(config.sample_rate))?;
+        let stream = build_mono_input_stream(
+            &device,
+            &supported.config(),
+            supported.sample_format(),
+            shaping,
+            on_data,
+            on_error,
+        )
+        .map_err(|e| {
+            normalize_input_error(&format!("Failed to open {} stream", role.label()), e)
+        })?;
+        stream.play().map_err(|e| {
+            normalize_input_error(&format!("Failed to start {} stream", role.label()), e)
+        })?;
+        Ok(Box::new(stream))
+    }
+}
@@ -0,0 +1,275 @@
+use cpal::traits::DeviceTrait;
+use cpal::{FromSample, Sample, SizedSample};
+
+/// Averages interleaved frames of any sample format into mono f32 in
+/// `-1.0..=1.0`. `out` is cleared first so the callback can reuse it.
+pub fn mix_to_mono<T>(data: &[T], channels: usize, out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    out.clear();
+    out.extend(data.chunks(channels.max(1)).map(|frame| {
+        let sum: f32 = frame.iter().map(|&s| s.to_sample::<f32>()).sum();
+        (sum / frame.len() as f32).clamp(-1.0, 1.0)
+    }));
+}
+
+/// Per-device adjustments made before anything else sees the input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputShaping {
+    /// Keep only this channel (0-based); `None` averages all of them.
+    pub channel: Option<u16>,
+    /// Linear gain.
+    pub gain: f32,
+}
+
+impl Default for InputShaping {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            gain: 1.0,
+        }
+    }
+}
+
+/// [`mix_to_mono`] with the channel selection and gain of `shaping`. A
+/// channel the device doesn't have falls back to averaging.
+pub fn shape_to_mono<T>(data: &[T], channels: usize, shaping: InputShaping, out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    match shaping.channel.map(usize::from).filter(|&c| c < channels) {
+        Some(channel) => {
+            out.clear();
+            out.extend(
+                data.chunks(channels)
+                    .filter_map(|frame| frame.get(channel))
+                    .map(|&s| s.to_sample::<f32>()),
+            );
+        }
+        None => mix_to_mono(data, channels, out),
+    }
+    if shaping.gain != 1.0 {
+        for sample in out.iter_mut() {
+            *sample = (*sample * shaping.gain).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+fn build_typed_stream<T, D, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    shaping: InputShaping,
+    mut on_data: D,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    D: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    let channels = config.channels as usize;
+    let mut mono = Vec::new();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                shape_to_mono(data, channels, shaping, &mut mono);
+                on_data(&mono);
+            },
+            on_error,
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Opens an input stream in whatever sample format the device delivers and
+/// hands `on_data` mono f32 frames. Recording, the microphone test and
+/// desktop capture all go through here.
+pub fn build_mono_input_stream<D, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    shaping: InputShaping,
+    on_data: D,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    D: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    use cpal::SampleFormat as F;
+    match sample_format {
+        F::I8 => build_typed_stream::<i8, _, _>(device, config, shaping, on_data, on_error),
+        F::I16 => build_typed_stream::<i16, _, _>(device, config, shaping, on_data, on_error),
+        F::I24 => build_typed_stream::<cpal::I24, _, _>(device, config, shaping, on_data, on_error),
+        F::I32 => build_typed_stream::<i32, _, _>(device, config, shaping, on_data, on_error),
+        F::I64 => build_typed_stream::<i64, _, _>(device, config, shaping, on_data, on_error),
+        F::U8 => build_typed_stream::<u8, _, _>(device, config, shaping, on_data, on_error),
+        F::U16 => build_typed_stream::<u16, _, _>(device, config, shaping, on_data, on_error),
+        F::U32 => build_typed_stream::<u32, _, _>(device, config, shaping, on_data, on_error),
+        F::U64 => build_typed_stream::<u64, _, _>(device, config, shaping, on_data, on_error),
+        F::F32 => build_typed_stream::<f32, _, _>(device, config, shaping, on_data, on_error),
+        F::F64 => build_typed_stream::<f64, _, _>(device, config, shaping, on_data, on_error),
+        other => Err(format!("Unsupported sample format: {other:?}")),
+    }
+}
+
+/// Writes one mono sample from `next` into every channel of each frame.
+pub fn fill_frames<T, N>(data: &mut [T], channels: usize, next: &mut N)
+where
+    T: Sample + FromSample<f32>,
+    N: FnMut() -> f32,
+{
+    for frame in data.chunks_mut(channels.max(1)) {
+        let sample = T::from_sample(next().clamp(-1.0, 1.0));
+        frame.fill(sample);
+    }
+}
+
+fn build_typed_output_stream<T, N, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut next: N,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+    N: FnMut() -> f32 + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| fill_frames(data, channels, &mut next),
+            on_error,
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Opens an output stream in the device's sample format and plays the mono
+/// samples returned by `next` on all channels.
+pub fn build_mono_output_stream<N, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    next: N,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    N: FnMut() -> f32 + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    use cpal::SampleFormat as F;
+    match sample_format {
+        F::I8 => build_typed_output_stream::<i8, _, _>(device, config, next, on_error),
+        F::I16 => build_typed_output_stream::<i16, _, _>(device, config, next, on_error),
+        F::I24 => build_typed_output_stream::<cpal::I24, _, _>(device, config, next, on_error),
+        F::I32 => build_typed_output_stream::<i32, _, _>(device, config, next, on_error),
+        F::I64 => build_typed_output_stream::<i64, _, _>(device, config, next, on_error),
+        F::U8 => build_typed_output_stream::<u8, _, _>(device, config, next, on_error),
+        F::U16 => build_typed_output_stream::<u16, _, _>(device, config, next, on_error),
+        F::U32 => build_typed_output_stream::<u32, _, _>(device, config, next, on_error),
+        F::U64 => build_typed_output_stream::<u64, _, _>(device, config, next, on_error),
+        F::F32 => build_typed_output_stream::<f32, _, _>(device, config, next, on_error),
+        F::F64 => build_typed_output_stream::<f64, _, _>(device, config, next, on_error),
+        other => Err(format!("Unsupported sample format: {other:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mono<T>(data: &[T], channels: usize) -> Vec<f32>
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let mut out = Vec::new();
+        mix_to_mono(data, channels, &mut out);
+        out
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-2, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn signed_formats_map_to_unit_range() {
+        assert_close(&mono(&[i8::MIN, 0, i8::MAX], 1), &[-1.0, 0.0, 1.0]);
+        assert_close(&mono(&[i16::MIN, 0, i16::MAX], 1), &[-1.0, 0.0, 1.0]);
+        assert_close(&mono(&[i32::MIN, 0, i32::MAX], 1), &[-1.0, 0.0, 1.0]);
+        assert_close(&mono(&[i64::MIN, 0, i64::MAX], 1), &[-1.0, 0.0, 1.0]);
+        let i24 = [
+            cpal::I24::new(-(1 << 23)).unwrap(),
+            cpal::I24::new(0).unwrap(),
+            cpal::I24::new((1 << 23) - 1).unwrap(),
+        ];
+        assert_close(&mono(&i24, 1), &[-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn unsigned_formats_are_centered() {
+        assert_close(&mono(&[0u8, 128, u8::MAX], 1), &[-1.0, 0.0, 1.0]);
+        assert_close(&mono(&[0u16, 32768, u16::MAX], 1), &[-1.0, 0.0, 1.0]);
+        assert_close(&mono(&[0u32, 1 << 31, u32::MAX], 1), &[-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn float_formats_are_clamped() {
+        assert_close(&mono(&[0.5f32, 1.5, -2.0], 1), &[0.5, 1.0, -1.0]);
+        assert_close(&mono(&[0.25f64, -0.25], 1), &[0.25, -0.25]);
+    }
+
+    #[test]
+    fn frames_are_averaged_across_channels() {
+        assert_close(&mono(&[1.0f32, 0.0, 0.5, 0.5], 2), &[0.5, 0.5]);
+        assert_close(&mono(&[i16::MAX, i16::MIN, 0, 0], 2), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn output_buffer_is_reused() {
+        let mut out = vec![9.0; 8];
+        mix_to_mono(&[0.0f32, 0.0, 0.0], 3, &mut out);
+        assert_eq!(out, vec![0.0]);
+    }
+
+    #[test]
+    fn shaping_selects_a_channel_and_applies_gain() {
+        let frames = [0.1f32, 0.4, 0.2, 0.6];
+        let mut out = Vec::new();
+        let right = InputShaping {
+            channel: Some(1),
+            gain: 2.0,
+        };
+        shape_to_mono(&frames, 2, right, &mut out);
+        assert_close(&out, &[0.8, 1.0]);
+
+        let missing = InputShaping {
+            channel: Some(4),
+            gain: 1.0,
+        };
+        shape_to_mono(&frames, 2, missing, &mut out);
+        assert_close(&out, &[0.25, 0.4]);
+    }
+
+    #[test]
+    fn playback_fills_every_channel() {
+        let mut samples = [0.5f32, -2.0].into_iter();
+        let mut next = || samples.next().unwrap_or(0.0);
+        let mut data = [0i16; 6];
+        fill_frames(&mut data, 2, &mut next);
+        assert_eq!(data[0], data[1]);
+        assert!(data[0] > 16_000);
+        assert_eq!(&data[2..], &[i16::MIN, i16::MIN, 0, 0]);
+    }
+}
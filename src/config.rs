@@ -1,8 +1,64 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::audio_recorder::AudioSource;
+use crate::audio_recorder::{AudioSource, DEFAULT_SEGMENT_MAX_MB};
+use crate::capture::InputShaping;
+use crate::dsp::DspSettings;
+use crate::encoder::RecordingFormat;
+use crate::mixer::DEFAULT_MIX_GAIN;
+use crate::pipe::{PipeSettings, MAX_PIPE_CHANNELS};
+use crate::preroll::MAX_PREROLL_SECS;
+use crate::prompt_context::{
+    PromptSource, DEFAULT_PROMPT_RECENT_SECS, DEFAULT_PROMPT_TOKEN_BUDGET,
+};
+use crate::replay::REPLAY_MINUTES;
+use crate::resampler::DEFAULT_SAMPLE_RATE;
+use crate::routing::{RoutingRule, DEFAULT_TRANSCRIPTION_MODEL};
+use crate::tracks::TrackLayout;
+use crate::translation::{TranslationLayout, DEFAULT_TRANSLATION_MODEL, GROQ_CHAT_COMPLETIONS_URL};
+
+/// A named set of preferences the user can switch between (e.g. "Work", "Private").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Overrides the global `inverse_text_normalization` switch.
+    #[serde(default)]
+    pub inverse_text_normalization: Option<bool>,
+    /// Translate every transcript made with this profile into this language.
+    #[serde(default)]
+    pub translate_to: Option<String>,
+}
+
+/// Input gain range offered for device profiles, in dB.
+pub const MIN_INPUT_GAIN_DB: f32 = -20.0;
+pub const MAX_INPUT_GAIN_DB: f32 = 30.0;
+
+/// Settings for one input device, applied whenever a recording, the
+/// microphone test or the pre-roll opens it.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceProfile {
+    pub gain_db: f32,
+    /// Record only this channel (0-based) instead of averaging all of them.
+    pub channel: Option<u16>,
+    /// Replaces the global `dsp` settings while this device is recording.
+    pub dsp: Option<DspSettings>,
+}
+
+impl DeviceProfile {
+    pub fn shaping(&self) -> InputShaping {
+        InputShaping {
+            channel: self.channel,
+            gain: 10f32.powf(self.gain_db / 20.0),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -19,6 +75,94 @@ pub struct AppConfig {
     pub show_window_shortcut: String,
     pub minimize_on_stop: bool,
     pub hold_to_record_key: Option<String>,
+    #[serde(default)]
+    pub transcription_model: String,
+    #[serde(default)]
+    pub local_endpoint: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub routing_rules: Vec<RoutingRule>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    #[serde(default = "default_true")]
+    pub suppress_hallucinations: bool,
+    #[serde(default)]
+    pub prompt_source: PromptSource,
+    #[serde(default)]
+    pub prompt_token_budget: usize,
+    #[serde(default)]
+    pub prompt_recent_secs: u64,
+    #[serde(default)]
+    pub inverse_text_normalization: bool,
+    #[serde(default)]
+    pub translate_shortcut: String,
+    #[serde(default)]
+    pub translate_shortcut_language: String,
+    #[serde(default)]
+    pub translation_endpoint: String,
+    #[serde(default)]
+    pub translation_model: String,
+    /// Falls back to `groq_api_key` when empty and the endpoint is Groq.
+    #[serde(default)]
+    pub translation_api_key: String,
+    #[serde(default)]
+    pub translation_layout: TranslationLayout,
+    /// Rate of the recorded WAV in Hz; the input is resampled to it.
+    #[serde(default)]
+    pub recording_sample_rate: u32,
+    /// Linear gains used when mixing microphone and desktop audio.
+    #[serde(default = "default_mix_gain")]
+    pub mic_mix_gain: f32,
+    #[serde(default = "default_mix_gain")]
+    pub desktop_mix_gain: f32,
+    #[serde(default)]
+    pub track_layout: TrackLayout,
+    /// Stops a toggle recording after this many seconds of silence; 0 = off.
+    #[serde(default)]
+    pub vad_auto_stop_secs: u64,
+    #[serde(default)]
+    pub vad_trim_silence: bool,
+    /// Upload WAV recordings with long pauses shortened.
+    #[serde(default)]
+    pub compress_pauses: bool,
+    /// Microphone processing before the audio is written.
+    #[serde(default)]
+    pub dsp: DspSettings,
+    #[serde(default)]
+    pub recording_format: RecordingFormat,
+    /// Long recordings continue in a new file past this size; 0 = largest.
+    #[serde(default)]
+    pub segment_max_mb: u64,
+    /// Long recordings continue in a new file after this many minutes; 0 = off.
+    #[serde(default)]
+    pub segment_max_minutes: u64,
+    /// Seconds of audio from before the hotkey that start each recording;
+    /// keeps the microphone open while idle. 0 = off.
+    #[serde(default)]
+    pub preroll_secs: f32,
+    /// Minutes of desktop audio kept for the replay shortcut; 0 = off.
+    #[serde(default)]
+    pub replay_buffer_minutes: u64,
+    /// Empty when no replay shortcut is set.
+    #[serde(default)]
+    pub replay_shortcut: String,
+    /// Gain, channel and processing per input device, keyed by device name.
+    #[serde(default)]
+    pub device_profiles: BTreeMap<String, DeviceProfile>,
+    /// Input of the `Pipe` audio source.
+    #[serde(default)]
+    pub pipe_input: PipeSettings,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_mix_gain() -> f32 {
+    DEFAULT_MIX_GAIN
 }
 
 impl Default for AppConfig {
@@ -43,6 +187,39 @@ impl Default for AppConfig {
             show_window_shortcut: "Alt+H".to_string(),
             minimize_on_stop: false,
             hold_to_record_key: Some("ControlLeft".to_string()),
+            transcription_model: DEFAULT_TRANSCRIPTION_MODEL.to_string(),
+            local_endpoint: "http://127.0.0.1:8000/v1/audio/transcriptions".to_string(),
+            language: None,
+            routing_rules: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: None,
+            suppress_hallucinations: true,
+            prompt_source: PromptSource::default(),
+            prompt_token_budget: DEFAULT_PROMPT_TOKEN_BUDGET,
+            prompt_recent_secs: DEFAULT_PROMPT_RECENT_SECS,
+            inverse_text_normalization: false,
+            translate_shortcut: String::new(),
+            translate_shortcut_language: "English".to_string(),
+            translation_endpoint: GROQ_CHAT_COMPLETIONS_URL.to_string(),
+            translation_model: DEFAULT_TRANSLATION_MODEL.to_string(),
+            translation_api_key: String::new(),
+            translation_layout: TranslationLayout::default(),
+            recording_sample_rate: DEFAULT_SAMPLE_RATE,
+            mic_mix_gain: DEFAULT_MIX_GAIN,
+            desktop_mix_gain: DEFAULT_MIX_GAIN,
+            track_layout: TrackLayout::default(),
+            vad_auto_stop_secs: 0,
+            vad_trim_silence: false,
+            compress_pauses: false,
+            dsp: DspSettings::default(),
+            recording_format: RecordingFormat::default(),
+            segment_max_mb: DEFAULT_SEGMENT_MAX_MB,
+            segment_max_minutes: 0,
+            preroll_secs: 0.0,
+            replay_buffer_minutes: 0,
+            replay_shortcut: String::new(),
+            device_profiles: BTreeMap::new(),
+            pipe_input: PipeSettings::default(),
         }
     }
 }
@@ -65,6 +242,78 @@ impl AppConfig {
         }
         merged.minimize_on_stop = cfg.minimize_on_stop;
         merged.hold_to_record_key = cfg.hold_to_record_key;
+        if !cfg.transcription_model.trim().is_empty() {
+            merged.transcription_model = cfg.transcription_model;
+        }
+        if !cfg.local_endpoint.trim().is_empty() {
+            merged.local_endpoint = cfg.local_endpoint;
+        }
+        merged.language = cfg.language.filter(|l| !l.trim().is_empty());
+        merged.routing_rules = cfg.routing_rules;
+        merged.profiles = cfg.profiles;
+        merged.active_profile = cfg.active_profile;
+        merged.suppress_hallucinations = cfg.suppress_hallucinations;
+        merged.prompt_source = cfg.prompt_source;
+        if cfg.prompt_token_budget > 0 {
+            merged.prompt_token_budget = cfg.prompt_token_budget;
+        }
+        if cfg.prompt_recent_secs > 0 {
+            merged.prompt_recent_secs = cfg.prompt_recent_secs;
+        }
+        merged.inverse_text_normalization = cfg.inverse_text_normalization;
+        merged.translate_shortcut = cfg.translate_shortcut;
+        if !cfg.translate_shortcut_language.trim().is_empty() {
+            merged.translate_shortcut_language = cfg.translate_shortcut_language;
+        }
+        if !cfg.translation_endpoint.trim().is_empty() {
+            merged.translation_endpoint = cfg.translation_endpoint;
+        }
+        if !cfg.translation_model.trim().is_empty() {
+            merged.translation_model = cfg.translation_model;
+        }
+        merged.translation_api_key = cfg.translation_api_key;
+        merged.translation_layout = cfg.translation_layout;
+        if cfg.recording_sample_rate > 0 {
+            merged.recording_sample_rate = cfg.recording_sample_rate;
+        }
+        merged.mic_mix_gain = cfg.mic_mix_gain.max(0.0);
+        merged.desktop_mix_gain = cfg.desktop_mix_gain.max(0.0);
+        merged.track_layout = cfg.track_layout;
+        merged.vad_auto_stop_secs = cfg.vad_auto_stop_secs;
+        merged.vad_trim_silence = cfg.vad_trim_silence;
+        merged.compress_pauses = cfg.compress_pauses;
+        merged.dsp = cfg.dsp;
+        merged.recording_format = cfg.recording_format;
+        if cfg.segment_max_mb > 0 {
+            merged.segment_max_mb = cfg.segment_max_mb.min(DEFAULT_SEGMENT_MAX_MB);
+        }
+        merged.segment_max_minutes = cfg.segment_max_minutes;
+        merged.preroll_secs = cfg.preroll_secs.clamp(0.0, MAX_PREROLL_SECS);
+        // Round up to the next offered length.
+        merged.replay_buffer_minutes = match cfg.replay_buffer_minutes {
+            0 => 0,
+            minutes => REPLAY_MINUTES
+                .into_iter()
+                .find(|&m| m >= minutes)
+                .unwrap_or(REPLAY_MINUTES[REPLAY_MINUTES.len() - 1]),
+        };
+        merged.replay_shortcut = cfg.replay_shortcut;
+        merged.device_profiles = cfg
+            .device_profiles
+            .into_iter()
+            .map(|(name, mut profile)| {
+                profile.gain_db = profile.gain_db.clamp(MIN_INPUT_GAIN_DB, MAX_INPUT_GAIN_DB);
+                (name, profile)
+            })
+            .collect();
+        merged.pipe_input = PipeSettings {
+            sample_rate: match cfg.pipe_input.sample_rate {
+                0 => DEFAULT_SAMPLE_RATE,
+                rate => rate,
+            },
+            channels: cfg.pipe_input.channels.clamp(1, MAX_PIPE_CHANNELS),
+            ..cfg.pipe_input
+        };
         merged
     }
 
@@ -126,6 +375,37 @@ impl AppConfig {
         !self.groq_api_key.trim().is_empty()
     }
 
+    pub fn active_profile(&self) -> Option<&Profile> {
+        let name = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Language hint for the next job: the active profile's language wins over
+    /// the global one.
+    pub fn effective_language(&self) -> Option<String> {
+        self.active_profile()
+            .and_then(|p| p.language.clone())
+            .or_else(|| self.language.clone())
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+    }
+
+    /// Whether spoken numbers, dates and units are rewritten; the active
+    /// profile's switch wins over the global one.
+    pub fn inverse_text_normalization_enabled(&self) -> bool {
+        self.active_profile()
+            .and_then(|p| p.inverse_text_normalization)
+            .unwrap_or(self.inverse_text_normalization)
+    }
+
+    /// Target language of the active profile, if it translates.
+    pub fn profile_translation_language(&self) -> Option<String> {
+        self.active_profile()
+            .and_then(|p| p.translate_to.clone())
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+    }
+
     pub fn app_data_dir() -> PathBuf {
         let base = dirs::data_local_dir()
             .or_else(dirs::data_dir)
@@ -171,7 +451,17 @@ impl AppConfig {
 
 #[cfg(test)]
 mod tests {
-    use super::AppConfig;
+    use super::{AppConfig, Profile};
+    use crate::audio_recorder::DEFAULT_SEGMENT_MAX_MB;
+    use crate::dsp::DspSettings;
+    use crate::encoder::RecordingFormat;
+    use crate::pipe::PipeSettings;
+    use crate::prompt_context::{
+        PromptSource, DEFAULT_PROMPT_RECENT_SECS, DEFAULT_PROMPT_TOKEN_BUDGET,
+    };
+    use crate::routing::DEFAULT_TRANSCRIPTION_MODEL;
+    use crate::tracks::TrackLayout;
+    use serde_json::json;
     use std::path::PathBuf;
     use tempfile::tempdir;
 
@@ -295,6 +585,199 @@ mod tests {
         assert!(loaded.hold_to_record_key.is_none());
     }
 
+    #[test]
+    fn merged_from_keeps_default_model_when_blank() {
+        let mut raw = AppConfig::default();
+        raw.transcription_model = " ".to_string();
+        let merged = AppConfig::merged_from(raw);
+        assert_eq!(merged.transcription_model, DEFAULT_TRANSCRIPTION_MODEL);
+    }
+
+    #[test]
+    fn old_configs_without_a_key_get_its_default() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("config.json");
+        let defaults = serde_json::to_value(AppConfig::default()).expect("to_value");
+        let cases = [
+            ("transcription_model", json!(DEFAULT_TRANSCRIPTION_MODEL)),
+            ("local_endpoint", defaults["local_endpoint"].clone()),
+            ("language", json!(null)),
+            ("routing_rules", json!([])),
+            ("profiles", json!([])),
+            ("active_profile", json!(null)),
+            ("suppress_hallucinations", json!(true)),
+            ("prompt_source", json!(PromptSource::Off)),
+            ("prompt_token_budget", json!(DEFAULT_PROMPT_TOKEN_BUDGET)),
+            ("prompt_recent_secs", json!(DEFAULT_PROMPT_RECENT_SECS)),
+            ("inverse_text_normalization", json!(false)),
+            ("recording_sample_rate", json!(16_000)),
+            ("mic_mix_gain", json!(0.5)),
+            ("desktop_mix_gain", json!(0.5)),
+            ("translate_shortcut", json!("")),
+            (
+                "translate_shortcut_language",
+                defaults["translate_shortcut_language"].clone(),
+            ),
+            (
+                "translation_endpoint",
+                defaults["translation_endpoint"].clone(),
+            ),
+            ("translation_model", defaults["translation_model"].clone()),
+            ("translation_api_key", json!("")),
+            ("translation_layout", defaults["translation_layout"].clone()),
+            ("track_layout", json!(TrackLayout::Mixed)),
+            ("vad_auto_stop_secs", json!(0)),
+            ("vad_trim_silence", json!(false)),
+            ("compress_pauses", json!(false)),
+            ("dsp", json!(DspSettings::default())),
+            ("recording_format", json!(RecordingFormat::Opus)),
+            ("segment_max_mb", json!(DEFAULT_SEGMENT_MAX_MB)),
+            ("segment_max_minutes", json!(0)),
+            ("preroll_secs", json!(0.0)),
+            ("replay_buffer_minutes", json!(0)),
+            ("replay_shortcut", json!("")),
+            ("device_profiles", json!({})),
+            ("pipe_input", json!(PipeSettings::default())),
+        ];
+
+        for (key, expected) in cases {
+            let mut value = defaults.clone();
+            value
+                .as_object_mut()
+                .expect("object")
+                .remove(key)
+                .unwrap_or_else(|| panic!("{key} is not a config key"));
+            std::fs::write(&path, value.to_string()).unwrap();
+
+            let loaded = AppConfig::read_config(&path).expect("read");
+            let loaded = serde_json::to_value(loaded).expect("to_value");
+            assert_eq!(loaded[key], expected, "{key}");
+        }
+    }
+
+    #[test]
+    fn mix_gains_allow_muting() {
+        let raw = AppConfig {
+            desktop_mix_gain: 0.0,
+            ..AppConfig::default()
+        };
+        assert_eq!(AppConfig::merged_from(raw).desktop_mix_gain, 0.0);
+    }
+
+    #[test]
+    fn device_profiles_load_by_name_with_clamped_gain() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("config.json");
+        let mut value = serde_json::to_value(AppConfig::default()).expect("to_value");
+        value.as_object_mut().expect("object").insert(
+            "device_profiles".to_string(),
+            serde_json::json!({
+                "Conference USB Mic": { "gain_db": 12.0, "channel": 1 },
+                "MacBook Pro Microphone": { "dsp": { "agc": true } },
+                "Loud Mixer": { "gain_db": 80.0 }
+            }),
+        );
+        std::fs::write(&path, value.to_string()).unwrap();
+
+        let loaded = AppConfig::read_config(&path).expect("read");
+        let usb = loaded.device_profiles["Conference USB Mic"];
+        assert_eq!(usb.channel, Some(1));
+        assert!((usb.shaping().gain - 3.98).abs() < 0.01);
+        let laptop = loaded.device_profiles["MacBook Pro Microphone"];
+        assert_eq!(laptop.shaping().gain, 1.0);
+        assert!(laptop.dsp.is_some_and(|dsp| dsp.agc && !dsp.high_pass));
+        assert_eq!(loaded.device_profiles["Loud Mixer"].gain_db, 30.0);
+    }
+
+    #[test]
+    fn pipe_input_fills_in_rate_and_channels() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("config.json");
+        let mut value = serde_json::to_value(AppConfig::default()).expect("to_value");
+        value.as_object_mut().expect("object").insert(
+            "pipe_input".to_string(),
+            serde_json::json!({ "path": "/tmp/wgo.fifo", "format": "S16Le", "sample_rate": 0, "channels": 64 }),
+        );
+        std::fs::write(&path, value.to_string()).unwrap();
+
+        let loaded = AppConfig::read_config(&path).expect("read");
+        assert_eq!(loaded.pipe_input.path, "/tmp/wgo.fifo");
+        assert_eq!(loaded.pipe_input.format, crate::pipe::PipeFormat::S16Le);
+        assert_eq!(loaded.pipe_input.sample_rate, 16_000);
+        assert_eq!(loaded.pipe_input.channels, crate::pipe::MAX_PIPE_CHANNELS);
+    }
+
+    #[test]
+    fn profile_translation_language_ignores_blank() {
+        let profile = |translate_to: Option<&str>| Profile {
+            name: "Travel".to_string(),
+            tags: Vec::new(),
+            language: None,
+            inverse_text_normalization: None,
+            translate_to: translate_to.map(str::to_string),
+        };
+        let mut cfg = AppConfig {
+            profiles: vec![profile(Some(" Japanese "))],
+            active_profile: Some("Travel".to_string()),
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            cfg.profile_translation_language().as_deref(),
+            Some("Japanese")
+        );
+
+        cfg.profiles = vec![profile(Some("  "))];
+        assert_eq!(cfg.profile_translation_language(), None);
+    }
+
+    #[test]
+    fn effective_language_prefers_active_profile() {
+        let cfg = AppConfig {
+            language: Some("en".to_string()),
+            profiles: vec![Profile {
+                name: "Büro".to_string(),
+                tags: Vec::new(),
+                language: Some("de".to_string()),
+                inverse_text_normalization: None,
+                translate_to: None,
+            }],
+            active_profile: Some("Büro".to_string()),
+            ..AppConfig::default()
+        };
+        assert_eq!(cfg.effective_language().as_deref(), Some("de"));
+
+        let no_profile = AppConfig {
+            active_profile: None,
+            ..cfg
+        };
+        assert_eq!(no_profile.effective_language().as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn profile_switch_overrides_global_text_normalization() {
+        let profile = |name: &str, itn: Option<bool>| Profile {
+            name: name.to_string(),
+            tags: Vec::new(),
+            language: None,
+            inverse_text_normalization: itn,
+            translate_to: None,
+        };
+        let mut cfg = AppConfig {
+            inverse_text_normalization: true,
+            profiles: vec![profile("Chat", Some(false)), profile("Notes", None)],
+            active_profile: Some("Chat".to_string()),
+            ..AppConfig::default()
+        };
+        assert!(!cfg.inverse_text_normalization_enabled());
+
+        cfg.active_profile = Some("Notes".to_string());
+        assert!(cfg.inverse_text_normalization_enabled());
+
+        cfg.active_profile = None;
+        cfg.inverse_text_normalization = false;
+        assert!(!cfg.inverse_text_normalization_enabled());
+    }
+
     #[test]
     fn read_config_returns_none_when_file_missing() {
         let tmp = tempdir().expect("tempdir");
@@ -0,0 +1,434 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+/// Below the lowest voice fundamentals; removes DC, rumble and handling noise.
+const HIGH_PASS_HZ: f32 = 80.0;
+/// Analysis window of the noise gate.
+const GATE_WINDOW_SECS: f32 = 0.032;
+/// How far a noise-only bin is pulled down (-20 dB).
+const GATE_FLOOR: f32 = 0.1;
+/// Noise estimate is over-subtracted a bit to catch its fluctuations.
+const GATE_OVER_SUBTRACTION: f32 = 2.0;
+/// Level the AGC aims for (-20 dBFS RMS).
+const AGC_TARGET_RMS: f32 = 0.1;
+/// Most the AGC will boost (+20 dB) or cut (-10 dB).
+const AGC_MAX_GAIN: f32 = 10.0;
+const AGC_MIN_GAIN: f32 = 0.3;
+/// Quieter input is treated as a pause and leaves the gain alone.
+const AGC_GATE_RMS: f32 = 0.003;
+/// The limiter keeps peaks below this.
+const LIMITER_CEILING: f32 = 0.9;
+
+/// Which stages of the capture chain are on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct DspSettings {
+    pub high_pass: bool,
+    pub noise_suppression: bool,
+    pub agc: bool,
+}
+
+/// Second-order Butterworth high-pass.
+struct HighPass {
+    b: [f32; 3],
+    a: [f32; 2],
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl HighPass {
+    fn new(sample_rate: u32, cutoff: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let alpha = w0.sin() / std::f32::consts::SQRT_2;
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [
+                (1.0 + cos) / 2.0 / a0,
+                -(1.0 + cos) / a0,
+                (1.0 + cos) / 2.0 / a0,
+            ],
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let x = *sample;
+            let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+                - self.a[0] * self.y[0]
+                - self.a[1] * self.y[1];
+            self.x = [x, self.x[0]];
+            self.y = [y, self.y[0]];
+            *sample = y;
+        }
+    }
+}
+
+/// In-place radix-2 FFT; `inverse` skips the 1/n scaling.
+fn fft(re: &mut [f32], im: &mut [f32], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Spectral gate: learns the steady background spectrum (fans, hum, hiss)
+/// and turns each frequency bin down by how much of it is noise. Works on
+/// half-overlapping square-root Hann windows, so it delays the audio by one
+/// window.
+struct NoiseGate {
+    size: usize,
+    window: Vec<f32>,
+    input: VecDeque<f32>,
+    output: VecDeque<f32>,
+    overlap: Vec<f32>,
+    /// Power per bin of the current window.
+    power: Vec<f32>,
+    noise: Vec<f32>,
+    /// False until the first window seeds `noise`.
+    noise_seeded: bool,
+    gains: Vec<f32>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl NoiseGate {
+    fn new(sample_rate: u32) -> Self {
+        let size = ((sample_rate as f32 * GATE_WINDOW_SECS) as usize)
+            .next_power_of_two()
+            .max(64);
+        let window = (0..size)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos()).sqrt())
+            .collect();
+        Self {
+            size,
+            window,
+            input: VecDeque::with_capacity(size * 2),
+            // One window of latency, filled with silence up front.
+            output: std::iter::repeat_n(0.0, size).collect(),
+            overlap: vec![0.0; size / 2],
+            // Allocated up front, since windows run on the audio callback.
+            power: vec![0.0; size / 2 + 1],
+            noise: vec![0.0; size / 2 + 1],
+            noise_seeded: false,
+            gains: vec![1.0; size / 2 + 1],
+            re: vec![0.0; size],
+            im: vec![0.0; size],
+        }
+    }
+
+    fn process_window(&mut self) {
+        let hop = self.size / 2;
+        for (i, (re, im)) in self.re.iter_mut().zip(&mut self.im).enumerate() {
+            *re = self.input[i] * self.window[i];
+            *im = 0.0;
+        }
+        fft(&mut self.re, &mut self.im, false);
+
+        let bins = self.size / 2 + 1;
+        for (k, power) in self.power.iter_mut().enumerate() {
+            *power = self.re[k] * self.re[k] + self.im[k] * self.im[k];
+        }
+        if !self.noise_seeded {
+            self.noise.copy_from_slice(&self.power);
+            self.noise_seeded = true;
+        }
+        for ((&power, noise), smoothed) in
+            self.power.iter().zip(&mut self.noise).zip(&mut self.gains)
+        {
+            // Bins far above the noise are most likely speech and barely
+            // move the estimate.
+            let rate = if power < 4.0 * *noise { 0.05 } else { 0.0005 };
+            *noise += rate * (power - *noise);
+            let gain = if power > 0.0 {
+                (1.0 - GATE_OVER_SUBTRACTION * *noise / power).max(GATE_FLOOR)
+            } else {
+                GATE_FLOOR
+            };
+            // Smoothing over time avoids "musical" warbling.
+            *smoothed = 0.6 * *smoothed + 0.4 * gain;
+        }
+        for k in 0..bins {
+            let gain = self.gains[k];
+            self.re[k] *= gain;
+            self.im[k] *= gain;
+            if k > 0 && k < self.size - k {
+                self.re[self.size - k] *= gain;
+                self.im[self.size - k] *= gain;
+            }
+        }
+
+        fft(&mut self.re, &mut self.im, true);
+        let scale = 1.0 / self.size as f32;
+        for i in 0..self.size {
+            let sample = self.re[i] * scale * self.window[i];
+            if i < hop {
+                self.output.push_back(self.overlap[i] + sample);
+            } else {
+                self.overlap[i - hop] = sample;
+            }
+        }
+        self.input.drain(..hop);
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            self.input.push_back(*sample);
+            if self.input.len() == self.size {
+                self.process_window();
+            }
+            *sample = self.output.pop_front().unwrap_or(0.0);
+        }
+    }
+}
+
+/// Slow automatic gain towards [`AGC_TARGET_RMS`] followed by a peak limiter.
+struct Agc {
+    sample_rate: f32,
+    block: usize,
+    sum_squares: f32,
+    count: usize,
+    gain: f32,
+    peak: f32,
+}
+
+impl Agc {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            // Level is measured over 10 ms blocks.
+            block: (sample_rate / 100).max(1) as usize,
+            sum_squares: 0.0,
+            count: 0,
+            gain: 1.0,
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        // Limiter envelope falls back over about 50 ms.
+        let release = (-1.0 / (0.05 * self.sample_rate)).exp();
+        for sample in samples {
+            self.sum_squares += *sample * *sample;
+            self.count += 1;
+            if self.count == self.block {
+                let rms = (self.sum_squares / self.count as f32).sqrt();
+                if rms > AGC_GATE_RMS {
+                    let wanted = (AGC_TARGET_RMS / rms).clamp(AGC_MIN_GAIN, AGC_MAX_GAIN);
+                    // Turn down faster than up, so loud onsets settle quickly.
+                    let speed = if wanted < self.gain { 0.2 } else { 0.02 };
+                    self.gain += speed * (wanted - self.gain);
+                }
+                self.sum_squares = 0.0;
+                self.count = 0;
+            }
+
+            let boosted = *sample * self.gain;
+            self.peak = boosted.abs().max(self.peak * release);
+            *sample = if self.peak > LIMITER_CEILING {
+                boosted * LIMITER_CEILING / self.peak
+            } else {
+                boosted
+            };
+        }
+    }
+}
+
+/// Capture-side processing: high-pass, noise suppression, then gain control.
+pub struct DspChain {
+    sample_rate: u32,
+    settings: DspSettings,
+    high_pass: HighPass,
+    noise_gate: NoiseGate,
+    agc: Agc,
+}
+
+impl DspChain {
+    pub fn new(sample_rate: u32, settings: DspSettings) -> Self {
+        let sample_rate = sample_rate.max(1);
+        Self {
+            sample_rate,
+            settings,
+            high_pass: HighPass::new(sample_rate, HIGH_PASS_HZ),
+            noise_gate: NoiseGate::new(sample_rate),
+            agc: Agc::new(sample_rate),
+        }
+    }
+
+    /// Switches stages on or off; a stage that comes back on starts fresh.
+    pub fn set_settings(&mut self, settings: DspSettings) {
+        if settings.high_pass && !self.settings.high_pass {
+            self.high_pass = HighPass::new(self.sample_rate, HIGH_PASS_HZ);
+        }
+        if settings.noise_suppression && !self.settings.noise_suppression {
+            self.noise_gate = NoiseGate::new(self.sample_rate);
+        }
+        if settings.agc && !self.settings.agc {
+            self.agc = Agc::new(self.sample_rate);
+        }
+        self.settings = settings;
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.settings.high_pass {
+            self.high_pass.process(samples);
+        }
+        if self.settings.noise_suppression {
+            self.noise_gate.process(samples);
+        }
+        if self.settings.agc {
+            self.agc.process(samples);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn sine(freq: f32, amplitude: f32, secs: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| (2.0 * PI * freq * i as f32 / RATE as f32).sin() * amplitude)
+            .collect()
+    }
+
+    fn noise(amplitude: f32, secs: f32) -> Vec<f32> {
+        let mut state = 0x9e37_79b9u32;
+        (0..(RATE as f32 * secs) as usize)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn run(settings: DspSettings, mut samples: Vec<f32>) -> Vec<f32> {
+        let mut chain = DspChain::new(RATE, settings);
+        // Odd block sizes, like a real capture callback.
+        for block in samples.chunks_mut(333) {
+            chain.process(block);
+        }
+        samples
+    }
+
+    #[test]
+    fn everything_off_leaves_audio_alone() {
+        let input = sine(440.0, 0.3, 0.5);
+        assert_eq!(run(DspSettings::default(), input.clone()), input);
+    }
+
+    #[test]
+    fn high_pass_removes_dc_and_rumble_but_keeps_voice() {
+        let settings = DspSettings {
+            high_pass: true,
+            ..DspSettings::default()
+        };
+        let dc = run(settings, vec![0.5; RATE as usize]);
+        assert!(dc[8_000..].iter().all(|s| s.abs() < 1e-3));
+
+        let rumble = run(settings, sine(20.0, 0.5, 1.0));
+        assert!(
+            rms(&rumble[8_000..]) < 0.05,
+            "rumble {}",
+            rms(&rumble[8_000..])
+        );
+
+        let voice = run(settings, sine(300.0, 0.5, 1.0));
+        assert!((rms(&voice[8_000..]) - rms(&sine(300.0, 0.5, 0.5))).abs() < 0.02);
+    }
+
+    #[test]
+    fn noise_gate_reconstructs_clean_audio_and_quietens_hiss() {
+        let settings = DspSettings {
+            noise_suppression: true,
+            ..DspSettings::default()
+        };
+        let hiss = run(settings, noise(0.05, 3.0));
+        let before = rms(&noise(0.05, 1.0));
+        assert!(
+            rms(&hiss[RATE as usize * 2..]) < before * 0.3,
+            "hiss {} vs {before}",
+            rms(&hiss[RATE as usize * 2..])
+        );
+
+        // A tone well above the hiss comes through at its level.
+        let mut mixed = noise(0.02, 4.0);
+        let tone = sine(500.0, 0.3, 1.0);
+        for (sample, t) in mixed[RATE as usize * 3..].iter_mut().zip(&tone) {
+            *sample += t;
+        }
+        let out = run(settings, mixed);
+        let tail = &out[RATE as usize * 3 + 2_000..];
+        assert!((rms(tail) - rms(&tone)).abs() < 0.03, "tone {}", rms(tail));
+    }
+
+    #[test]
+    fn agc_brings_quiet_speech_up_and_limits_peaks() {
+        let settings = DspSettings {
+            agc: true,
+            ..DspSettings::default()
+        };
+        let quiet = run(settings, sine(300.0, 0.02, 4.0));
+        let settled = rms(&quiet[RATE as usize * 3..]);
+        assert!(settled > 0.08 && settled < 0.2, "settled {settled}");
+
+        let loud = run(settings, sine(300.0, 1.0, 1.0));
+        assert!(loud.iter().all(|s| s.abs() <= LIMITER_CEILING + 1e-3));
+
+        // Silence is not pumped up.
+        let silence = run(settings, vec![0.0005; RATE as usize]);
+        assert!(silence.iter().all(|s| s.abs() < 0.001));
+    }
+
+    #[test]
+    fn fft_round_trips() {
+        let mut re: Vec<f32> = (0..64).map(|i| (i as f32 * 0.3).sin()).collect();
+        let original = re.clone();
+        let mut im = vec![0.0; 64];
+        fft(&mut re, &mut im, false);
+        fft(&mut re, &mut im, true);
+        for (a, b) in re.iter().zip(&original) {
+            assert!((a / 64.0 - b).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use audiopus_sys as opus;
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::BufReader;
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+
+/// Speech stays clear at this rate; it matches the AAC files wgo used to make.
+const OPUS_BITRATE: i32 = 32_000;
+/// Ogg Opus granule positions always count 48 kHz samples.
+const GRANULE_RATE: u32 = 48_000;
+/// Largest packet libopus recommends allowing for.
+const MAX_PACKET_BYTES: usize = 4000;
+/// Longest Opus packet, 120 ms at 48 kHz.
+const MAX_FRAME_SAMPLES: usize = 5760;
+const STREAM_SERIAL: u32 = 0x7767_6f31;
+/// Rates libopus encodes and decodes at.
+const OPUS_RATES: [u32; 5] = [8_000, 12_000, 16_000, 24_000, 48_000];
+
+/// File format recordings are saved and uploaded in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum RecordingFormat {
+    /// Lossy speech codec, about a tenth of the size of WAV.
+    #[default]
+    Opus,
+    Wav,
+}
+
+impl RecordingFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordingFormat::Opus => "Ogg Opus (compressed)",
+            RecordingFormat::Wav => "WAV (uncompressed)",
+        }
+    }
+}
+
+fn opus_error(context: &str, code: c_int) -> String {
+    // SAFETY: opus_strerror returns a static, NUL-terminated string for any code.
+    let message = unsafe { CStr::from_ptr(opus::opus_strerror(code)) };
+    format!("{context}: {}", message.to_string_lossy())
+}
+
+/// A mono libopus encoder, freed on drop.
+struct Encoder(NonNull<opus::OpusEncoder>);
+
+impl Encoder {
+    fn new(rate: u32) -> Result<Self, String> {
+        let mut error = opus::OPUS_OK;
+        // SAFETY: `error` outlives the call; a null result means it failed.
+        let state = unsafe {
+            opus::opus_encoder_create(rate as c_int, 1, opus::OPUS_APPLICATION_VOIP, &mut error)
+        };
+        NonNull::new(state)
+            .filter(|_| error == opus::OPUS_OK)
+            .map(Self)
+            .ok_or_else(|| opus_error("Failed to create the Opus encoder", error))
+    }
+
+    fn set_bitrate(&mut self, bitrate: i32) -> Result<(), String> {
+        // SAFETY: OPUS_SET_BITRATE takes one opus_int32 argument.
+        let code = unsafe {
+            opus::opus_encoder_ctl(self.0.as_ptr(), opus::OPUS_SET_BITRATE_REQUEST, bitrate)
+        };
+        match code {
+            opus::OPUS_OK => Ok(()),
+            code => Err(opus_error("Failed to set the Opus bitrate", code)),
+        }
+    }
+
+    /// Samples the encoder delays its output by.
+    fn lookahead(&mut self) -> Result<u64, String> {
+        let mut lookahead: c_int = 0;
+        // SAFETY: OPUS_GET_LOOKAHEAD writes one opus_int32 through the pointer.
+        let code = unsafe {
+            opus::opus_encoder_ctl(
+                self.0.as_ptr(),
+                opus::OPUS_GET_LOOKAHEAD_REQUEST,
+                &mut lookahead as *mut c_int,
+            )
+        };
+        match code {
+            opus::OPUS_OK => Ok(lookahead.max(0) as u64),
+            code => Err(opus_error("Failed to read the Opus lookahead", code)),
+        }
+    }
+
+    /// Encodes one frame into `packet` and returns the packet's length.
+    fn encode(&mut self, frame: &[f32], packet: &mut [u8]) -> Result<usize, String> {
+        // SAFETY: both buffers are valid for the lengths passed.
+        let len = unsafe {
+            opus::opus_encode_float(
+                self.0.as_ptr(),
+                frame.as_ptr(),
+                frame.len() as c_int,
+                packet.as_mut_ptr(),
+                packet.len() as c_int,
+            )
+        };
+        usize::try_from(len).map_err(|_| opus_error("Opus encoding failed", len))
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        // SAFETY: the state came from opus_encoder_create and is freed once.
+        unsafe { opus::opus_encoder_destroy(self.0.as_ptr()) }
+    }
+}
+
+/// A libopus decoder, freed on drop.
+struct Decoder {
+    state: NonNull<opus::OpusDecoder>,
+    channels: usize,
+}
+
+impl Decoder {
+    fn new(rate: u32, channels: usize) -> Option<Self> {
+        let mut error = opus::OPUS_OK;
+        // SAFETY: `error` outlives the call; a null result means it failed.
+        let state =
+            unsafe { opus::opus_decoder_create(rate as c_int, channels as c_int, &mut error) };
+        let state = NonNull::new(state).filter(|_| error == opus::OPUS_OK)?;
+        Some(Self { state, channels })
+    }
+
+    /// Decodes `packet` into `out` as interleaved samples and returns the
+    /// number of frames.
+    fn decode(&mut self, packet: &[u8], out: &mut [f32]) -> Option<usize> {
+        // SAFETY: `packet` is valid for its length and `out` holds the
+        // frames passed for every channel.
+        let frames = unsafe {
+            opus::opus_decode_float(
+                self.state.as_ptr(),
+                packet.as_ptr(),
+                packet.len() as c_int,
+                out.as_mut_ptr(),
+                (out.len() / self.channels) as c_int,
+                0,
+            )
+        };
+        usize::try_from(frames).ok()
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // SAFETY: the state came from opus_decoder_create and is freed once.
+        unsafe { opus::opus_decoder_destroy(self.state.as_ptr()) }
+    }
+}
+
+/// Encodes mono samples in `-1.0..=1.0` as an Ogg Opus stream. Rates Opus
+/// doesn't run at are resampled to 48 kHz.
+pub fn encode_opus(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, String> {
+    let (samples, rate) = if OPUS_RATES.contains(&sample_rate) {
+        (Cow::Borrowed(samples), sample_rate)
+    } else {
+        let mut resampler = crate::resampler::Resampler::new(sample_rate, GRANULE_RATE);
+        let mut out = Vec::new();
+        resampler.process(samples, &mut out);
+        resampler.finish(&mut out);
+        (Cow::Owned(out), GRANULE_RATE)
+    };
+    let scale = (GRANULE_RATE / rate) as u64;
+
+    let mut encoder = Encoder::new(rate)?;
+    encoder.set_bitrate(OPUS_BITRATE)?;
+    let pre_skip = encoder.lookahead()? * scale;
+
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(1);
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    let vendor = b"wgo";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut writer = PacketWriter::new(Vec::new());
+    let write_error = |e: std::io::Error| format!("Failed to write the Ogg stream: {e}");
+    writer
+        .write_packet(head.into(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)
+        .map_err(write_error)?;
+    writer
+        .write_packet(tags.into(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)
+        .map_err(write_error)?;
+
+    // 20 ms frames, running past the end by the encoder's delay. The last one
+    // is padded with silence and its granule position tells decoders where
+    // the audio really ends; every other page counts all samples decoded so far.
+    let frame_len = (rate / 50) as usize;
+    let delay = (pre_skip / scale) as usize;
+    let frames = (samples.len() + delay).div_ceil(frame_len).max(1);
+    let mut frame = vec![0.0f32; frame_len];
+    let mut packet = vec![0u8; MAX_PACKET_BYTES];
+    for index in 0..frames {
+        let start = (index * frame_len).min(samples.len());
+        let end = (start + frame_len).min(samples.len());
+        frame.fill(0.0);
+        frame[..end - start].copy_from_slice(&samples[start..end]);
+        let len = encoder.encode(&frame, &mut packet)?;
+        let (info, granule) = if index + 1 == frames {
+            (
+                PacketWriteEndInfo::EndStream,
+                pre_skip + samples.len() as u64 * scale,
+            )
+        } else {
+            (
+                PacketWriteEndInfo::NormalPacket,
+                ((index + 1) * frame_len) as u64 * scale,
+            )
+        };
+        writer
+            .write_packet(packet[..len].into(), STREAM_SERIAL, info, granule)
+            .map_err(write_error)?;
+    }
+    Ok(writer.into_inner())
+}
+
+/// Replaces a WAV recording with an Ogg Opus file next to it.
+pub fn wav_to_opus(wav_path: &Path) -> Result<PathBuf, String> {
+    let (samples, rate) =
+        read_mono(wav_path).ok_or_else(|| format!("Failed to read {}", wav_path.display()))?;
+    let opus_path = wav_path.with_extension("ogg");
+    std::fs::write(&opus_path, encode_opus(&samples, rate)?)
+        .map_err(|e| format!("Failed to write {}: {e}", opus_path.display()))?;
+    let _ = std::fs::remove_file(wav_path);
+    Ok(opus_path)
+}
+
+/// Channels, pre-skip and original rate from an `OpusHead` packet.
+fn opus_head(packet: &[u8]) -> Option<(u8, u64, u32)> {
+    if packet.len() < 19 || &packet[..8] != b"OpusHead" {
+        return None;
+    }
+    let pre_skip = u16::from_le_bytes([packet[10], packet[11]]) as u64;
+    let rate = u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]);
+    Some((packet[9], pre_skip, rate))
+}
+
+fn open_ogg(path: &Path) -> Option<PacketReader<BufReader<File>>> {
+    Some(PacketReader::new(BufReader::new(File::open(path).ok()?)))
+}
+
+/// Duration of an Ogg Opus file, from the granule position of its last page.
+/// Packets are skipped over, not decoded.
+pub fn opus_duration_secs(path: &Path) -> Option<f64> {
+    let mut reader = open_ogg(path)?;
+    let (_, pre_skip, _) = opus_head(&reader.read_packet().ok()??.data)?;
+    let mut granule = 0;
+    while let Some(packet) = reader.read_packet().ok()? {
+        granule = packet.absgp_page();
+    }
+    Some(granule.saturating_sub(pre_skip) as f64 / GRANULE_RATE as f64)
+}
+
+/// Decodes an Ogg Opus file as interleaved samples at its original rate, or
+/// 48 kHz when Opus can't output that rate.
+fn read_opus(path: &Path) -> Option<(Vec<f32>, u16, u32)> {
+    let mut reader = open_ogg(path)?;
+    let (channels, pre_skip, original_rate) = opus_head(&reader.read_packet().ok()??.data)?;
+    let channels = match channels {
+        1 | 2 => channels as usize,
+        _ => return None,
+    };
+    let rate = if OPUS_RATES.contains(&original_rate) {
+        original_rate
+    } else {
+        GRANULE_RATE
+    };
+    let scale = (GRANULE_RATE / rate) as u64;
+    let mut decoder = Decoder::new(rate, channels)?;
+
+    // The second packet holds the tags.
+    reader.read_packet().ok()??;
+    let mut samples = Vec::new();
+    let mut block = vec![0.0f32; MAX_FRAME_SAMPLES * channels];
+    let mut granule = 0;
+    while let Some(packet) = reader.read_packet().ok()? {
+        granule = packet.absgp_page();
+        let frames = decoder.decode(&packet.data, &mut block)?;
+        samples.extend_from_slice(&block[..frames * channels]);
+    }
+
+    let skip = (pre_skip / scale) as usize * channels;
+    let len = (granule.saturating_sub(pre_skip) / scale) as usize * channels;
+    samples.drain(..skip.min(samples.len()));
+    samples.truncate(len);
+    Some((samples, channels as u16, rate))
+}
+
+/// Duration from the STREAMINFO block.
+pub fn flac_duration_secs(path: &Path) -> Option<f64> {
+    let reader = claxon::FlacReader::open(path).ok()?;
+    let info = reader.streaminfo();
+    let samples = info.samples? as f64;
+    (info.sample_rate > 0).then(|| samples / info.sample_rate as f64)
+}
+
+/// Reads a WAV, FLAC or Ogg Opus file as mono samples in `-1.0..=1.0`. `None`
+/// for other formats or unreadable files.
+pub fn read_mono(path: &Path) -> Option<(Vec<f32>, u32)> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let (interleaved, channels, rate) = match ext.as_str() {
+        "wav" => {
+            let mut reader = hound::WavReader::open(path).ok()?;
+            let spec = reader.spec();
+            let samples: Vec<f32> = match spec.sample_format {
+                hound::SampleFormat::Float => {
+                    reader.samples::<f32>().collect::<Result<_, _>>().ok()?
+                }
+                hound::SampleFormat::Int => {
+                    let scale = (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                    reader
+                        .samples::<i32>()
+                        .map(|s| s.map(|s| s as f32 / scale))
+                        .collect::<Result<_, _>>()
+                        .ok()?
+                }
+            };
+            (samples, spec.channels, spec.sample_rate)
+        }
+        "flac" => {
+            let mut reader = claxon::FlacReader::open(path).ok()?;
+            let info = reader.streaminfo();
+            let scale = (1i64 << (info.bits_per_sample.max(1) - 1)) as f32;
+            let samples: Vec<f32> = reader
+                .samples()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .ok()?;
+            (samples, info.channels as u16, info.sample_rate)
+        }
+        "ogg" | "opus" => read_opus(path)?,
+        _ => return None,
+    };
+    let channels = channels.max(1) as usize;
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Some((mono, rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Speech-like test signal: a few harmonics plus a little noise.
+    fn signal(len: usize, rate: u32) -> Vec<f32> {
+        let mut state = 0x1234_5678u32;
+        (0..len)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let t = i as f32 / rate as f32;
+                let w = 2.0 * std::f32::consts::PI * 180.0 * t;
+                let noise = ((state % 200) as f32 - 100.0) / 32_768.0;
+                0.3 * w.sin() + 0.1 * (3.0 * w).sin() + noise
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+    }
+
+    fn write_wav(path: &Path, samples: &[f32], rate: u32) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for s in samples {
+            writer.write_sample((s * 32_767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn wav_converts_to_a_much_smaller_opus_file_of_the_same_duration() {
+        let tmp = tempdir().expect("tempdir");
+        let wav = tmp.path().join("rec.wav");
+        let original = signal(16_000 * 5 / 2, 16_000);
+        write_wav(&wav, &original, 16_000);
+        let wav_bytes = std::fs::metadata(&wav).unwrap().len();
+
+        let opus = wav_to_opus(&wav).expect("convert");
+        assert!(!wav.exists());
+        assert_eq!(opus.extension().unwrap(), "ogg");
+        assert_eq!(opus_duration_secs(&opus), Some(2.5));
+        let opus_bytes = std::fs::metadata(&opus).unwrap().len();
+        assert!(opus_bytes * 5 < wav_bytes, "{opus_bytes} of {wav_bytes}");
+
+        let (decoded, rate) = read_mono(&opus).expect("decode");
+        assert_eq!((decoded.len(), rate), (40_000, 16_000));
+        let level = rms(&decoded) / rms(&original);
+        assert!((0.8..1.2).contains(&level), "level {level}");
+        // Lined up with the original: the encoder delay was skipped.
+        let diff: Vec<f32> = decoded.iter().zip(&original).map(|(d, o)| d - o).collect();
+        let error = rms(&diff) / rms(&original);
+        assert!(error < 0.6, "error {error}");
+    }
+
+    #[test]
+    fn rates_opus_lacks_are_resampled_to_48k() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("cd.ogg");
+        let bytes = encode_opus(&signal(44_100, 44_100), 44_100).expect("encode");
+        std::fs::write(&path, bytes).unwrap();
+
+        let duration = opus_duration_secs(&path).expect("duration");
+        assert!((duration - 1.0).abs() < 0.001, "{duration}");
+        let (decoded, rate) = read_mono(&path).expect("decode");
+        assert_eq!(rate, 48_000);
+        assert!(decoded.len().abs_diff(48_000) <= 2, "{}", decoded.len());
+    }
+
+    #[test]
+    fn short_and_empty_inputs_are_valid_streams() {
+        let tmp = tempdir().expect("tempdir");
+        for len in [0, 7, 320] {
+            let path = tmp.path().join(format!("short_{len}.ogg"));
+            std::fs::write(&path, encode_opus(&signal(len, 16_000), 16_000).unwrap()).unwrap();
+            let (decoded, rate) = read_mono(&path).expect("decode");
+            assert_eq!((decoded.len(), rate), (len, 16_000));
+        }
+    }
+}
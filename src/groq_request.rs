@@ -1,14 +1,18 @@
 use crate::config::AppConfig;
+use crate::routing::{Route, TranscriptionProvider};
 use reqwest::blocking::multipart::{Form, Part};
 use reqwest::blocking::Client;
 use rust_ffmpeg::{Codec, FFmpegBuilder, Output};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+const GROQ_TRANSCRIPTIONS_URL: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
+
 const SUPPORTED_AUDIO_EXTS: [&str; 7] = ["mp3", "wav", "m4a", "ogg", "opus", "flac", "webm"];
 const SUPPORTED_VIDEO_EXTS: [&str; 19] = [
     "mp4", "mov", "m4v", "mkv", "avi", "webm", "mpg", "mpeg",
@@ -80,6 +84,128 @@ fn find_ffmpeg() -> Option<PathBuf> {
     None
 }
 
+fn find_ffprobe() -> Option<PathBuf> {
+    if let Ok(p) = which::which("ffprobe") {
+        return Some(p);
+    }
+    // ffprobe ships next to ffmpeg in every distribution we probe for.
+    let sibling = find_ffmpeg()?.with_file_name("ffprobe");
+    sibling.exists().then_some(sibling)
+}
+
+/// Reads a box header: its type and where its body starts and ends.
+fn read_mp4_box(reader: &mut (impl Read + Seek), end: u64) -> Option<([u8; 4], u64, u64)> {
+    let start = reader.stream_position().ok()?;
+    if start + 8 > end {
+        return None;
+    }
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).ok()?;
+    let kind = [header[4], header[5], header[6], header[7]];
+    let (body, size) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+        // A 64-bit size follows the type.
+        1 => {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large).ok()?;
+            (start + 16, u64::from_be_bytes(large))
+        }
+        // The box runs to the end of its parent.
+        0 => (start + 8, end - start),
+        size => (start + 8, size as u64),
+    };
+    let box_end = start.checked_add(size)?;
+    (box_end >= body && box_end <= end).then_some((kind, body, box_end))
+}
+
+/// Reads the duration out of the `moov/mvhd` box of an MP4-family file (m4a,
+/// mp4, mov), walking the box headers instead of reading the samples.
+fn mp4_duration_secs(reader: &mut (impl Read + Seek)) -> Option<f64> {
+    let file_end = reader.seek(SeekFrom::End(0)).ok()?;
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    let mut end = file_end;
+    let body = loop {
+        let (kind, body, box_end) = read_mp4_box(reader, end)?;
+        match &kind {
+            b"moov" => end = box_end,
+            b"mvhd" => break body,
+            _ => {
+                reader.seek(SeekFrom::Start(box_end)).ok()?;
+                continue;
+            }
+        }
+        reader.seek(SeekFrom::Start(body)).ok()?;
+    };
+
+    let mut header = [0u8; 32];
+    reader.seek(SeekFrom::Start(body)).ok()?;
+    let read = reader.take(32).read(&mut header).ok()?;
+    let header = &header[..read];
+    let version = *header.first()?;
+    let be_u32 = |at: usize| -> Option<u64> {
+        let b = header.get(at..at + 4)?;
+        Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64)
+    };
+    let (timescale, duration) = if version == 1 {
+        let b = header.get(24..32)?;
+        let duration = u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+        (be_u32(20)?, duration)
+    } else {
+        (be_u32(12)?, be_u32(16)?)
+    };
+    if timescale == 0 {
+        return None;
+    }
+    Some(duration as f64 / timescale as f64)
+}
+
+/// Best-effort clip length used by the routing rules. WAV, FLAC, Ogg Opus and
+/// MP4-family files are read directly; anything else needs ffprobe. Returns
+/// `None` when unknown.
+pub fn media_duration_secs(path: &Path) -> Option<f64> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "wav" => {
+            let reader = hound::WavReader::open(path).ok()?;
+            let spec = reader.spec();
+            if spec.sample_rate == 0 {
+                return None;
+            }
+            Some(reader.duration() as f64 / spec.sample_rate as f64)
+        }
+        "flac" => crate::encoder::flac_duration_secs(path),
+        // Other Ogg codecs are left to ffprobe.
+        "ogg" | "opus" => {
+            crate::encoder::opus_duration_secs(path).or_else(|| ffprobe_duration_secs(path))
+        }
+        "m4a" | "mp4" | "mov" | "m4v" => mp4_duration_secs(&mut File::open(path).ok()?),
+        _ => ffprobe_duration_secs(path),
+    }
+}
+
+fn ffprobe_duration_secs(path: &Path) -> Option<f64> {
+    let output = Command::new(find_ffprobe()?)
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+        ])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
 fn extract_audio_from_video(input_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let ffmpeg_path = find_ffmpeg().ok_or_else(|| {
         "ffmpeg not found. Install it with: brew install ffmpeg".to_string()
@@ -200,15 +326,100 @@ fn resolve_audio_file_path_with(file_path: &str, recordings_dir: &Path, exe_dir:
     }
 }
 
-pub fn transcribe_audio(file_path: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let config = AppConfig::load();
-    let api_key = config.groq_api_key.trim();
+/// One segment of a `verbose_json` response. The scores are optional because
+/// not every OpenAI-compatible server reports them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    #[serde(default)]
+    pub no_speech_prob: Option<f64>,
+    #[serde(default)]
+    pub compression_ratio: Option<f64>,
+    #[serde(default)]
+    pub avg_logprob: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
+    /// Language Whisper detected (`verbose_json` reports names like "english").
+    pub language: Option<String>,
+}
+
+fn parse_transcript(json: &Value) -> Transcript {
+    let text = json
+        .get("text")
+        .and_then(Value::as_str)
+        .map(|t| t.trim().to_owned())
+        .unwrap_or_else(|| "No transcription text found in response".to_owned());
+
+    let segments = json
+        .get("segments")
+        .and_then(Value::as_array)
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(|seg| {
+                    Some(TranscriptSegment {
+                        start: seg.get("start").and_then(Value::as_f64)?,
+                        end: seg.get("end").and_then(Value::as_f64)?,
+                        text: seg.get("text").and_then(Value::as_str)?.trim().to_owned(),
+                        no_speech_prob: seg.get("no_speech_prob").and_then(Value::as_f64),
+                        compression_ratio: seg.get("compression_ratio").and_then(Value::as_f64),
+                        avg_logprob: seg.get("avg_logprob").and_then(Value::as_f64),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let language = json
+        .get("language")
+        .and_then(Value::as_str)
+        .map(str::to_owned);
 
-    if api_key.is_empty() {
-        return Err("Groq API key is empty. Set it in the app settings.".into());
+    Transcript {
+        text,
+        segments,
+        language,
     }
+}
+
+/// Per-job settings decided when the job is created.
+#[derive(Debug, Clone)]
+pub struct TranscriptionOptions {
+    pub route: Route,
+    pub language: Option<String>,
+    /// Text Whisper treats as spoken right before the clip, to carry over
+    /// spelling and casing.
+    pub prompt: Option<String>,
+}
+
+pub fn transcribe_audio(
+    file_path: &str,
+    options: &TranscriptionOptions,
+) -> Result<Transcript, Box<dyn std::error::Error>> {
+    let config = AppConfig::load();
+    let api_key = config.groq_api_key.trim();
 
-    let url = "https://api.groq.com/openai/v1/audio/transcriptions";
+    let (url, auth) = match options.route.provider {
+        TranscriptionProvider::Groq => {
+            if api_key.is_empty() {
+                return Err("Groq API key is empty. Set it in the app settings.".into());
+            }
+            (GROQ_TRANSCRIPTIONS_URL, Some(api_key))
+        }
+        TranscriptionProvider::Local => {
+            let endpoint = config.local_endpoint.trim();
+            if endpoint.is_empty() {
+                return Err("Local transcription endpoint is empty. Set it in the app settings.".into());
+            }
+            (endpoint, None)
+        }
+    };
 
     let media_file_path = resolve_audio_file_path(file_path);
 
@@ -232,25 +443,27 @@ pub fn transcribe_audio(file_path: &str) -> Result<String, Box<dyn std::error::E
 
     let file_part = Part::bytes(buffer).file_name(file_name).mime_str(mime)?;
 
-    let form = Form::new()
+    let mut form = Form::new()
         .part("file", file_part)
-        .text("model", "whisper-large-v3");
+        .text("model", options.route.model.clone())
+        .text("response_format", "verbose_json");
+    if let Some(ref language) = options.language {
+        form = form.text("language", language.clone());
+    }
+    if let Some(ref prompt) = options.prompt {
+        form = form.text("prompt", prompt.clone());
+    }
 
     let client = Client::new();
-    let response = client
-        .post(url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .multipart(form)
-        .send()?;
+    let mut request = client.post(url).multipart(form);
+    if let Some(key) = auth {
+        request = request.header("Authorization", format!("Bearer {}", key));
+    }
+    let response = request.send()?;
 
     let result = if response.status().is_success() {
         let json: Value = response.json()?;
-
-        if let Some(text) = json.get("text").and_then(Value::as_str) {
-            Ok(text.to_owned())
-        } else {
-            Ok("No transcription text found in response".to_owned())
-        }
+        Ok(parse_transcript(&json))
     } else {
         let status = response.status();
         let error_body = response
@@ -318,6 +531,110 @@ mod tests {
         assert!(!is_video_file("audio.wav"));
     }
 
+    #[test]
+    fn parse_transcript_reads_verbose_segments() {
+        let json: Value = serde_json::from_str(
+            r#"{
+                "text": " Hello there. ",
+                "language": "english",
+                "segments": [
+                    {"start": 0.0, "end": 1.5, "text": " Hello", "no_speech_prob": 0.01,
+                     "compression_ratio": 0.9, "avg_logprob": -0.2},
+                    {"start": 1.5, "end": 2.0, "text": " there."}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let transcript = parse_transcript(&json);
+        assert_eq!(transcript.text, "Hello there.");
+        assert_eq!(transcript.segments.len(), 2);
+        assert_eq!(transcript.segments[0].no_speech_prob, Some(0.01));
+        assert_eq!(transcript.segments[1].compression_ratio, None);
+        assert_eq!(transcript.language.as_deref(), Some("english"));
+    }
+
+    #[test]
+    fn parse_transcript_tolerates_plain_json_response() {
+        let json: Value = serde_json::from_str(r#"{"text": "plain"}"#).unwrap();
+        let transcript = parse_transcript(&json);
+        assert_eq!(transcript.text, "plain");
+        assert!(transcript.segments.is_empty());
+        assert_eq!(transcript.language, None);
+    }
+
+    #[test]
+    fn media_duration_reads_wav_header() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("two_seconds.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).expect("create");
+        for _ in 0..16000 {
+            writer.write_sample(0i16).expect("write");
+        }
+        writer.finalize().expect("finalize");
+
+        let duration = media_duration_secs(&path).expect("duration");
+        assert!((duration - 2.0).abs() < 1e-9);
+    }
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn mvhd_v0(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut body = vec![0; 12]; // version + flags, creation + modification time
+        body.extend_from_slice(&timescale.to_be_bytes());
+        body.extend_from_slice(&duration.to_be_bytes());
+        mp4_box(b"mvhd", &body)
+    }
+
+    #[test]
+    fn mp4_duration_parses_version_0_mvhd() {
+        let mut bytes = mp4_box(b"ftyp", b"M4A isom");
+        bytes.extend(mp4_box(b"moov", &mvhd_v0(1000, 12_500)));
+        assert_eq!(
+            mp4_duration_secs(&mut std::io::Cursor::new(bytes)),
+            Some(12.5)
+        );
+    }
+
+    #[test]
+    fn mp4_duration_ignores_mvhd_bytes_in_sample_data() {
+        // Sample data that happens to contain "mvhd", ahead of the real moov.
+        let mut mdat = b"noise mvhd".to_vec();
+        mdat.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 99, 0, 0, 0, 1]);
+        let mut trak = mp4_box(b"tkhd", &[0; 20]);
+        trak = mp4_box(b"trak", &trak);
+        let mut moov = trak;
+        moov.extend(mvhd_v0(48_000, 96_000));
+
+        let mut bytes = mp4_box(b"ftyp", b"M4A isom");
+        bytes.extend(mp4_box(b"mdat", &mdat));
+        bytes.extend(mp4_box(b"moov", &moov));
+        assert_eq!(
+            mp4_duration_secs(&mut std::io::Cursor::new(bytes)),
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn mp4_duration_reads_real_test_material() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_material")
+            .join("test_video.m4a");
+        let duration = media_duration_secs(&path).expect("duration");
+        assert!(duration > 0.0);
+    }
+
     #[test]
     fn extract_audio_from_real_test_material_videos() {
         let base = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_material");
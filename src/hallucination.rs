@@ -0,0 +1,341 @@
+use crate::groq_request::{Transcript, TranscriptSegment};
+use std::path::Path;
+
+// Whisper's own decoding fallbacks use the same thresholds.
+const NO_SPEECH_PROB_THRESHOLD: f64 = 0.6;
+const LOW_LOGPROB_THRESHOLD: f64 = -1.0;
+const COMPRESSION_RATIO_THRESHOLD: f64 = 2.4;
+/// Loudest 30 ms window below this level means nobody said anything.
+const SILENCE_DBFS: f32 = -50.0;
+const ENERGY_WINDOW_SECS: f32 = 0.03;
+
+/// Phrases Whisper is known to produce from silence or background noise
+/// (mostly leftovers from subtitled YouTube training data).
+const KNOWN_HALLUCINATIONS: [&str; 14] = [
+    "thank you for watching",
+    "thanks for watching",
+    "thank you for watching and see you next time",
+    "please subscribe",
+    "like and subscribe",
+    "subtitles by the amara.org community",
+    "thank you",
+    "thank you very much",
+    "you",
+    "bye",
+    "untertitel im auftrag des zdf",
+    "untertitel der amara.org-community",
+    "sous-titres réalisés par la communauté d'amara.org",
+    "amara.org",
+];
+
+/// Energy summary of a recording, computed locally before trusting the text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioEnergy {
+    pub rms_dbfs: f32,
+    pub loudest_window_dbfs: f32,
+}
+
+impl AudioEnergy {
+    pub fn is_silent(&self) -> bool {
+        self.loudest_window_dbfs < SILENCE_DBFS
+    }
+}
+
+fn to_dbfs(rms: f32) -> f32 {
+    if rms <= 0.0 {
+        return -120.0;
+    }
+    (20.0 * rms.log10()).max(-120.0)
+}
+
+pub fn energy_of_samples(samples: &[f32], sample_rate: u32) -> AudioEnergy {
+    let window = ((sample_rate as f32 * ENERGY_WINDOW_SECS) as usize).max(1);
+    let mut total = 0.0f64;
+    let mut loudest = 0.0f32;
+    for chunk in samples.chunks(window) {
+        let sum: f32 = chunk.iter().map(|s| s * s).sum();
+        total += sum as f64;
+        loudest = loudest.max((sum / chunk.len() as f32).sqrt());
+    }
+    let rms = if samples.is_empty() {
+        0.0
+    } else {
+        (total / samples.len() as f64).sqrt() as f32
+    };
+    AudioEnergy {
+        rms_dbfs: to_dbfs(rms),
+        loudest_window_dbfs: to_dbfs(loudest),
+    }
+}
+
+/// Reads a WAV, FLAC or Ogg Opus recording and measures its energy. Other formats
+/// return `None` and are judged on the transcript alone.
+pub fn audio_energy(path: &Path) -> Option<AudioEnergy> {
+    let (mono, sample_rate) = crate::encoder::read_mono(path)?;
+    Some(energy_of_samples(&mono, sample_rate))
+}
+
+fn normalize_phrase(text: &str) -> String {
+    text.trim()
+        .trim_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_known_hallucination(text: &str) -> bool {
+    let normalized = normalize_phrase(text);
+    KNOWN_HALLUCINATIONS.iter().any(|p| *p == normalized)
+}
+
+/// True when one short phrase makes up nearly the whole transcript, e.g.
+/// "I'm sorry. I'm sorry. I'm sorry. I'm sorry."
+fn is_repetitive(text: &str) -> bool {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(normalize_phrase)
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.len() < 8 {
+        return false;
+    }
+
+    for n in 1..=4 {
+        let mut best = 0usize;
+        for start in 0..n.min(words.len()) {
+            let mut run = 1usize;
+            let mut i = start + n;
+            while i + n <= words.len() {
+                if words[i..i + n] == words[i - n..i] {
+                    run += 1;
+                    best = best.max(run);
+                } else {
+                    run = 1;
+                }
+                i += n;
+            }
+        }
+        if best * n * 10 >= words.len() * 7 && best >= 4 {
+            return true;
+        }
+    }
+    false
+}
+
+fn segment_duration(seg: &TranscriptSegment) -> f64 {
+    (seg.end - seg.start).max(0.0)
+}
+
+fn is_no_speech(seg: &TranscriptSegment) -> bool {
+    let no_speech = seg.no_speech_prob.unwrap_or(0.0) > NO_SPEECH_PROB_THRESHOLD;
+    // Whisper only trusts no_speech_prob when the decoder was also unsure.
+    let unsure = seg.avg_logprob.is_none_or(|p| p < LOW_LOGPROB_THRESHOLD);
+    no_speech && unsure
+}
+
+/// Decides whether a transcript is likely a hallucination and returns the
+/// reason, or `None` when it looks genuine.
+pub fn assess(transcript: &Transcript, energy: Option<AudioEnergy>) -> Option<String> {
+    let text = transcript.text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if let Some(energy) = energy {
+        if energy.is_silent() {
+            return Some(format!(
+                "recording is silent (loudest passage {:.0} dBFS)",
+                energy.loudest_window_dbfs
+            ));
+        }
+    }
+
+    let total: f64 = transcript.segments.iter().map(segment_duration).sum();
+    if total > 0.0 {
+        let no_speech: f64 = transcript
+            .segments
+            .iter()
+            .filter(|s| is_no_speech(s))
+            .map(segment_duration)
+            .sum();
+        if no_speech / total >= 0.8 {
+            return Some("the model reported no speech".to_string());
+        }
+
+        let repetitive: f64 = transcript
+            .segments
+            .iter()
+            .filter(|s| s.compression_ratio.unwrap_or(0.0) > COMPRESSION_RATIO_THRESHOLD)
+            .map(segment_duration)
+            .sum();
+        if repetitive / total >= 0.5 {
+            return Some("output is highly repetitive (compression ratio)".to_string());
+        }
+    }
+
+    if is_repetitive(text) {
+        return Some("output repeats the same phrase".to_string());
+    }
+
+    let quiet = energy.is_some_and(|e| e.loudest_window_dbfs < SILENCE_DBFS + 15.0);
+    let unsure = transcript
+        .segments
+        .iter()
+        .any(|s| s.no_speech_prob.unwrap_or(0.0) > NO_SPEECH_PROB_THRESHOLD / 2.0);
+    if is_known_hallucination(text) && (quiet || unsure) {
+        return Some(format!(
+            "\"{text}\" is a typical output for near-silent audio"
+        ));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f64, end: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start,
+            end,
+            text: text.to_string(),
+            no_speech_prob: Some(0.01),
+            compression_ratio: Some(1.1),
+            avg_logprob: Some(-0.2),
+        }
+    }
+
+    fn transcript(text: &str, segments: Vec<TranscriptSegment>) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            segments,
+            language: None,
+        }
+    }
+
+    const LOUD: AudioEnergy = AudioEnergy {
+        rms_dbfs: -22.0,
+        loudest_window_dbfs: -8.0,
+    };
+    const QUIET: AudioEnergy = AudioEnergy {
+        rms_dbfs: -58.0,
+        loudest_window_dbfs: -42.0,
+    };
+    const SILENT: AudioEnergy = AudioEnergy {
+        rms_dbfs: -80.0,
+        loudest_window_dbfs: -65.0,
+    };
+
+    #[test]
+    fn normal_dictation_passes() {
+        let t = transcript(
+            "Please send the report to Anna by Friday.",
+            vec![seg(0.0, 3.0, "Please send the report to Anna by Friday.")],
+        );
+        assert_eq!(assess(&t, Some(LOUD)), None);
+    }
+
+    #[test]
+    fn silent_recording_is_flagged_whatever_the_text() {
+        let t = transcript("Thank you for watching.", vec![]);
+        assert!(assess(&t, Some(SILENT)).unwrap().contains("silent"));
+    }
+
+    #[test]
+    fn high_no_speech_prob_is_flagged() {
+        let mut s = seg(0.0, 2.0, "Thank you.");
+        s.no_speech_prob = Some(0.92);
+        s.avg_logprob = Some(-1.3);
+        let t = transcript("Thank you.", vec![s]);
+        assert!(assess(&t, Some(LOUD)).unwrap().contains("no speech"));
+    }
+
+    #[test]
+    fn no_speech_prob_is_ignored_when_decoder_was_confident() {
+        let mut s = seg(0.0, 2.0, "Yes, exactly.");
+        s.no_speech_prob = Some(0.7);
+        s.avg_logprob = Some(-0.1);
+        let t = transcript("Yes, exactly.", vec![s]);
+        assert_eq!(assess(&t, Some(LOUD)), None);
+    }
+
+    #[test]
+    fn high_compression_ratio_is_flagged() {
+        let mut s = seg(0.0, 10.0, "la la la");
+        s.compression_ratio = Some(3.1);
+        let t = transcript("la la la", vec![s, seg(10.0, 11.0, "ok")]);
+        assert!(assess(&t, Some(LOUD)).unwrap().contains("repetitive"));
+    }
+
+    #[test]
+    fn repeated_phrase_without_segments_is_flagged() {
+        let t = transcript(
+            "I'm sorry. I'm sorry. I'm sorry. I'm sorry. I'm sorry.",
+            vec![],
+        );
+        assert!(assess(&t, None).unwrap().contains("repeats"));
+    }
+
+    #[test]
+    fn short_genuine_repetition_passes() {
+        let t = transcript("No no no, that's wrong.", vec![]);
+        assert_eq!(assess(&t, None), None);
+    }
+
+    #[test]
+    fn known_phrase_on_quiet_audio_is_flagged() {
+        let t = transcript(
+            " Thanks for watching! ",
+            vec![seg(0.0, 1.0, "Thanks for watching!")],
+        );
+        assert!(assess(&t, Some(QUIET)).is_some());
+    }
+
+    #[test]
+    fn known_phrase_on_loud_confident_audio_passes() {
+        let t = transcript("Thank you.", vec![seg(0.0, 1.0, "Thank you.")]);
+        assert_eq!(assess(&t, Some(LOUD)), None);
+    }
+
+    #[test]
+    fn empty_text_is_not_flagged() {
+        assert_eq!(assess(&transcript("   ", vec![]), Some(SILENT)), None);
+    }
+
+    #[test]
+    fn energy_of_silence_and_tone() {
+        let silence = vec![0.0f32; 16000];
+        assert!(energy_of_samples(&silence, 16000).is_silent());
+
+        let tone: Vec<f32> = (0..16000)
+            .map(|i| 0.5 * (i as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin())
+            .collect();
+        let energy = energy_of_samples(&tone, 16000);
+        assert!(!energy.is_silent());
+        // RMS of a 0.5 amplitude sine is about -9 dBFS.
+        assert!((energy.rms_dbfs + 9.0).abs() < 0.5, "{}", energy.rms_dbfs);
+    }
+
+    #[test]
+    fn audio_energy_reads_recorded_file() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("quiet.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..16000 {
+            writer.write_sample(3i16).unwrap();
+            writer.write_sample(-3i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        assert!(audio_energy(&path).expect("energy").is_silent());
+        assert_eq!(audio_energy(&tmp.path().join("clip.m4a")), None);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::backend::{DataCallback, ErrorCallback, InputStream};
+use crate::capture::InputShaping;
+use crate::pipe::{PipeFormat, PipeSettings};
+use std::process::Command;
+
+/// Marks monitor sources in the desktop device list.
+pub const LOOPBACK_PREFIX: &str = "Loopback: ";
+
+const NO_LOOPBACK: &str = "No loopback source found for desktop audio. wgo looks for \
+PulseAudio or PipeWire monitor sources with pactl; install pactl or pick a loopback \
+input device in Settings.";
+
+/// Rate monitor sources are recorded at when the caller has no preference.
+/// The sound server resamples to whatever is asked for.
+pub const MONITOR_RATE: u32 = 48_000;
+
+/// A PulseAudio/PipeWire source that captures what plays on a sink.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorSource {
+    pub name: String,
+    pub description: String,
+    pub sink: String,
+}
+
+impl MonitorSource {
+    /// How the source is shown in the device list and stored in the config.
+    pub fn label(&self) -> String {
+        format!("{LOOPBACK_PREFIX}{}", self.description)
+    }
+}
+
+/// Picks the monitor sources out of `pactl list sources`.
+pub fn parse_monitor_sources(list: &str) -> Vec<MonitorSource> {
+    let mut sources = Vec::new();
+    for block in list.split("\nSource #") {
+        let field = |key: &str| {
+            block
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key))
+                .map(|value| value.trim().to_string())
+        };
+        let (Some(name), Some(sink)) = (field("Name:"), field("Monitor of Sink:")) else {
+            continue;
+        };
+        if sink == "n/a" {
+            continue;
+        }
+        let description = field("Description:").unwrap_or_else(|| name.clone());
+        sources.push(MonitorSource {
+            name,
+            description,
+            sink,
+        });
+    }
+    sources
+}
+
+/// The default sink from `pactl info`.
+pub fn parse_default_sink(info: &str) -> Option<String> {
+    info.lines()
+        .find_map(|line| line.strip_prefix("Default Sink:"))
+        .map(|sink| sink.trim().to_string())
+        .filter(|sink| !sink.is_empty())
+}
+
+fn pactl(args: &[&str]) -> Option<String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Monitor sources of the running sound server; empty off Linux.
+pub fn monitor_sources() -> Vec<MonitorSource> {
+    if !cfg!(target_os = "linux") {
+        return Vec::new();
+    }
+    pactl(&["list", "sources"])
+        .map(|list| parse_monitor_sources(&list))
+        .unwrap_or_default()
+}
+
+/// What the "default" desktop device means on this platform.
+pub fn default_device_label() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "Monitor of the default output"
+    } else {
+        "Default device"
+    }
+}
+
+/// Which monitor a desktop device setting refers to. `None` is the default
+/// sink's monitor; names without [`LOOPBACK_PREFIX`] are plain input devices.
+fn select_monitor(
+    name: &Option<String>,
+    sources: &[MonitorSource],
+    default_sink: Option<&str>,
+) -> Result<Option<String>, String> {
+    match name {
+        Some(name) if name.starts_with(LOOPBACK_PREFIX) => sources
+            .iter()
+            .find(|s| s.label() == *name)
+            .map(|s| Some(s.name.clone()))
+            .ok_or_else(|| format!("'{name}' is no longer available")),
+        Some(_) => Ok(None),
+        None => sources
+            .iter()
+            .find(|s| Some(s.sink.as_str()) == default_sink)
+            .or(sources.first())
+            .map(|s| Some(s.name.clone()))
+            .ok_or_else(|| NO_LOOPBACK.to_string()),
+    }
+}
+
+/// The monitor source to open for a desktop device setting, or `None` for a
+/// regular input device. Only Linux has monitor sources.
+pub fn resolve(name: &Option<String>) -> Result<Option<String>, String> {
+    if !cfg!(target_os = "linux") {
+        return Ok(None);
+    }
+    let default_sink = match name {
+        None => pactl(&["info"]).and_then(|info| parse_default_sink(&info)),
+        Some(_) => None,
+    };
+    select_monitor(name, &monitor_sources(), default_sink.as_deref())
+}
+
+/// Records `source` with `parec`, which ships with `pactl`. Its interleaved
+/// stereo output is read like a pipe, so the channel shaping still applies.
+pub fn open_monitor(
+    source: &str,
+    sample_rate: u32,
+    shaping: InputShaping,
+    on_data: DataCallback,
+    on_error: ErrorCallback,
+) -> Result<InputStream, String> {
+    let mut command = Command::new("parec");
+    command.args([
+        format!("--device={source}"),
+        format!("--rate={sample_rate}"),
+        "--format=float32le".to_string(),
+        "--channels=2".to_string(),
+        "--latency-msec=20".to_string(),
+        "--raw".to_string(),
+    ]);
+    println!("Using loopback source: {source}");
+    let settings = PipeSettings {
+        path: source.to_string(),
+        format: PipeFormat::F32Le,
+        sample_rate,
+        channels: 2,
+    };
+    crate::pipe::open_command(command, settings, shaping, on_data, on_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: &str = "Source #52
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+\tDescription: Monitor of Built-in Audio Analog Stereo
+\tMonitor of Sink: alsa_output.pci-0000_00_1f.3.analog-stereo
+
+Source #53
+\tState: RUNNING
+\tName: alsa_input.pci-0000_00_1f.3.analog-stereo
+\tDescription: Built-in Audio Analog Stereo
+\tMonitor of Sink: n/a
+
+Source #60
+\tName: bluez_output.headset.1.monitor
+\tDescription: Monitor of Headset
+\tMonitor of Sink: bluez_output.headset.1
+";
+
+    #[test]
+    fn only_monitor_sources_are_listed() {
+        let sources = parse_monitor_sources(SOURCES);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources[0].label(),
+            "Loopback: Monitor of Built-in Audio Analog Stereo"
+        );
+        assert_eq!(sources[1].name, "bluez_output.headset.1.monitor");
+        assert_eq!(sources[1].sink, "bluez_output.headset.1");
+        assert_eq!(
+            parse_default_sink("Server Name: PulseAudio (on PipeWire 1.0.5)\nDefault Sink: bluez_output.headset.1\nDefault Source: x\n"),
+            Some("bluez_output.headset.1".to_string())
+        );
+    }
+
+    #[test]
+    fn default_sink_monitor_is_chosen_when_nothing_is_selected() {
+        let sources = parse_monitor_sources(SOURCES);
+        assert_eq!(
+            select_monitor(&None, &sources, Some("bluez_output.headset.1")),
+            Ok(Some("bluez_output.headset.1.monitor".to_string()))
+        );
+        // Unknown default sink: any monitor beats recording the microphone.
+        assert_eq!(
+            select_monitor(&None, &sources, None),
+            Ok(Some(sources[0].name.clone()))
+        );
+        assert!(select_monitor(&None, &[], None)
+            .unwrap_err()
+            .contains("No loopback source"));
+    }
+
+    #[test]
+    fn selected_devices_resolve_by_label() {
+        let sources = parse_monitor_sources(SOURCES);
+        let label = Some("Loopback: Monitor of Headset".to_string());
+        assert_eq!(
+            select_monitor(&label, &sources, None),
+            Ok(Some("bluez_output.headset.1.monitor".to_string()))
+        );
+        let gone = Some("Loopback: Monitor of HDMI".to_string());
+        assert!(select_monitor(&gone, &sources, None).is_err());
+        let plain = Some("BlackHole 2ch".to_string());
+        assert_eq!(select_monitor(&plain, &sources, None), Ok(None));
+    }
+}
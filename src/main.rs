@@ -1,11 +1,36 @@
 mod app;
 mod audio_recorder;
+mod backend;
+mod capture;
 mod config;
+mod dsp;
+mod encoder;
 mod groq_request;
+mod hallucination;
 mod icon;
+mod loopback;
+mod mic_check;
+mod mixer;
+mod pauses;
+mod pipe;
+mod player;
+mod preroll;
+mod prompt_context;
+mod quality;
+mod recovery;
+mod replay;
+mod resampler;
+mod ring_buffer;
+mod routing;
 mod shortcut_detector;
+#[cfg(test)]
+mod synthetic;
+mod text_normalization;
+mod tracks;
 mod transcription_history;
+mod translation;
 mod utils;
+mod vad;
 
 use app::WgoApp;
 use config::AppConfig;
@@ -23,6 +48,8 @@ fn main() {
             config.toggle_shortcut.clone(),
             config.show_window_shortcut.clone(),
             config.hold_to_record_key.clone(),
+            config.translate_shortcut.clone(),
+            config.replay_shortcut.clone(),
         ),
     );
 
@@ -0,0 +1,203 @@
+use crate::backend::{AudioBackend, InputConfig, InputStream};
+use crate::capture::{build_mono_output_stream, InputShaping};
+use crate::dsp::{DspChain, DspSettings};
+use crate::quality::{AudioQuality, QualityMeter};
+use crate::resampler::Resampler;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Length of the test take.
+pub const MIC_CHECK_SECS: f32 = 5.0;
+
+/// Turns raw microphone input into what a recording would contain: resampled,
+/// processed and scaled by the mix gain.
+struct TakeBuilder {
+    resampler: Resampler,
+    dsp: DspChain,
+    gain: f32,
+    resampled: Vec<f32>,
+    samples: Vec<f32>,
+    len: usize,
+}
+
+impl TakeBuilder {
+    fn new(input_rate: u32, sample_rate: u32, dsp: DspSettings, gain: f32) -> Self {
+        let len = (MIC_CHECK_SECS * sample_rate as f32) as usize;
+        Self {
+            resampler: Resampler::new(input_rate, sample_rate),
+            dsp: DspChain::new(sample_rate, dsp),
+            gain,
+            resampled: Vec::new(),
+            samples: Vec::with_capacity(len),
+            len,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.samples.len() >= self.len
+    }
+
+    fn push(&mut self, data: &[f32]) {
+        if self.is_full() {
+            return;
+        }
+        self.resampled.clear();
+        self.resampler.process(data, &mut self.resampled);
+        self.dsp.process(&mut self.resampled);
+        let room = self.len - self.samples.len();
+        self.samples.extend(
+            self.resampled
+                .iter()
+                .take(room)
+                .map(|s| (s * self.gain).clamp(-1.0, 1.0)),
+        );
+    }
+}
+
+pub enum MicCheckState {
+    Recording,
+    Playing,
+    Finished(AudioQuality),
+}
+
+/// Records a few seconds from the microphone with the active settings and
+/// plays them back, so users hear what gets sent for transcription.
+pub struct MicCheck {
+    input: Option<InputStream>,
+    output: Option<cpal::Stream>,
+    take: Arc<Mutex<TakeBuilder>>,
+    sample_rate: u32,
+    played: Arc<AtomicUsize>,
+    playback_len: usize,
+    quality: Option<AudioQuality>,
+}
+
+impl MicCheck {
+    pub fn start(
+        backend: &dyn AudioBackend,
+        input: &InputConfig,
+        sample_rate: u32,
+        dsp: DspSettings,
+        shaping: InputShaping,
+        gain: f32,
+    ) -> Result<Self, String> {
+        let take = Arc::new(Mutex::new(TakeBuilder::new(
+            input.sample_rate,
+            sample_rate,
+            dsp,
+            gain,
+        )));
+        let stream = {
+            let take = take.clone();
+            backend.open_input(
+                input,
+                shaping,
+                Box::new(move |data: &[f32]| {
+                    if let Ok(mut take) = take.lock() {
+                        take.push(data);
+                    }
+                }),
+                Box::new(move |err| eprintln!("Microphone check stream error: {}", err)),
+            )?
+        };
+
+        Ok(Self {
+            input: Some(stream),
+            output: None,
+            take,
+            sample_rate,
+            played: Arc::new(AtomicUsize::new(0)),
+            playback_len: 0,
+            quality: None,
+        })
+    }
+
+    /// Seconds recorded so far, or `None` once playback started.
+    pub fn recorded_secs(&self) -> Option<f32> {
+        self.input.as_ref()?;
+        let take = self.take.lock().ok()?;
+        Some(take.samples.len() as f32 / self.sample_rate as f32)
+    }
+
+    /// Moves on from recording to playback once the take is complete, and
+    /// reports where the check is. Call it every frame.
+    pub fn poll(&mut self) -> Result<MicCheckState, String> {
+        if self.input.is_some() {
+            let Ok(take) = self.take.lock() else {
+                return Err("Failed to read the microphone check".to_string());
+            };
+            if !take.is_full() {
+                return Ok(MicCheckState::Recording);
+            }
+            let samples = take.samples.clone();
+            drop(take);
+            self.input = None;
+
+            let mut meter = QualityMeter::new(self.sample_rate);
+            meter.process(&samples);
+            self.quality = meter.summary();
+            self.start_playback(&samples)?;
+        }
+
+        match self.quality {
+            Some(quality) if self.played.load(Ordering::SeqCst) >= self.playback_len => {
+                self.output = None;
+                Ok(MicCheckState::Finished(quality))
+            }
+            Some(_) => Ok(MicCheckState::Playing),
+            None => Err("The microphone check recorded nothing".to_string()),
+        }
+    }
+
+    fn start_playback(&mut self, samples: &[f32]) -> Result<(), String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No output device found for playback")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output configuration: {e}"))?;
+        let config = supported.config();
+
+        let mut playback = Vec::new();
+        let mut resampler = Resampler::new(self.sample_rate, config.sample_rate.0);
+        resampler.process(samples, &mut playback);
+        resampler.finish(&mut playback);
+        self.playback_len = playback.len();
+
+        let played = self.played.clone();
+        let stream = build_mono_output_stream(
+            &device,
+            &config,
+            supported.sample_format(),
+            move || {
+                let i = played.fetch_add(1, Ordering::SeqCst);
+                playback.get(i).copied().unwrap_or(0.0)
+            },
+            move |err| eprintln!("Playback stream error: {}", err),
+        )?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start playback: {e}"))?;
+        self.output = Some(stream);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_is_resampled_to_the_check_length_with_gain() {
+        let mut take = TakeBuilder::new(48_000, 16_000, DspSettings::default(), 0.5);
+        let block = vec![0.8f32; 4_800];
+        for _ in 0..60 {
+            take.push(&block);
+        }
+        assert!(take.is_full());
+        assert_eq!(take.samples.len(), 80_000);
+        let settled = &take.samples[1_000..];
+        assert!(settled.iter().all(|s| (s - 0.4).abs() < 0.01));
+    }
+}
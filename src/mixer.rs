@@ -0,0 +1,213 @@
+/// Mic and desktop each get half the headroom unless configured otherwise.
+pub const DEFAULT_MIX_GAIN: f32 = 0.5;
+
+/// How much desktop audio is kept queued, so short scheduling hiccups of
+/// either stream don't starve the mixer.
+const TARGET_FILL_SECS: f32 = 0.1;
+/// Above this the queue is cut back to the target in one go.
+const MAX_FILL_SECS: f32 = 1.0;
+/// Largest read-speed correction (0.5 %), far above real clock drift.
+const MAX_RATE_CORRECTION: f64 = 0.005;
+/// Correction per unit of relative fill error.
+const CORRECTION_GAIN: f64 = 0.01;
+/// Smoothing of the fill error, per pulled block.
+const ERROR_SMOOTHING: f64 = 0.05;
+
+/// Queue between the desktop stream and the mic callback, which drives the
+/// output clock. Both sides run at the same nominal rate, but two devices'
+/// clocks never match exactly; the reader speeds up or slows down slightly to
+/// keep the queue near its target fill instead of letting it run dry or
+/// overflow.
+pub struct DriftBuffer {
+    queue: std::collections::VecDeque<f32>,
+    target: usize,
+    max: usize,
+    primed: bool,
+    /// Fractional read position into `queue`.
+    pos: f64,
+    smoothed_error: f64,
+}
+
+impl DriftBuffer {
+    pub fn new(sample_rate: u32) -> Self {
+        let target = ((sample_rate as f32 * TARGET_FILL_SECS) as usize).max(2);
+        let max = ((sample_rate as f32 * MAX_FILL_SECS) as usize).max(target * 2);
+        Self {
+            queue: std::collections::VecDeque::with_capacity(max),
+            target,
+            max,
+            primed: false,
+            pos: 0.0,
+            smoothed_error: 0.0,
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.primed = false;
+        self.pos = 0.0;
+        self.smoothed_error = 0.0;
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        self.queue.extend(samples);
+        if self.queue.len() > self.max {
+            let excess = self.queue.len() - self.target;
+            self.queue.drain(..excess);
+            self.pos = 0.0;
+        }
+    }
+
+    /// Current read speed: above 1.0 when the desktop clock runs fast.
+    fn read_rate(&self) -> f64 {
+        1.0 + (self.smoothed_error * CORRECTION_GAIN)
+            .clamp(-MAX_RATE_CORRECTION, MAX_RATE_CORRECTION)
+    }
+
+    /// Appends exactly `n` desktop samples to `out`, silence while the queue
+    /// is still filling up or has run dry.
+    pub fn pull(&mut self, n: usize, out: &mut Vec<f32>) {
+        let start = out.len();
+        if !self.primed {
+            if self.queue.len() < self.target {
+                out.resize(start + n, 0.0);
+                return;
+            }
+            self.primed = true;
+        }
+
+        let error = (self.queue.len() as f64 - self.target as f64) / self.target as f64;
+        self.smoothed_error += ERROR_SMOOTHING * (error - self.smoothed_error);
+        let rate = self.read_rate();
+
+        for _ in 0..n {
+            let index = self.pos as usize;
+            if index + 1 >= self.queue.len() {
+                // Ran dry: wait for the target fill again.
+                self.primed = false;
+                break;
+            }
+            let frac = (self.pos - index as f64) as f32;
+            let a = self.queue[index];
+            let b = self.queue[index + 1];
+            out.push(a + (b - a) * frac);
+            self.pos += rate;
+        }
+        out.resize(start + n, 0.0);
+
+        let consumed = (self.pos as usize).min(self.queue.len());
+        self.queue.drain(..consumed);
+        self.pos -= consumed as f64;
+        if !self.primed {
+            self.pos = 0.0;
+        }
+    }
+}
+
+/// Mixes one mic and one desktop sample with their gains, clipped to range.
+pub fn mix(mic: f32, desktop: f32, mic_gain: f32, desktop_gain: f32) -> f32 {
+    (mic * mic_gain + desktop * desktop_gain).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+    const BLOCK: usize = 160;
+
+    fn sine_at(i: u64) -> f32 {
+        (2.0 * std::f32::consts::PI * 200.0 * i as f32 / RATE as f32).sin() * 0.5
+    }
+
+    /// Runs `secs` of 10 ms mic blocks while the desktop side delivers
+    /// `speed` times as many samples. Returns the mixed-in desktop audio.
+    fn simulate(speed: f64, secs: usize) -> (DriftBuffer, Vec<f32>) {
+        let mut buffer = DriftBuffer::new(RATE);
+        let mut produced = 0u64;
+        let mut owed = 0.0f64;
+        let mut out = Vec::new();
+        for _ in 0..secs * 100 {
+            owed += BLOCK as f64 * speed;
+            let count = owed as u64;
+            owed -= count as f64;
+            let block: Vec<f32> = (produced..produced + count).map(sine_at).collect();
+            produced += count;
+            buffer.push(&block);
+            buffer.pull(BLOCK, &mut out);
+        }
+        (buffer, out)
+    }
+
+    fn largest_step(samples: &[f32]) -> f32 {
+        samples
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn fast_desktop_clock_does_not_overflow() {
+        let (buffer, out) = simulate(1.002, 120);
+        assert_eq!(out.len(), 120 * RATE as usize);
+        let target = (RATE as f32 * TARGET_FILL_SECS) as usize;
+        assert!(buffer.len() < target * 2, "queue grew to {}", buffer.len());
+        // No drops or gaps: the 200 Hz tone stays smooth after priming.
+        assert!(largest_step(&out[RATE as usize..]) < 0.05);
+    }
+
+    #[test]
+    fn slow_desktop_clock_does_not_run_dry() {
+        let (buffer, out) = simulate(0.998, 120);
+        let target = (RATE as f32 * TARGET_FILL_SECS) as usize;
+        assert!(buffer.len() > target / 2, "queue fell to {}", buffer.len());
+        assert!(largest_step(&out[RATE as usize..]) < 0.05);
+    }
+
+    #[test]
+    fn matching_clocks_pass_audio_through_unchanged() {
+        let (_, out) = simulate(1.0, 5);
+        let first = out.iter().position(|s| *s != 0.0).expect("audio");
+        for (i, sample) in out[first..].iter().enumerate().take(8_000) {
+            assert!((sample - sine_at(i as u64 + 1)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn silence_until_primed_and_after_running_dry() {
+        let mut buffer = DriftBuffer::new(RATE);
+        let mut out = Vec::new();
+        buffer.push(&[0.3; 100]);
+        buffer.pull(50, &mut out);
+        assert_eq!(out, vec![0.0; 50]);
+
+        buffer.push(&[0.3; 2_000]);
+        out.clear();
+        buffer.pull(50, &mut out);
+        assert!(out.iter().all(|s| (s - 0.3).abs() < 1e-6));
+
+        out.clear();
+        buffer.pull(4_000, &mut out);
+        assert_eq!(out.len(), 4_000);
+        assert_eq!(out[3_999], 0.0);
+    }
+
+    #[test]
+    fn overflow_cuts_back_to_target() {
+        let mut buffer = DriftBuffer::new(RATE);
+        buffer.push(&vec![0.1; RATE as usize * 2]);
+        assert_eq!(buffer.len(), (RATE as f32 * TARGET_FILL_SECS) as usize);
+    }
+
+    #[test]
+    fn mix_applies_gains_and_clips() {
+        assert_eq!(mix(0.4, 0.2, 0.5, 0.5), 0.3);
+        assert_eq!(mix(0.4, 0.2, 1.0, 0.0), 0.4);
+        assert_eq!(mix(0.9, 0.9, 1.0, 1.0), 1.0);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::groq_request::{Transcript, TranscriptionOptions};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Pauses longer than this are shortened to it.
+const KEPT_PAUSE_SECS: f32 = 0.6;
+/// Not worth a second file for less than this.
+const MIN_SAVED_SECS: f32 = 1.0;
+
+/// Maps times in the shortened audio back to the original recording.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OffsetMap {
+    /// `(shortened, original)` start of each kept stretch, in seconds.
+    points: Vec<(f64, f64)>,
+}
+
+impl OffsetMap {
+    pub fn to_original(&self, secs: f64) -> f64 {
+        let index = self
+            .points
+            .partition_point(|&(shortened, _)| shortened <= secs);
+        match index.checked_sub(1).map(|i| self.points[i]) {
+            Some((shortened, original)) => original + (secs - shortened),
+            None => secs,
+        }
+    }
+
+    /// Seconds the shortened audio lost.
+    pub fn removed_secs(&self) -> f64 {
+        self.points
+            .last()
+            .map_or(0.0, |&(shortened, original)| original - shortened)
+    }
+}
+
+/// Shortens every pause longer than [`KEPT_PAUSE_SECS`] to that length by
+/// cutting out its middle, so the speech on both sides keeps some air.
+pub fn compress_pauses(samples: &[f32], sample_rate: u32) -> (Vec<f32>, OffsetMap) {
+    let frame_len = crate::vad::frame_len(sample_rate);
+    let frames = crate::vad::speech_frames(samples, sample_rate);
+    let keep = ((KEPT_PAUSE_SECS * sample_rate as f32) as usize / frame_len).max(2);
+    let rate = sample_rate as f64;
+
+    let mut out = Vec::with_capacity(samples.len());
+    let mut map = OffsetMap::default();
+    let mut copied_to = 0;
+    let mut i = 0;
+    while i < frames.len() {
+        if frames[i] {
+            i += 1;
+            continue;
+        }
+        let run_end = frames[i..]
+            .iter()
+            .position(|&speech| speech)
+            .map_or(frames.len(), |n| i + n);
+        // Leading and trailing silence count too; trimming them is up to
+        // the VAD trim setting, so only their inner part is cut here.
+        if run_end - i > keep {
+            let cut_start = (i + keep / 2) * frame_len;
+            let cut_end = ((run_end - keep / 2) * frame_len).min(samples.len());
+            out.extend_from_slice(&samples[copied_to..cut_start]);
+            copied_to = cut_end;
+            map.points
+                .push((out.len() as f64 / rate, cut_end as f64 / rate));
+        }
+        i = run_end;
+    }
+    out.extend_from_slice(&samples[copied_to..]);
+    (out, map)
+}
+
+/// Writes an Opus copy of a recording with long pauses shortened.
+/// `None` when the file can't be read or too little would be saved.
+fn shortened_copy(path: &Path) -> Option<(std::path::PathBuf, OffsetMap)> {
+    let (samples, sample_rate) = crate::encoder::read_mono(path)?;
+    let (shortened, map) = compress_pauses(&samples, sample_rate);
+    if map.removed_secs() < MIN_SAVED_SECS as f64 {
+        return None;
+    }
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let copy = std::env::temp_dir().join(format!("wgo_pauses_{}_{ts}.ogg", std::process::id()));
+    let bytes = crate::encoder::encode_opus(&shortened, sample_rate).ok()?;
+    std::fs::write(&copy, bytes).ok()?;
+    println!(
+        "Uploading {} with {:.1}s of pauses removed",
+        path.display(),
+        map.removed_secs()
+    );
+    Some((copy, map))
+}
+
+/// Transcribes `audio_path`, uploading a copy with long pauses shortened when
+/// `enabled`. Segment timestamps always refer to the original recording.
+pub fn transcribe(
+    audio_path: &str,
+    options: &TranscriptionOptions,
+    enabled: bool,
+) -> Result<Transcript, String> {
+    let shortened = enabled
+        .then(|| shortened_copy(Path::new(audio_path)))
+        .flatten();
+    let Some((copy, map)) = shortened else {
+        return crate::groq_request::transcribe_audio(audio_path, options)
+            .map_err(|e| e.to_string());
+    };
+
+    let result = crate::groq_request::transcribe_audio(&copy.to_string_lossy(), options)
+        .map_err(|e| e.to_string());
+    let _ = std::fs::remove_file(&copy);
+    let mut transcript = result?;
+    for segment in &mut transcript.segments {
+        segment.start = map.to_original(segment.start);
+        segment.end = map.to_original(segment.end);
+    }
+    Ok(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn silence(secs: f32) -> Vec<f32> {
+        vec![0.0; (RATE as f32 * secs) as usize]
+    }
+
+    fn voice(secs: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * 150.0 * i as f32 / RATE as f32).sin() * 0.3)
+            .collect()
+    }
+
+    fn secs(samples: &[f32]) -> f64 {
+        samples.len() as f64 / RATE as f64
+    }
+
+    #[test]
+    fn long_pauses_shrink_and_map_back() {
+        // Speech at 0-1 s and 6-7 s in the original.
+        let samples = [voice(1.0), silence(5.0), voice(1.0)].concat();
+        let (shortened, map) = compress_pauses(&samples, RATE);
+
+        assert!(
+            (secs(&shortened) - 2.6).abs() < 0.05,
+            "{}",
+            secs(&shortened)
+        );
+        assert!((map.removed_secs() - 4.4).abs() < 0.05);
+        // Second word starts 0.6 s after the first ends in the upload.
+        assert!((map.to_original(1.6) - 6.0).abs() < 0.05);
+        assert!((map.to_original(2.1) - 6.5).abs() < 0.05);
+        // Before the first cut nothing moves.
+        assert_eq!(map.to_original(0.5), 0.5);
+    }
+
+    #[test]
+    fn short_pauses_are_kept() {
+        let samples = [voice(1.0), silence(0.4), voice(1.0)].concat();
+        let (shortened, map) = compress_pauses(&samples, RATE);
+        assert_eq!(shortened.len(), samples.len());
+        assert_eq!(map, OffsetMap::default());
+        assert_eq!(map.to_original(1.7), 1.7);
+    }
+
+    #[test]
+    fn every_long_pause_gets_its_own_offset() {
+        let samples = [
+            voice(1.0),
+            silence(3.0),
+            voice(1.0),
+            silence(10.0),
+            voice(1.0),
+        ]
+        .concat();
+        let (shortened, map) = compress_pauses(&samples, RATE);
+        assert!((secs(&shortened) - 4.2).abs() < 0.1, "{}", secs(&shortened));
+        // Third word: 1 + 0.6 + 1 + 0.6 = 3.2 s in, 15 s in the original.
+        assert!((map.to_original(3.2) - 15.0).abs() < 0.05);
+        assert!((map.to_original(2.0) - 4.4).abs() < 0.05);
+    }
+}
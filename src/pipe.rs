@@ -0,0 +1,659 @@
+use crate::backend::{
+    AudioBackend, DataCallback, DeviceRole, ErrorCallback, InputConfig, InputStream,
+};
+use crate::capture::{shape_to_mono, InputShaping};
+use crate::resampler::{Resampler, DEFAULT_SAMPLE_RATE};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Most channels a raw stream may have.
+pub const MAX_PIPE_CHANNELS: u16 = 8;
+const READ_BYTES: usize = 4096;
+/// How often a dropped stream tries to wake a reader stuck opening its pipe.
+const WAKE_ATTEMPTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PipeFormat {
+    /// A WAV header, then 16-bit or 32-bit float samples.
+    #[default]
+    Wav,
+    /// Raw signed 16-bit little-endian samples.
+    S16Le,
+    /// Raw 32-bit float little-endian samples.
+    F32Le,
+}
+
+impl PipeFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            PipeFormat::Wav => "WAV",
+            PipeFormat::S16Le => "Raw 16-bit (s16le)",
+            PipeFormat::F32Le => "Raw float (f32le)",
+        }
+    }
+}
+
+/// Where the `Pipe` audio source reads from and how the bytes are laid out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipeSettings {
+    /// Named pipe or file to read; empty reads stdin.
+    pub path: String,
+    pub format: PipeFormat,
+    /// Rate of raw input. WAV input is resampled to it.
+    pub sample_rate: u32,
+    /// Interleaved channels of raw input.
+    pub channels: u16,
+}
+
+impl Default for PipeSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            format: PipeFormat::default(),
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            channels: 1,
+        }
+    }
+}
+
+impl PipeSettings {
+    /// Name the input is shown and profiled under.
+    pub fn label(&self) -> String {
+        match self.path.trim() {
+            "" => "stdin".to_string(),
+            path => path.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    S16,
+    F32,
+}
+
+impl Encoding {
+    fn bytes(self) -> usize {
+        match self {
+            Encoding::S16 => 2,
+            Encoding::F32 => 4,
+        }
+    }
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Reads a WAV header up to the first sample. The sizes in it are ignored,
+/// since a program writing to a pipe can't know them in advance.
+fn read_wav_header(reader: &mut impl Read) -> Result<(Encoding, u16, u32), String> {
+    let header_error = |e: std::io::Error| format!("Failed to read the WAV header: {e}");
+    let mut riff = [0u8; 12];
+    reader.read_exact(&mut riff).map_err(header_error)?;
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return Err("The input is not a WAV stream; set the pipe format to raw".to_string());
+    }
+
+    let mut format = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.read_exact(&mut chunk).map_err(header_error)?;
+        let size = le_u32(&chunk, 4) as u64;
+        match &chunk[..4] {
+            b"data" => {
+                return format.ok_or_else(|| "The WAV stream has no format chunk".to_string())
+            }
+            b"fmt " if size >= 16 => {
+                let mut body = vec![0u8; size as usize];
+                reader.read_exact(&mut body).map_err(header_error)?;
+                let (channels, rate, bits) =
+                    (le_u16(&body, 2), le_u32(&body, 4), le_u16(&body, 14));
+                // WAVE_FORMAT_EXTENSIBLE keeps the real format in its sub-format.
+                let tag = match le_u16(&body, 0) {
+                    0xFFFE if body.len() >= 26 => le_u16(&body, 24),
+                    tag => tag,
+                };
+                let encoding = match (tag, bits) {
+                    (1, 16) => Encoding::S16,
+                    (3, 32) => Encoding::F32,
+                    _ => {
+                        return Err(format!(
+                            "Only 16-bit PCM and 32-bit float WAV can be piped in (format {tag}, {bits} bits)"
+                        ))
+                    }
+                };
+                if channels == 0 || rate == 0 {
+                    return Err("The WAV header has no channels or sample rate".to_string());
+                }
+                format = Some((encoding, channels, rate));
+                std::io::copy(&mut reader.take(size & 1), &mut std::io::sink())
+                    .map_err(header_error)?;
+            }
+            _ => {
+                let padded = size + (size & 1);
+                std::io::copy(&mut reader.take(padded), &mut std::io::sink())
+                    .map_err(header_error)?;
+            }
+        }
+    }
+}
+
+/// Turns bytes into interleaved samples, keeping a split frame for the next
+/// read.
+struct Decoder {
+    encoding: Encoding,
+    channels: usize,
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    fn new(encoding: Encoding, channels: u16) -> Self {
+        Self {
+            encoding,
+            channels: channels.max(1) as usize,
+            pending: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8], out: &mut Vec<f32>) {
+        self.pending.extend_from_slice(bytes);
+        let frame = self.encoding.bytes() * self.channels;
+        let whole = self.pending.len() / frame * frame;
+        let data = &self.pending[..whole];
+        match self.encoding {
+            Encoding::S16 => out.extend(
+                data.chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0),
+            ),
+            Encoding::F32 => out.extend(
+                data.chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            ),
+        }
+        self.pending.drain(..whole);
+    }
+}
+
+struct Listener {
+    shaping: InputShaping,
+    on_data: DataCallback,
+    on_error: ErrorCallback,
+}
+
+/// What a reader thread delivers to. Dropping the stream clears `listener`,
+/// which releases its callbacks right away even if the reader is blocked.
+#[derive(Default)]
+struct Tap {
+    listener: Option<Listener>,
+    ended: bool,
+    /// The reader is still opening a named pipe, waiting for a writer.
+    opening: bool,
+}
+
+type SharedTap = Arc<Mutex<Tap>>;
+
+fn lock(tap: &SharedTap) -> std::sync::MutexGuard<'_, Tap> {
+    tap.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+struct PipeStream {
+    tap: SharedTap,
+    /// The program writing the stream, stopped with it.
+    child: Option<Child>,
+    /// The named pipe a reader may still be waiting to open.
+    path: Option<String>,
+}
+
+impl Drop for PipeStream {
+    fn drop(&mut self) {
+        lock(&self.tap).listener = None;
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(path) = &self.path {
+            wake_reader(&self.tap, path);
+        }
+    }
+}
+
+/// Lets a reader that waits for a writer on the named pipe `path` finish
+/// opening it, so it sees nobody listens and exits instead of taking the
+/// start of the next stream. The reader thread may not have reached the
+/// open yet, so this tries until it has.
+fn wake_reader(tap: &SharedTap, path: &str) {
+    for _ in 0..WAKE_ATTEMPTS {
+        if !lock(tap).opening {
+            return;
+        }
+        open_for_writing(path);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
+/// Opens `path` without waiting for a reader; fails at once if there is none.
+#[cfg(unix)]
+fn open_for_writing(path: &str) {
+    use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+    if std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo()) {
+        let _ = std::fs::OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path);
+    }
+}
+
+#[cfg(not(unix))]
+fn open_for_writing(_path: &str) {}
+
+/// Reads `reader` into `tap` until the stream ends. With `drain`, audio that
+/// arrives while nobody listens is dropped; otherwise the reader stops then.
+fn pump(
+    mut reader: impl Read,
+    settings: &PipeSettings,
+    tap: &SharedTap,
+    drain: bool,
+) -> Result<(), String> {
+    let (encoding, channels, rate) = match settings.format {
+        PipeFormat::Wav => read_wav_header(&mut reader)?,
+        PipeFormat::S16Le => (Encoding::S16, settings.channels, settings.sample_rate),
+        PipeFormat::F32Le => (Encoding::F32, settings.channels, settings.sample_rate),
+    };
+    let mut decoder = Decoder::new(encoding, channels);
+    let mut resampler = Resampler::new(rate, settings.sample_rate);
+    let mut bytes = vec![0u8; READ_BYTES];
+    let (mut interleaved, mut mono, mut resampled) = (Vec::new(), Vec::new(), Vec::new());
+    loop {
+        let n = match reader.read(&mut bytes) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read {}: {e}", settings.label())),
+        };
+        interleaved.clear();
+        decoder.push(&bytes[..n], &mut interleaved);
+
+        let mut tap = lock(tap);
+        let Some(listener) = tap.listener.as_mut() else {
+            if drain {
+                continue;
+            }
+            return Ok(());
+        };
+        shape_to_mono(&interleaved, decoder.channels, listener.shaping, &mut mono);
+        resampled.clear();
+        resampler.process(&mono, &mut resampled);
+        if !resampled.is_empty() {
+            (listener.on_data)(&resampled);
+        }
+    }
+}
+
+/// Marks the input as gone and tells the listener, if any.
+fn finish(tap: &SharedTap, label: &str, result: Result<(), String>) {
+    match result {
+        Ok(()) => println!("Input {label} ended"),
+        Err(err) => eprintln!("{err}"),
+    }
+    let mut tap = lock(tap);
+    tap.ended = true;
+    if let Some(listener) = tap.listener.as_mut() {
+        (listener.on_error)(cpal::StreamError::DeviceNotAvailable);
+    }
+}
+
+/// Runs `command` and reads its output like a pipe laid out as `settings`
+/// describes, until the stream is dropped or the program exits.
+pub(crate) fn open_command(
+    mut command: Command,
+    settings: PipeSettings,
+    shaping: InputShaping,
+    on_data: DataCallback,
+    on_error: ErrorCallback,
+) -> Result<InputStream, String> {
+    let label = settings.label();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {label}: {e}"))?;
+    let Some(stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        return Err(format!("{label} has no output"));
+    };
+    let tap = Arc::new(Mutex::new(Tap {
+        listener: Some(Listener {
+            shaping,
+            on_data,
+            on_error,
+        }),
+        ended: false,
+        opening: false,
+    }));
+    let reader_tap = tap.clone();
+    std::thread::spawn(move || {
+        let result = pump(stdout, &settings, &reader_tap, false);
+        finish(&reader_tap, &settings.label(), result);
+    });
+    Ok(Box::new(PipeStream {
+        tap,
+        child: Some(child),
+        path: None,
+    }))
+}
+
+/// Stdin can only be read once, so a single reader serves every recording
+/// and drops what arrives in between, which keeps each recording live.
+static STDIN_TAP: OnceLock<SharedTap> = OnceLock::new();
+
+/// Audio that another program writes to stdin or a named pipe, for example
+/// `parec`, `ffmpeg` or an SDR receiver.
+pub struct PipeBackend {
+    settings: PipeSettings,
+}
+
+impl PipeBackend {
+    pub fn new(settings: PipeSettings) -> Self {
+        Self { settings }
+    }
+
+    fn stdin_tap(&self) -> SharedTap {
+        STDIN_TAP
+            .get_or_init(|| {
+                let tap = SharedTap::default();
+                let (settings, reader_tap) = (self.settings.clone(), tap.clone());
+                std::thread::spawn(move || {
+                    let result = pump(std::io::stdin().lock(), &settings, &reader_tap, true);
+                    finish(&reader_tap, &settings.label(), result);
+                });
+                tap
+            })
+            .clone()
+    }
+
+    /// Opening a named pipe waits for a writer, so it happens on the reader
+    /// thread; dropping the stream wakes it. Each recording reads one stream,
+    /// up to where the writer closes it.
+    fn path_tap(&self, path: String, listener: Listener) -> SharedTap {
+        let tap = Arc::new(Mutex::new(Tap {
+            listener: Some(listener),
+            ended: false,
+            opening: true,
+        }));
+        let (settings, reader_tap) = (self.settings.clone(), tap.clone());
+        std::thread::spawn(move || {
+            let opened = std::fs::File::open(&path);
+            let listening = {
+                let mut tap = lock(&reader_tap);
+                tap.opening = false;
+                tap.listener.is_some()
+            };
+            let result = opened
+                .map_err(|e| format!("Failed to open {path}: {e}"))
+                .and_then(|file| {
+                    if listening {
+                        pump(file, &settings, &reader_tap, false)
+                    } else {
+                        Ok(())
+                    }
+                });
+            finish(&reader_tap, &path, result);
+        });
+        tap
+    }
+}
+
+impl AudioBackend for PipeBackend {
+    fn input_device_names(&self) -> Result<Vec<String>, String> {
+        Ok(vec![self.settings.label()])
+    }
+
+    fn default_input_name(&self) -> Option<String> {
+        Some(self.settings.label())
+    }
+
+    fn input_config(
+        &self,
+        role: DeviceRole,
+        _name: &Option<String>,
+        _preferred_rate: Option<u32>,
+    ) -> Result<InputConfig, String> {
+        Ok(InputConfig {
+            role,
+            device_name: self.settings.label(),
+            sample_rate: self.settings.sample_rate,
+            monitor: None,
+        })
+    }
+
+    fn open_input(
+        &self,
+        _config: &InputConfig,
+        shaping: InputShaping,
+        on_data: DataCallback,
+        on_error: ErrorCallback,
+    ) -> Result<InputStream, String> {
+        let listener = Listener {
+            shaping,
+            on_data,
+            on_error,
+        };
+        let path = self.settings.path.trim();
+        let tap = match path {
+            "" => {
+                let tap = self.stdin_tap();
+                let mut state = lock(&tap);
+                if state.ended {
+                    return Err("stdin has already ended".to_string());
+                }
+                state.listener = Some(listener);
+                drop(state);
+                tap
+            }
+            path => self.path_tap(path.to_string(), listener),
+        };
+        Ok(Box::new(PipeStream {
+            tap,
+            child: None,
+            path: Some(path.to_string()).filter(|p| !p.is_empty()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn wav_header(tag: u16, channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let mut header = b"RIFF\xff\xff\xff\xffWAVE".to_vec();
+        header.extend(b"fmt \x10\0\0\0");
+        header.extend(tag.to_le_bytes());
+        header.extend(channels.to_le_bytes());
+        header.extend(rate.to_le_bytes());
+        header.extend((rate * channels as u32 * bits as u32 / 8).to_le_bytes());
+        header.extend((channels * bits / 8).to_le_bytes());
+        header.extend(bits.to_le_bytes());
+        // An odd-sized chunk before the samples, as ffmpeg writes a LIST chunk.
+        header.extend(b"LIST\x03\0\0\0abc\0");
+        header.extend(b"data\xff\xff\xff\xff");
+        header
+    }
+
+    #[test]
+    fn streamed_wav_headers_are_read_up_to_the_samples() {
+        let mut stream = wav_header(3, 2, 48_000, 32);
+        stream.extend(0.25f32.to_le_bytes());
+        let mut reader = stream.as_slice();
+        assert_eq!(read_wav_header(&mut reader), Ok((Encoding::F32, 2, 48_000)));
+        assert_eq!(reader, 0.25f32.to_le_bytes());
+
+        let err = read_wav_header(&mut wav_header(1, 1, 16_000, 24).as_slice()).unwrap_err();
+        assert!(err.contains("24 bits"), "{err}");
+        assert!(read_wav_header(&mut &b"not audio at all"[..]).is_err());
+    }
+
+    #[test]
+    fn frames_split_across_reads_are_decoded_whole() {
+        let bytes: Vec<u8> = [16_384i16, -16_384, 8_192, 0]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let mut decoder = Decoder::new(Encoding::S16, 2);
+        let mut out = Vec::new();
+        decoder.push(&bytes[..3], &mut out);
+        assert!(out.is_empty());
+        decoder.push(&bytes[3..], &mut out);
+        assert_eq!(out, vec![0.5, -0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn piped_wav_is_resampled_to_the_configured_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.wav");
+        let mut stream = wav_header(1, 2, 48_000, 16);
+        for _ in 0..48_000 {
+            stream.extend(8_192i16.to_le_bytes());
+            stream.extend(24_576i16.to_le_bytes());
+        }
+        std::fs::write(&path, stream).unwrap();
+
+        let backend = PipeBackend::new(PipeSettings {
+            path: path.to_string_lossy().to_string(),
+            ..PipeSettings::default()
+        });
+        let config = backend
+            .input_config(DeviceRole::Microphone, &None, None)
+            .unwrap();
+        assert_eq!(config.sample_rate, 16_000);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (ended_tx, ended_rx) = mpsc::channel();
+        let sink = received.clone();
+        let _stream = backend
+            .open_input(
+                &config,
+                InputShaping::default(),
+                Box::new(move |data: &[f32]| sink.lock().unwrap().extend_from_slice(data)),
+                Box::new(move |err| {
+                    let _ = ended_tx.send(err);
+                }),
+            )
+            .unwrap();
+
+        let err = ended_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(err, cpal::StreamError::DeviceNotAvailable));
+        let received = received.lock().unwrap();
+        assert!((received.len() as i64 - 16_000).abs() < 100);
+        // Both channels averaged: (0.25 + 0.75) / 2.
+        assert!(received[1_000..15_000]
+            .iter()
+            .all(|s| (s - 0.5).abs() < 0.01));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_dropped_stream_leaves_the_named_pipe_to_the_next_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.fifo");
+        let fifo = std::ffi::CString::new(path.to_string_lossy().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+
+        let backend = PipeBackend::new(PipeSettings {
+            path: path.to_string_lossy().to_string(),
+            ..PipeSettings::default()
+        });
+        let config = backend
+            .input_config(DeviceRole::Microphone, &None, None)
+            .unwrap();
+        let open = |on_data: DataCallback, on_error: ErrorCallback| {
+            backend
+                .open_input(&config, InputShaping::default(), on_data, on_error)
+                .unwrap()
+        };
+        // Nobody writes before this one is dropped; its reader still exits.
+        let first = open(Box::new(|_| {}), Box::new(|_| {}));
+        let tap = first.downcast_ref::<PipeStream>().unwrap().tap.clone();
+        drop(first);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !lock(&tap).ended {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the reader never woke"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (ended_tx, ended_rx) = mpsc::channel();
+        let sink = received.clone();
+        let _stream = open(
+            Box::new(move |data: &[f32]| sink.lock().unwrap().extend_from_slice(data)),
+            Box::new(move |err| {
+                let _ = ended_tx.send(err);
+            }),
+        );
+        let mut stream = wav_header(1, 1, 16_000, 16);
+        for _ in 0..16_000 {
+            stream.extend(8_192i16.to_le_bytes());
+        }
+        // In pieces, so a reader left behind would get some of them.
+        let mut writer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        for piece in stream.chunks(4_000) {
+            std::io::Write::write_all(&mut writer, piece).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        drop(writer);
+
+        ended_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 16_000);
+        assert!(received.iter().all(|&s| s == 0.25));
+    }
+
+    #[test]
+    fn command_output_is_read_until_the_program_exits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.f32");
+        let bytes: Vec<u8> = (0..16_000)
+            .flat_map(|_| [0.5f32, 0.0])
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut command = Command::new("cat");
+        command.arg(&path);
+        let settings = PipeSettings {
+            path: "cat".to_string(),
+            format: PipeFormat::F32Le,
+            sample_rate: 16_000,
+            channels: 2,
+        };
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (ended_tx, ended_rx) = mpsc::channel();
+        let sink = received.clone();
+        let _stream = open_command(
+            command,
+            settings,
+            InputShaping::default(),
+            Box::new(move |data: &[f32]| sink.lock().unwrap().extend_from_slice(data)),
+            Box::new(move |err| {
+                let _ = ended_tx.send(err);
+            }),
+        )
+        .unwrap();
+
+        ended_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 16_000);
+        assert!(received.iter().all(|&s| s == 0.25));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::capture::build_mono_output_stream;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Playback speeds offered in the History tab. Speed changes the pitch too.
+pub const PLAYBACK_SPEEDS: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+/// Bars in the rendered waveform.
+const WAVEFORM_BUCKETS: usize = 400;
+const NO_SEEK: u64 = u64::MAX;
+
+/// Decodes a recording for the player. Slow for long files, so callers run
+/// it off the UI thread.
+pub fn load(path: &str) -> Result<(Vec<f32>, u32), String> {
+    match crate::encoder::read_mono(Path::new(path)) {
+        Some((samples, rate)) if !samples.is_empty() && rate > 0 => Ok((samples, rate)),
+        Some(_) => Err(format!("{path} contains no audio")),
+        None => Err(format!(
+            "Can't play {path}; only WAV, FLAC and Ogg Opus are supported"
+        )),
+    }
+}
+
+/// Peak level of each of `buckets` equal slices of `samples`.
+pub fn waveform(samples: &[f32], buckets: usize) -> Vec<f32> {
+    if samples.is_empty() || buckets == 0 {
+        return Vec::new();
+    }
+    let size = samples.len().div_ceil(buckets);
+    samples
+        .chunks(size)
+        .map(|chunk| chunk.iter().fold(0.0f32, |peak, s| peak.max(s.abs())))
+        .collect()
+}
+
+/// Linearly interpolated sample at a fractional index; `None` past the end.
+fn sample_at(samples: &[f32], position: f64) -> Option<f32> {
+    let i = position as usize;
+    let a = *samples.get(i)?;
+    let b = samples.get(i + 1).copied().unwrap_or(a);
+    Some(a + (b - a) * (position - i as f64) as f32)
+}
+
+pub fn format_time(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Plays one recording through the default output device.
+pub struct Player {
+    path: String,
+    _stream: cpal::Stream,
+    sample_rate: u32,
+    len: usize,
+    waveform: Vec<f32>,
+    playing: Arc<AtomicBool>,
+    /// Sample index the output callback is at.
+    position: Arc<AtomicU64>,
+    /// Sample index to jump to, or `NO_SEEK`.
+    seek: Arc<AtomicU64>,
+    speed_bits: Arc<AtomicU32>,
+}
+
+impl Player {
+    /// Opens the output stream paused at the start of `samples`.
+    pub fn new(path: String, samples: Vec<f32>, sample_rate: u32) -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No output device found for playback")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output configuration: {e}"))?;
+        let config = supported.config();
+
+        let len = samples.len();
+        let waveform = waveform(&samples, WAVEFORM_BUCKETS);
+        let playing = Arc::new(AtomicBool::new(false));
+        let position = Arc::new(AtomicU64::new(0));
+        let seek = Arc::new(AtomicU64::new(NO_SEEK));
+        let speed_bits = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let rate_ratio = sample_rate as f64 / config.sample_rate.0 as f64;
+
+        let stream = {
+            let (playing, position, seek, speed_bits) = (
+                playing.clone(),
+                position.clone(),
+                seek.clone(),
+                speed_bits.clone(),
+            );
+            let mut head = 0.0f64;
+            build_mono_output_stream(
+                &device,
+                &config,
+                supported.sample_format(),
+                move || {
+                    if seek.load(Ordering::Relaxed) != NO_SEEK {
+                        head = seek.swap(NO_SEEK, Ordering::SeqCst) as f64;
+                    }
+                    if !playing.load(Ordering::Relaxed) {
+                        return 0.0;
+                    }
+                    match sample_at(&samples, head) {
+                        Some(sample) => {
+                            let speed = f32::from_bits(speed_bits.load(Ordering::Relaxed));
+                            head += rate_ratio * speed as f64;
+                            position.store(head as u64, Ordering::Relaxed);
+                            sample
+                        }
+                        None => {
+                            playing.store(false, Ordering::SeqCst);
+                            0.0
+                        }
+                    }
+                },
+                move |err| eprintln!("Playback stream error: {}", err),
+            )?
+        };
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start playback: {e}"))?;
+
+        Ok(Self {
+            path,
+            _stream: stream,
+            sample_rate,
+            len,
+            waveform,
+            playing,
+            position,
+            seek,
+            speed_bits,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn waveform(&self) -> &[f32] {
+        &self.waveform
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::SeqCst)
+    }
+
+    /// Plays from the current position, or from the start once finished.
+    pub fn play(&self) {
+        if self.position.load(Ordering::SeqCst) as usize + 1 >= self.len {
+            self.seek(0.0);
+        }
+        self.playing.store(true, Ordering::SeqCst);
+    }
+
+    pub fn pause(&self) {
+        self.playing.store(false, Ordering::SeqCst);
+    }
+
+    pub fn seek(&self, secs: f64) {
+        let index = ((secs.max(0.0) * self.sample_rate as f64) as u64).min(self.len as u64);
+        self.position.store(index, Ordering::SeqCst);
+        self.seek.store(index, Ordering::SeqCst);
+    }
+
+    pub fn position_secs(&self) -> f64 {
+        self.position.load(Ordering::SeqCst) as f64 / self.sample_rate as f64
+    }
+
+    pub fn duration_secs(&self) -> f64 {
+        self.len as f64 / self.sample_rate as f64
+    }
+
+    pub fn speed(&self) -> f32 {
+        f32::from_bits(self.speed_bits.load(Ordering::SeqCst))
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        self.speed_bits.store(speed.to_bits(), Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waveform_keeps_the_peak_of_each_slice() {
+        let samples = [0.1, -0.5, 0.2, 0.3, -0.9, 0.0, 0.4];
+        assert_eq!(waveform(&samples, 3), vec![0.5, 0.9, 0.4]);
+        assert_eq!(waveform(&samples, 100).len(), samples.len());
+        assert!(waveform(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn fractional_positions_are_interpolated() {
+        let samples = [0.0, 1.0, -1.0];
+        assert_eq!(sample_at(&samples, 0.5), Some(0.5));
+        assert_eq!(sample_at(&samples, 1.25), Some(0.5));
+        assert_eq!(sample_at(&samples, 2.5), Some(-1.0));
+        assert_eq!(sample_at(&samples, 3.0), None);
+    }
+
+    #[test]
+    fn times_read_as_minutes_and_seconds() {
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!(format_time(65.9), "1:05");
+        assert_eq!(format_time(3_600.0), "60:00");
+    }
+}
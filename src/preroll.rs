@@ -0,0 +1,58 @@
+use crate::ring_buffer::RingBuffer;
+
+/// Longest pre-roll that can be configured.
+pub const MAX_PREROLL_SECS: f32 = 10.0;
+
+/// The most recent input samples, kept while no recording is running so a
+/// recording can start with what was said just before the hotkey.
+pub struct PreRoll {
+    samples: RingBuffer<f32>,
+    sample_rate: u32,
+}
+
+impl PreRoll {
+    pub fn new(secs: f32, sample_rate: u32) -> Self {
+        let capacity = (secs.clamp(0.0, MAX_PREROLL_SECS) * sample_rate as f32) as usize;
+        Self {
+            samples: RingBuffer::new(capacity),
+            sample_rate,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn push(&mut self, input: &[f32]) {
+        self.samples.extend(input.iter().copied());
+    }
+
+    /// Empties the buffer, oldest sample first.
+    pub fn take(&mut self) -> Vec<f32> {
+        self.samples.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_most_recent_samples() {
+        let mut preroll = PreRoll::new(1.0, 4);
+        preroll.push(&[1.0, 2.0, 3.0]);
+        preroll.push(&[4.0, 5.0]);
+        assert_eq!(preroll.take(), vec![2.0, 3.0, 4.0, 5.0]);
+        assert!(preroll.take().is_empty());
+
+        preroll.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(preroll.take(), vec![3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn zero_seconds_keeps_nothing() {
+        let mut preroll = PreRoll::new(0.0, 16_000);
+        preroll.push(&[0.5; 100]);
+        assert!(preroll.take().is_empty());
+    }
+}
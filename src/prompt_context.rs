@@ -0,0 +1,188 @@
+use crate::config::AppConfig;
+use crate::transcription_history::TranscriptionHistory;
+use serde::{Deserialize, Serialize};
+
+/// Whisper only looks at the last 224 tokens of a prompt.
+pub const DEFAULT_PROMPT_TOKEN_BUDGET: usize = 224;
+/// A transcription newer than this counts as part of the same dictation.
+pub const DEFAULT_PROMPT_RECENT_SECS: u64 = 120;
+
+/// Where the Whisper `prompt` for the next job comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum PromptSource {
+    #[default]
+    Off,
+    Clipboard,
+    PreviousTranscription,
+}
+
+impl PromptSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptSource::Off => "Off",
+            PromptSource::Clipboard => "Clipboard",
+            PromptSource::PreviousTranscription => "Previous transcription",
+        }
+    }
+}
+
+/// Rough GPT-2 BPE estimate: common words are one token, longer ones about
+/// one per four characters.
+fn estimate_tokens(word: &str) -> usize {
+    word.chars().count().div_ceil(4).max(1)
+}
+
+/// Keeps the tail of `text` that fits into `budget` tokens. Whisper reads the
+/// prompt as the text spoken right before the clip, so the end matters most.
+pub fn fit_to_token_budget(text: &str, budget: usize) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut used = 0usize;
+    let mut start = words.len();
+    while start > 0 {
+        let cost = estimate_tokens(words[start - 1]);
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        start -= 1;
+    }
+
+    if start == words.len() {
+        return None;
+    }
+    Some(words[start..].join(" "))
+}
+
+/// The latest usable transcription, if it was made within `recent_secs` of
+/// `now` (i.e. while dictating in consecutive bursts).
+pub fn previous_transcription(
+    history: &TranscriptionHistory,
+    now: u64,
+    recent_secs: u64,
+) -> Option<&str> {
+    let record = history.latest()?;
+    if record.suspicious.is_some() || now.saturating_sub(record.timestamp) > recent_secs {
+        return None;
+    }
+    let text = record.transcription.trim();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Builds the prompt for the next job according to `config.prompt_source`.
+pub fn build_prompt(
+    config: &AppConfig,
+    history: &TranscriptionHistory,
+    now: u64,
+) -> Option<String> {
+    let source = match config.prompt_source {
+        PromptSource::Off => return None,
+        PromptSource::Clipboard => crate::utils::read_clipboard_text()?,
+        PromptSource::PreviousTranscription => {
+            previous_transcription(history, now, config.prompt_recent_secs)?.to_string()
+        }
+    };
+    fit_to_token_budget(&source, config.prompt_token_budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracks::TrackLayout;
+    use crate::transcription_history::TranscriptionRecord;
+
+    fn history_with(text: &str, timestamp: u64, suspicious: Option<&str>) -> TranscriptionHistory {
+        let mut history = TranscriptionHistory::new();
+        history.records.push(TranscriptionRecord {
+            filename: String::new(),
+            transcription: text.to_string(),
+            timestamp,
+            audio_path: None,
+            route: None,
+            suspicious: suspicious.map(str::to_string),
+            translation: None,
+            translation_language: None,
+            track_layout: TrackLayout::Mixed,
+            quality: None,
+            segments: Vec::new(),
+        });
+        history
+    }
+
+    #[test]
+    fn short_text_is_kept_whole() {
+        assert_eq!(
+            fit_to_token_budget("  Deploy the  Kubernetes cluster ", 224).as_deref(),
+            Some("Deploy the Kubernetes cluster")
+        );
+    }
+
+    #[test]
+    fn long_text_keeps_the_tail() {
+        let text = (1..=500)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let prompt = fit_to_token_budget(&text, 10).expect("prompt");
+        assert_eq!(prompt.split_whitespace().count(), 10);
+        assert!(prompt.ends_with("w500"));
+        assert!(prompt.starts_with("w491"));
+    }
+
+    #[test]
+    fn long_words_cost_more_tokens() {
+        // "internationalization" is 20 chars -> 5 tokens.
+        assert_eq!(
+            fit_to_token_budget("a internationalization", 5).as_deref(),
+            Some("internationalization")
+        );
+        assert_eq!(fit_to_token_budget("internationalization", 4), None);
+    }
+
+    #[test]
+    fn empty_text_or_budget_gives_no_prompt() {
+        assert_eq!(fit_to_token_budget("   ", 224), None);
+        assert_eq!(fit_to_token_budget("hello", 0), None);
+    }
+
+    #[test]
+    fn previous_transcription_only_counts_when_recent() {
+        let history = history_with("Talked to Siobhán about gRPC.", 1_000, None);
+        assert_eq!(
+            previous_transcription(&history, 1_060, 120),
+            Some("Talked to Siobhán about gRPC.")
+        );
+        assert_eq!(previous_transcription(&history, 1_200, 120), None);
+    }
+
+    #[test]
+    fn suspicious_or_missing_transcription_is_not_used() {
+        let history = history_with("Thank you for watching.", 1_000, Some("silent"));
+        assert_eq!(previous_transcription(&history, 1_001, 120), None);
+        assert_eq!(
+            previous_transcription(&TranscriptionHistory::new(), 1_001, 120),
+            None
+        );
+    }
+
+    #[test]
+    fn off_builds_no_prompt() {
+        let history = history_with("recent text", 1_000, None);
+        let config = AppConfig::default();
+        assert_eq!(config.prompt_source, PromptSource::Off);
+        assert_eq!(build_prompt(&config, &history, 1_001), None);
+    }
+
+    #[test]
+    fn previous_transcription_source_respects_budget() {
+        let history = history_with("one two three four", 1_000, None);
+        let config = AppConfig {
+            prompt_source: PromptSource::PreviousTranscription,
+            prompt_token_budget: 3,
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            build_prompt(&config, &history, 1_001).as_deref(),
+            Some("three four")
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const FRAME_SECS: f32 = 0.02;
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.99;
+/// Frame levels are kept in 1 dB bins from -100 to 0 dBFS.
+const HISTOGRAM_FLOOR_DB: f32 = -100.0;
+const HISTOGRAM_BINS: usize = 100;
+/// Share of the quietest frames whose level is taken as the noise floor.
+const NOISE_PERCENTILE: f32 = 0.1;
+const SPEECH_OVER_NOISE_DB: f32 = 10.0;
+const MIN_SPEECH_DBFS: f32 = -50.0;
+/// Live warnings look at this much of the most recent audio.
+const RECENT_SECS: f32 = 5.0;
+
+const CLIPPED_RATIO: f64 = 0.001;
+const QUIET_DBFS: f32 = -35.0;
+const NOISY_FLOOR_DBFS: f32 = -45.0;
+const LOW_SPEECH_RATIO: f32 = 0.1;
+
+fn dbfs(level: f32) -> f32 {
+    20.0 * level.max(1e-5).log10()
+}
+
+/// Level statistics of a finished recording.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AudioQuality {
+    pub rms_dbfs: f32,
+    pub peak_dbfs: f32,
+    pub clipped_samples: u64,
+    pub samples: u64,
+    pub noise_floor_dbfs: f32,
+    /// Share of 20 ms frames that contain speech.
+    pub speech_ratio: f32,
+}
+
+impl AudioQuality {
+    /// What is wrong with the recording; empty when nothing is.
+    pub fn issues(&self) -> Vec<&'static str> {
+        let mut issues = Vec::new();
+        if self.clipped_samples as f64 > self.samples as f64 * CLIPPED_RATIO {
+            issues.push("clipping");
+        }
+        if self.peak_dbfs < QUIET_DBFS {
+            issues.push("very quiet");
+        }
+        if self.noise_floor_dbfs > NOISY_FLOOR_DBFS {
+            issues.push("noisy background");
+        }
+        if self.speech_ratio < LOW_SPEECH_RATIO {
+            issues.push("little speech");
+        }
+        issues
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "RMS {:.0} dBFS, peak {:.0} dBFS, {} clipped samples, noise floor {:.0} dBFS, {:.0}% speech",
+            self.rms_dbfs,
+            self.peak_dbfs,
+            self.clipped_samples,
+            self.noise_floor_dbfs,
+            self.speech_ratio * 100.0
+        )
+    }
+}
+
+/// Collects [`AudioQuality`] while recording, without keeping the audio.
+pub struct QualityMeter {
+    frame_len: usize,
+    frame_squares: f64,
+    frame_fill: usize,
+    sum_squares: f64,
+    samples: u64,
+    peak: f32,
+    clipped: u64,
+    histogram: [u32; HISTOGRAM_BINS],
+    frames: u32,
+    recent: VecDeque<f32>,
+    recent_frames: usize,
+    samples_since_clip: u64,
+    recent_samples: u64,
+}
+
+impl QualityMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = ((sample_rate as f32 * FRAME_SECS) as usize).max(1);
+        let recent_frames = (RECENT_SECS / FRAME_SECS) as usize;
+        Self {
+            frame_len,
+            frame_squares: 0.0,
+            frame_fill: 0,
+            sum_squares: 0.0,
+            samples: 0,
+            peak: 0.0,
+            clipped: 0,
+            histogram: [0; HISTOGRAM_BINS],
+            frames: 0,
+            recent: VecDeque::with_capacity(recent_frames),
+            recent_frames,
+            samples_since_clip: u64::MAX,
+            recent_samples: (sample_rate as f32 * RECENT_SECS) as u64,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let magnitude = sample.abs();
+            let square = (sample as f64) * (sample as f64);
+            self.sum_squares += square;
+            self.frame_squares += square;
+            self.peak = self.peak.max(magnitude);
+            if magnitude >= CLIP_LEVEL {
+                self.clipped += 1;
+                self.samples_since_clip = 0;
+            } else {
+                self.samples_since_clip = self.samples_since_clip.saturating_add(1);
+            }
+            self.frame_fill += 1;
+            if self.frame_fill == self.frame_len {
+                let level = dbfs((self.frame_squares / self.frame_len as f64).sqrt() as f32);
+                self.push_frame(level);
+                self.frame_squares = 0.0;
+                self.frame_fill = 0;
+            }
+        }
+        self.samples += samples.len() as u64;
+    }
+
+    fn push_frame(&mut self, level: f32) {
+        let bin = ((level - HISTOGRAM_FLOOR_DB) as usize).min(HISTOGRAM_BINS - 1);
+        self.histogram[bin] += 1;
+        self.frames += 1;
+        if self.recent.len() == self.recent_frames {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(level);
+    }
+
+    fn noise_floor(&self) -> f32 {
+        let target = (self.frames as f32 * NOISE_PERCENTILE).ceil() as u32;
+        let mut seen = 0;
+        for (bin, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return HISTOGRAM_FLOOR_DB + bin as f32;
+            }
+        }
+        HISTOGRAM_FLOOR_DB
+    }
+
+    /// `None` until some audio came in.
+    pub fn summary(&self) -> Option<AudioQuality> {
+        if self.samples == 0 {
+            return None;
+        }
+        let noise_floor = self.noise_floor();
+        let speech_threshold = (noise_floor + SPEECH_OVER_NOISE_DB).max(MIN_SPEECH_DBFS);
+        let speech_frames: u32 = self
+            .histogram
+            .iter()
+            .enumerate()
+            .filter(|(bin, _)| HISTOGRAM_FLOOR_DB + *bin as f32 >= speech_threshold)
+            .map(|(_, &count)| count)
+            .sum();
+        Some(AudioQuality {
+            rms_dbfs: dbfs((self.sum_squares / self.samples as f64).sqrt() as f32),
+            peak_dbfs: dbfs(self.peak),
+            clipped_samples: self.clipped,
+            samples: self.samples,
+            noise_floor_dbfs: noise_floor,
+            speech_ratio: speech_frames as f32 / self.frames.max(1) as f32,
+        })
+    }
+
+    /// A warning about the last few seconds, for showing while recording.
+    pub fn live_warning(&self) -> Option<&'static str> {
+        if self.samples_since_clip < self.recent_samples {
+            return Some("Input is clipping. Lower the input gain.");
+        }
+        if self.recent.len() < self.recent_frames {
+            return None;
+        }
+        // Only while talking: something rises above the noise, but not much.
+        let loudest = self.recent.iter().copied().fold(f32::MIN, f32::max);
+        if loudest > self.noise_floor() + SPEECH_OVER_NOISE_DB && loudest < QUIET_DBFS {
+            return Some("You are barely audible. Move closer or raise the input gain.");
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn tone(secs: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| {
+                (2.0 * std::f32::consts::PI * 200.0 * i as f32 / RATE as f32).sin() * amplitude
+            })
+            .collect()
+    }
+
+    fn hiss(secs: f32, amplitude: f32) -> Vec<f32> {
+        let mut state = 12345u32;
+        (0..(RATE as f32 * secs) as usize)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    fn measure(samples: &[f32]) -> QualityMeter {
+        let mut meter = QualityMeter::new(RATE);
+        for chunk in samples.chunks(441) {
+            meter.process(chunk);
+        }
+        meter
+    }
+
+    #[test]
+    fn clean_speech_has_no_issues() {
+        let samples = [hiss(2.0, 0.001), tone(3.0, 0.3), hiss(1.0, 0.001)].concat();
+        let quality = measure(&samples).summary().unwrap();
+        assert!(quality.issues().is_empty(), "{quality:?}");
+        assert!((quality.peak_dbfs - dbfs(0.3)).abs() < 0.5);
+        assert!(quality.noise_floor_dbfs < -60.0);
+        assert!((quality.speech_ratio - 0.5).abs() < 0.05, "{quality:?}");
+    }
+
+    #[test]
+    fn clipping_is_counted_and_warned_about() {
+        let samples = [tone(2.0, 0.3), tone(0.5, 1.5)].concat();
+        let meter = measure(&samples);
+        let quality = meter.summary().unwrap();
+        assert!(quality.clipped_samples > 0);
+        assert!(quality.issues().contains(&"clipping"));
+        assert!(meter.live_warning().unwrap().contains("clipping"));
+    }
+
+    #[test]
+    fn quiet_and_noisy_recordings_are_flagged() {
+        let quiet = [hiss(1.0, 0.0005), tone(5.0, 0.01)].concat();
+        let meter = measure(&quiet);
+        assert!(meter.summary().unwrap().issues().contains(&"very quiet"));
+        assert!(meter.live_warning().unwrap().contains("barely audible"));
+
+        let noisy = hiss(6.0, 0.05);
+        let issues = measure(&noisy).summary().unwrap().issues();
+        assert!(issues.contains(&"noisy background"), "{issues:?}");
+        assert!(issues.contains(&"little speech"), "{issues:?}");
+    }
+
+    #[test]
+    fn nothing_recorded_has_no_summary() {
+        assert!(QualityMeter::new(RATE).summary().is_none());
+    }
+}
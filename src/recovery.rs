@@ -0,0 +1,232 @@
+use crate::tracks::{desktop_track_path, TrackLayout};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Marker written next to a recording while it is open; left behind if wgo
+/// never got to finalize the file.
+const MARKER_EXTENSION: &str = "inprogress";
+
+fn marker_path(recording: &Path) -> PathBuf {
+    let mut name = recording.as_os_str().to_owned();
+    name.push(".");
+    name.push(MARKER_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Notes that `recording` is being written with `layout`.
+pub fn mark_in_progress(recording: &Path, layout: TrackLayout) -> Result<(), String> {
+    let content = serde_json::to_string(&layout).map_err(|e| e.to_string())?;
+    fs::write(marker_path(recording), content)
+        .map_err(|e| format!("Failed to write recording marker: {e}"))
+}
+
+/// Notes that `recording` was finalized.
+pub fn mark_finished(recording: &Path) {
+    let _ = fs::remove_file(marker_path(recording));
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Fixes the RIFF and `data` sizes of a WAV whose header was not updated
+/// before wgo stopped. Returns whether anything had to change.
+pub fn repair_wav_header(path: &Path) -> Result<bool, String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let file_len = file.metadata().map_err(|e| e.to_string())?.len();
+
+    // Chunks before `data` are small; the header fits in the first few KB.
+    let mut head = vec![0u8; file_len.min(4096) as usize];
+    file.read_exact(&mut head).map_err(|e| e.to_string())?;
+    if head.len() < 12 || &head[0..4] != b"RIFF" || &head[8..12] != b"WAVE" {
+        return Err(format!("{} is not a WAV file", path.display()));
+    }
+
+    let mut block_align = 1u64;
+    let mut at = 12;
+    while at + 8 <= head.len() {
+        let id = &head[at..at + 4];
+        let size = read_u32(&head, at + 4) as usize;
+        if id == b"fmt " && at + 8 + 14 <= head.len() {
+            block_align = (u16::from_le_bytes([head[at + 20], head[at + 21]]) as u64).max(1);
+        }
+        if id == b"data" {
+            let data_start = (at + 8) as u64;
+            let available = file_len.saturating_sub(data_start);
+            let data_len = (available / block_align * block_align).min(u32::MAX as u64);
+            let riff_len = (data_start + data_len - 8).min(u32::MAX as u64);
+            if size as u64 == data_len && read_u32(&head, 4) as u64 == riff_len {
+                return Ok(false);
+            }
+            file.seek(SeekFrom::Start(4)).map_err(|e| e.to_string())?;
+            file.write_all(&(riff_len as u32).to_le_bytes())
+                .map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::Start(at as u64 + 4))
+                .map_err(|e| e.to_string())?;
+            file.write_all(&(data_len as u32).to_le_bytes())
+                .map_err(|e| e.to_string())?;
+            // Drop a trailing partial sample frame.
+            file.set_len(data_start + data_len)
+                .map_err(|e| e.to_string())?;
+            return Ok(true);
+        }
+        at += 8 + size + size % 2;
+    }
+    Err(format!("{} has no audio data", path.display()))
+}
+
+/// A recording found unfinished and repaired on launch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredRecording {
+    pub path: String,
+    pub track_layout: TrackLayout,
+}
+
+/// Repairs every recording in `dir` that was still being written when wgo
+/// last stopped. Files that can't be repaired are reported and skipped.
+pub fn recover_recordings(dir: &Path) -> Vec<RecoveredRecording> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut markers: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == MARKER_EXTENSION))
+        .collect();
+    markers.sort();
+
+    let mut recovered = Vec::new();
+    for marker in markers {
+        let recording = marker.with_extension("");
+        let track_layout = fs::read_to_string(&marker)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+        let _ = fs::remove_file(&marker);
+        if !recording.exists() {
+            continue;
+        }
+
+        let mut tracks = vec![recording.clone()];
+        if track_layout == TrackLayout::SeparateFiles {
+            tracks.push(desktop_track_path(&recording));
+        }
+        let repaired = tracks.iter().try_for_each(|track| {
+            repair_wav_header(track).map(|changed| {
+                if changed {
+                    println!("Repaired interrupted recording {}", track.display());
+                }
+            })
+        });
+        match repaired {
+            Ok(()) => recovered.push(RecoveredRecording {
+                path: recording.to_string_lossy().to_string(),
+                track_layout,
+            }),
+            Err(err) => eprintln!("Could not recover {}: {err}", recording.display()),
+        }
+    }
+    recovered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavReader, WavSpec, WavWriter};
+    use tempfile::tempdir;
+
+    fn spec(channels: u16) -> WavSpec {
+        WavSpec {
+            channels,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        }
+    }
+
+    /// Writes a WAV the way a crash leaves it: samples on disk, header
+    /// claiming no data.
+    fn crashed_wav(path: &Path, channels: u16, samples: usize) {
+        let mut writer = WavWriter::create(path, spec(channels)).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let mut bytes = fs::read(path).unwrap();
+        for i in 0..samples * channels as usize {
+            bytes.extend_from_slice(&((i % 100) as i16).to_le_bytes());
+        }
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn header_of_interrupted_wav_is_repaired() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("recording_1.wav");
+        crashed_wav(&path, 1, 16_000);
+        assert_eq!(WavReader::open(&path).unwrap().duration(), 0);
+
+        assert!(repair_wav_header(&path).expect("repair"));
+        let reader = WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 16_000);
+        // A second pass has nothing to do.
+        assert!(!repair_wav_header(&path).expect("repair"));
+    }
+
+    #[test]
+    fn partial_stereo_frame_is_dropped() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("stereo.wav");
+        crashed_wav(&path, 2, 1_000);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1, 2]).unwrap();
+        drop(file);
+
+        repair_wav_header(&path).expect("repair");
+        let mut reader = WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 1_000);
+        assert_eq!(reader.samples::<i16>().count(), 2_000);
+    }
+
+    #[test]
+    fn finished_recordings_are_left_alone() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("done.wav");
+        let mut writer = WavWriter::create(&path, spec(1)).unwrap();
+        for i in 0..500 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert!(!repair_wav_header(&path).expect("repair"));
+    }
+
+    #[test]
+    fn only_marked_recordings_are_recovered() {
+        let tmp = tempdir().expect("tempdir");
+        let interrupted = tmp.path().join("recording_2.wav");
+        crashed_wav(&interrupted, 1, 8_000);
+        crashed_wav(&desktop_track_path(&interrupted), 1, 8_000);
+        mark_in_progress(&interrupted, TrackLayout::SeparateFiles).unwrap();
+
+        let finished = tmp.path().join("recording_3.wav");
+        crashed_wav(&finished, 1, 100);
+        mark_in_progress(&finished, TrackLayout::Mixed).unwrap();
+        mark_finished(&finished);
+
+        let recovered = recover_recordings(tmp.path());
+        assert_eq!(
+            recovered,
+            vec![RecoveredRecording {
+                path: interrupted.to_string_lossy().to_string(),
+                track_layout: TrackLayout::SeparateFiles,
+            }]
+        );
+        assert_eq!(WavReader::open(&interrupted).unwrap().duration(), 8_000);
+        let desktop = desktop_track_path(&interrupted);
+        assert_eq!(WavReader::open(&desktop).unwrap().duration(), 8_000);
+        // Markers are consumed.
+        assert!(recover_recordings(tmp.path()).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::backend::{AudioBackend, DeviceRole, InputStream};
+use crate::capture::InputShaping;
+use crate::resampler::{Resampler, DEFAULT_SAMPLE_RATE};
+use crate::ring_buffer::RingBuffer;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Replay lengths offered in the settings and the main window, in minutes.
+pub const REPLAY_MINUTES: [u64; 3] = [1, 5, 10];
+
+const NO_DESKTOP_DEVICE: &str = "no desktop or loopback device found. Pick one as the \
+desktop audio device in Settings.";
+
+/// The last few minutes of desktop audio. Kept as 16-bit samples, so ten
+/// minutes at 16 kHz stay under 20 MB.
+pub struct ReplayBuffer {
+    samples: RingBuffer<i16>,
+    sample_rate: u32,
+}
+
+impl ReplayBuffer {
+    pub fn new(minutes: u64, sample_rate: u32) -> Self {
+        let capacity = (minutes * 60 * sample_rate as u64) as usize;
+        Self {
+            samples: RingBuffer::new(capacity),
+            sample_rate,
+        }
+    }
+
+    pub fn push(&mut self, input: &[f32]) {
+        self.samples.extend(
+            input
+                .iter()
+                .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+        );
+    }
+
+    pub fn buffered_secs(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+
+    /// The last `secs` seconds, oldest first; less if the buffer isn't full yet.
+    pub fn last(&self, secs: u64) -> Vec<i16> {
+        self.samples.last((secs * self.sample_rate as u64) as usize)
+    }
+}
+
+/// Keeps the desktop device open and fills a [`ReplayBuffer`] with it.
+pub struct ReplayCapture {
+    _stream: InputStream,
+    buffer: Arc<Mutex<ReplayBuffer>>,
+    minutes: u64,
+    device_name: Option<String>,
+}
+
+impl ReplayCapture {
+    pub fn start(
+        backend: &dyn AudioBackend,
+        device_name: Option<String>,
+        minutes: u64,
+    ) -> Result<Self, String> {
+        let buffer = Arc::new(Mutex::new(ReplayBuffer::new(minutes, DEFAULT_SAMPLE_RATE)));
+        let input = backend.input_config(DeviceRole::Desktop, &device_name, None)?;
+        // Without a monitor source the default input is the microphone, which
+        // would be recorded all the time instead.
+        if device_name.is_none() && input.monitor.is_none() {
+            return Err(NO_DESKTOP_DEVICE.to_string());
+        }
+        let mut resampler = Resampler::new(input.sample_rate, DEFAULT_SAMPLE_RATE);
+        let mut resampled = Vec::new();
+        let stream = {
+            let buffer = buffer.clone();
+            backend.open_input(
+                &input,
+                InputShaping::default(),
+                Box::new(move |data: &[f32]| {
+                    resampled.clear();
+                    resampler.process(data, &mut resampled);
+                    if let Ok(mut buffer) = buffer.lock() {
+                        buffer.push(&resampled);
+                    }
+                }),
+                Box::new(move |err| eprintln!("Replay stream error: {}", err)),
+            )?
+        };
+
+        Ok(Self {
+            _stream: stream,
+            buffer,
+            minutes,
+            device_name,
+        })
+    }
+
+    /// Whether this capture already matches the given settings.
+    pub fn matches(&self, device_name: &Option<String>, minutes: u64) -> bool {
+        self.device_name == *device_name && self.minutes == minutes
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.minutes
+    }
+
+    pub fn buffered_secs(&self) -> f32 {
+        self.buffer.lock().map(|b| b.buffered_secs()).unwrap_or(0.0)
+    }
+
+    /// Copies out the last `minutes`. Encoding is left to the caller so it
+    /// can happen off the UI thread.
+    pub fn snapshot(&self, minutes: u64) -> Vec<i16> {
+        self.buffer
+            .lock()
+            .map(|b| b.last(minutes * 60))
+            .unwrap_or_default()
+    }
+}
+
+/// Writes a snapshot as `replay_<timestamp>.ogg` into `dir`.
+pub fn save(samples: &[i16], dir: &Path) -> Result<PathBuf, String> {
+    if samples.is_empty() {
+        return Err("The replay buffer is still empty".to_string());
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("replay_{timestamp}.ogg"));
+    let samples: Vec<f32> = samples.iter().map(|&s| s as f32 / 32_768.0).collect();
+    let bytes = crate::encoder::encode_opus(&samples, DEFAULT_SAMPLE_RATE)?;
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to save replay: {e}"))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::SyntheticBackend;
+
+    #[test]
+    fn buffer_keeps_the_configured_minutes() {
+        let mut buffer = ReplayBuffer::new(1, 100);
+        for _ in 0..90 {
+            buffer.push(&[0.5; 100]);
+        }
+        assert_eq!(buffer.buffered_secs(), 60.0);
+        buffer.push(&[-0.5; 50]);
+        let last = buffer.last(1);
+        assert_eq!(last.len(), 100);
+        assert!(last[..50].iter().all(|&s| s > 0));
+        assert!(last[50..].iter().all(|&s| s < 0));
+        // Asking for more than is buffered returns what there is.
+        assert_eq!(buffer.last(600).len(), 6000);
+    }
+
+    #[test]
+    fn replay_needs_a_desktop_device() {
+        let backend = SyntheticBackend::new();
+        backend.add_tone("Mic", 16_000, 440.0, 0.5);
+        backend.add_tone("Speakers", 48_000, 1_000.0, 0.5);
+        let err = ReplayCapture::start(&backend, None, 1)
+            .err()
+            .expect("refused");
+        assert!(err.contains("desktop"), "{err}");
+        assert!(backend.open_streams().is_empty());
+
+        let replay = ReplayCapture::start(&backend, Some("Speakers".to_string()), 1).unwrap();
+        backend.advance(1.0);
+        let secs = replay.buffered_secs();
+        assert!((secs - 1.0).abs() < 0.01, "{secs}");
+    }
+
+    #[test]
+    fn saved_replay_decodes_to_the_snapshot() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let samples: Vec<i16> = (0..16_000).map(|i| ((i % 200) * 50) as i16).collect();
+        let path = save(&samples, tmp.path()).expect("save");
+        let (decoded, rate) = crate::encoder::read_mono(&path).expect("read");
+        assert_eq!(rate, DEFAULT_SAMPLE_RATE);
+        assert_eq!(decoded.len(), samples.len());
+        assert!(save(&[], tmp.path()).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+/// Whisper is trained on 16 kHz audio; more only makes uploads bigger.
+pub const DEFAULT_SAMPLE_RATE: u32 = 16_000;
+
+/// Taps on each side of the interpolation point.
+const HALF_TAPS: usize = 16;
+/// Upper bound for the precomputed filter phases. Ratios with more phases
+/// (e.g. 44100 → 16001) round to the nearest one.
+const MAX_PHASES: usize = 1024;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+fn blackman(x: f64) -> f64 {
+    // `x` runs from -1.0 to 1.0 across the window.
+    let t = std::f64::consts::PI * (x + 1.0);
+    0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos()
+}
+
+/// Streaming windowed-sinc resampler for mono f32 audio.
+///
+/// The rate ratio is kept as an exact fraction, so long recordings do not
+/// drift: one second in is always one second out.
+pub struct Resampler {
+    /// Input samples per output sample, as `step_num / step_den`.
+    step_num: u64,
+    step_den: u64,
+    /// `phases x (2 * HALF_TAPS)` filter coefficients.
+    kernel: Vec<f32>,
+    phases: usize,
+    /// Input samples not consumed yet; `buf[0]` is input index `buf_start`.
+    buf: Vec<f32>,
+    buf_start: u64,
+    /// Integer part and fractional numerator of the next output position.
+    pos: u64,
+    frac: u64,
+    total_in: u64,
+    total_out: u64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let input_rate = input_rate.max(1) as u64;
+        let output_rate = output_rate.max(1) as u64;
+        let g = gcd(input_rate, output_rate);
+        let step_num = input_rate / g;
+        let step_den = output_rate / g;
+
+        let phases = (step_den as usize).min(MAX_PHASES);
+        // Cut off just below the lower of the two Nyquist frequencies.
+        let cutoff = (output_rate as f64 / input_rate as f64).min(1.0) * 0.94;
+        let taps = 2 * HALF_TAPS;
+        let mut kernel = vec![0.0f32; phases * taps];
+        for phase in 0..phases {
+            let offset = phase as f64 / phases as f64;
+            let row = &mut kernel[phase * taps..(phase + 1) * taps];
+            let mut sum = 0.0;
+            let mut values = [0.0f64; 2 * HALF_TAPS];
+            for (j, value) in values.iter_mut().enumerate() {
+                // Tap j sits at input index floor(pos) - HALF_TAPS + 1 + j.
+                let distance = j as f64 - (HALF_TAPS as f64 - 1.0) - offset;
+                *value = cutoff * sinc(cutoff * distance) * blackman(distance / HALF_TAPS as f64);
+                sum += *value;
+            }
+            for (coefficient, value) in row.iter_mut().zip(values) {
+                *coefficient = (value / sum) as f32;
+            }
+        }
+
+        Self {
+            step_num,
+            step_den,
+            kernel,
+            phases,
+            // Zeros before the first sample so the filter can look back.
+            buf: vec![0.0; HALF_TAPS - 1],
+            buf_start: 0,
+            pos: (HALF_TAPS - 1) as u64,
+            frac: 0,
+            total_in: 0,
+            total_out: 0,
+        }
+    }
+
+    pub fn is_passthrough(&self) -> bool {
+        self.step_num == self.step_den
+    }
+
+    /// Centre input index and filter phase of the next output sample.
+    fn next_tap(&self) -> (u64, usize) {
+        if self.phases as u64 == self.step_den {
+            return (self.pos, self.frac as usize);
+        }
+        let phase = (self.frac * self.phases as u64 + self.step_den / 2) / self.step_den;
+        if phase as usize >= self.phases {
+            // Rounded up to the next whole sample.
+            (self.pos + 1, 0)
+        } else {
+            (self.pos, phase as usize)
+        }
+    }
+
+    fn run(&mut self, out: &mut Vec<f32>, limit: u64) {
+        let taps = 2 * HALF_TAPS;
+        let available = self.buf_start + self.buf.len() as u64;
+        while self.total_out < limit {
+            let (center, phase) = self.next_tap();
+            let first = center + 1 - HALF_TAPS as u64;
+            if first + taps as u64 > available {
+                break;
+            }
+            let start = (first - self.buf_start) as usize;
+            let window = &self.buf[start..start + taps];
+            let sample: f32 = window
+                .iter()
+                .zip(&self.kernel[phase * taps..(phase + 1) * taps])
+                .map(|(x, h)| x * h)
+                .sum();
+            out.push(sample);
+            self.total_out += 1;
+
+            self.frac += self.step_num;
+            self.pos += self.frac / self.step_den;
+            self.frac %= self.step_den;
+        }
+
+        // Drop input the filter will not look at again.
+        let keep_from = (self.pos + 1).saturating_sub(HALF_TAPS as u64);
+        if keep_from > self.buf_start {
+            let drop = ((keep_from - self.buf_start) as usize).min(self.buf.len());
+            self.buf.drain(..drop);
+            self.buf_start += drop as u64;
+        }
+    }
+
+    /// Number of output samples `input_len` input samples turn into.
+    fn expected_output(&self, input_len: u64) -> u64 {
+        (input_len * self.step_den).div_ceil(self.step_num)
+    }
+
+    /// Resamples `input` and appends the result to `out`. A few samples are
+    /// held back until more input (or [`Resampler::finish`]) arrives.
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.total_in += input.len() as u64;
+        if self.is_passthrough() {
+            out.extend_from_slice(input);
+            self.total_out += input.len() as u64;
+            return;
+        }
+        self.buf.extend_from_slice(input);
+        self.run(out, u64::MAX);
+    }
+
+    /// Flushes the samples still held back at the end of the input.
+    pub fn finish(&mut self, out: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            return;
+        }
+        let expected = self.expected_output(self.total_in);
+        self.buf.extend(std::iter::repeat_n(0.0, HALF_TAPS + 1));
+        self.run(out, expected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(input: &[f32], input_rate: u32, output_rate: u32) -> Vec<f32> {
+        let mut resampler = Resampler::new(input_rate, output_rate);
+        let mut out = Vec::new();
+        resampler.process(input, &mut out);
+        resampler.finish(&mut out);
+        out
+    }
+
+    fn sine(freq: f32, rate: u32, secs: f32) -> Vec<f32> {
+        let n = (rate as f32 * secs) as usize;
+        (0..n)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Frequency estimated from zero crossings.
+    fn frequency(samples: &[f32], rate: u32) -> f32 {
+        let crossings = samples
+            .windows(2)
+            .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+            .count();
+        crossings as f32 * rate as f32 / samples.len() as f32
+    }
+
+    #[test]
+    fn converts_44_1k_to_16k_with_correct_duration() {
+        let input = sine(440.0, 44_100, 3.0);
+        let out = resample(&input, 44_100, DEFAULT_SAMPLE_RATE);
+        assert_eq!(out.len(), 48_000);
+        assert!((frequency(&out, 16_000) - 440.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn converts_48k_to_16k_with_correct_duration() {
+        let input = sine(1_000.0, 48_000, 2.0);
+        let out = resample(&input, 48_000, DEFAULT_SAMPLE_RATE);
+        assert_eq!(out.len(), 32_000);
+        assert!((frequency(&out, 16_000) - 1_000.0).abs() < 2.0);
+        // A tone well inside the passband keeps its level.
+        assert!((rms(&out[1_000..31_000]) - rms(&input)).abs() < 0.01);
+    }
+
+    #[test]
+    fn upsamples_8k_to_16k() {
+        let input = sine(300.0, 8_000, 1.0);
+        let out = resample(&input, 8_000, 16_000);
+        assert_eq!(out.len(), 16_000);
+        assert!((frequency(&out, 16_000) - 300.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn tones_above_the_new_nyquist_are_removed() {
+        let input = sine(12_000.0, 48_000, 1.0);
+        let out = resample(&input, 48_000, 16_000);
+        assert!(rms(&out[500..15_500]) < 0.01, "rms {}", rms(&out));
+    }
+
+    #[test]
+    fn streaming_in_small_chunks_matches_one_shot() {
+        let input = sine(440.0, 44_100, 0.5);
+        let expected = resample(&input, 44_100, 16_000);
+
+        let mut resampler = Resampler::new(44_100, 16_000);
+        let mut out = Vec::new();
+        for chunk in input.chunks(441) {
+            resampler.process(chunk, &mut out);
+        }
+        resampler.finish(&mut out);
+        assert_eq!(out.len(), expected.len());
+        for (a, b) in out.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn odd_ratios_keep_exact_length() {
+        let input = vec![0.1; 44_100 * 2];
+        let out = resample(&input, 44_100, 16_001);
+        assert_eq!(out.len(), 32_002);
+        // DC passes through unchanged away from the edges.
+        assert!(out[100..31_900].iter().all(|s| (s - 0.1).abs() < 1e-3));
+    }
+
+    #[test]
+    fn same_rate_is_passthrough() {
+        let input = sine(440.0, 16_000, 0.1);
+        assert_eq!(resample(&input, 16_000, 16_000), input);
+    }
+}
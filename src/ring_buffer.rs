@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+/// The most recent samples up to a fixed capacity; older ones are dropped as
+/// new ones arrive.
+pub struct RingBuffer<T> {
+    samples: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T: Copy> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Appends `input`, dropping the oldest samples past the capacity.
+    pub fn extend(&mut self, input: impl ExactSizeIterator<Item = T>) {
+        let skip = input.len().saturating_sub(self.capacity);
+        let kept = input.len() - skip;
+        let overflow = (self.samples.len() + kept).saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        self.samples.extend(input.skip(skip));
+    }
+
+    /// The last `n` samples, oldest first; fewer if the buffer holds less.
+    pub fn last(&self, n: usize) -> Vec<T> {
+        let n = n.min(self.samples.len());
+        self.samples
+            .range(self.samples.len() - n..)
+            .copied()
+            .collect()
+    }
+
+    /// Empties the buffer, oldest sample first.
+    pub fn take(&mut self) -> Vec<T> {
+        self.samples.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_most_recent_samples() {
+        let mut ring = RingBuffer::new(4);
+        ring.extend([1, 2, 3].into_iter());
+        ring.extend([4, 5].into_iter());
+        assert_eq!(ring.last(2), vec![4, 5]);
+        assert_eq!(ring.last(10), vec![2, 3, 4, 5]);
+        assert_eq!(ring.take(), vec![2, 3, 4, 5]);
+        assert_eq!(ring.len(), 0);
+
+        ring.extend([1, 2, 3, 4, 5, 6].into_iter());
+        assert_eq!(ring.take(), vec![3, 4, 5, 6]);
+
+        let mut empty = RingBuffer::new(0);
+        empty.extend([1.0f32; 8].into_iter());
+        assert!(empty.take().is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::config::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-large-v3";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum TranscriptionProvider {
+    #[default]
+    Groq,
+    /// Any OpenAI-compatible transcription server reachable at `local_endpoint`
+    /// (whisper.cpp server, faster-whisper-server, speaches, ...).
+    Local,
+}
+
+impl TranscriptionProvider {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TranscriptionProvider::Groq => "groq",
+            TranscriptionProvider::Local => "local",
+        }
+    }
+}
+
+/// A rule from `routing_rules` in config.json. Every condition that is set must
+/// hold for the rule to match; the first matching rule wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pub name: String,
+    #[serde(default)]
+    pub min_duration_secs: Option<f64>,
+    #[serde(default)]
+    pub max_duration_secs: Option<f64>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub provider: TranscriptionProvider,
+    pub model: String,
+}
+
+/// The provider and model a job was sent to, kept on the history entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    pub provider: TranscriptionProvider,
+    pub model: String,
+    /// Name of the routing rule that picked this route, `None` for the default.
+    #[serde(default)]
+    pub rule: Option<String>,
+}
+
+impl Route {
+    pub fn default_for(config: &AppConfig) -> Self {
+        Self {
+            provider: TranscriptionProvider::Groq,
+            model: config.transcription_model.clone(),
+            rule: None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.rule {
+            Some(rule) => format!("{}/{} (rule: {rule})", self.provider.label(), self.model),
+            None => format!("{}/{}", self.provider.label(), self.model),
+        }
+    }
+}
+
+/// Everything a routing rule can look at when a job is created.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobContext {
+    pub duration_secs: Option<f64>,
+    pub language: Option<String>,
+    pub profile: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl JobContext {
+    pub fn for_audio(config: &AppConfig, audio_path: &str) -> Self {
+        let profile = config.active_profile();
+        let mut tags = tags_from_path(Path::new(audio_path));
+        if let Some(profile) = profile {
+            for tag in &profile.tags {
+                let tag = tag.trim().to_lowercase();
+                if !tag.is_empty() && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+
+        Self {
+            duration_secs: crate::groq_request::media_duration_secs(Path::new(audio_path)),
+            language: config.effective_language(),
+            profile: profile.map(|p| p.name.clone()),
+            tags,
+        }
+    }
+}
+
+/// Files are "tagged" by their name and the folder they sit in, e.g.
+/// `confidential/standup.wav` or `standup_confidential.m4a` both carry the
+/// `confidential` tag.
+fn tags_from_path(path: &Path) -> Vec<String> {
+    let mut tags = Vec::new();
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let parent = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+
+    for token in stem
+        .split(|c: char| !c.is_alphanumeric())
+        .chain(std::iter::once(parent))
+    {
+        let token = token.trim().to_lowercase();
+        if !token.is_empty() && !tags.contains(&token) {
+            tags.push(token);
+        }
+    }
+    tags
+}
+
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+impl RoutingRule {
+    pub fn matches(&self, ctx: &JobContext) -> bool {
+        if let Some(min) = self.min_duration_secs {
+            match ctx.duration_secs {
+                Some(duration) if duration >= min => {}
+                _ => return false,
+            }
+        }
+        if let Some(max) = self.max_duration_secs {
+            match ctx.duration_secs {
+                Some(duration) if duration < max => {}
+                _ => return false,
+            }
+        }
+        if let Some(ref language) = self.language {
+            match ctx.language {
+                Some(ref job_language) if eq_ignore_case(language, job_language) => {}
+                _ => return false,
+            }
+        }
+        if let Some(ref profile) = self.profile {
+            match ctx.profile {
+                Some(ref job_profile) if eq_ignore_case(profile, job_profile) => {}
+                _ => return false,
+            }
+        }
+        if let Some(ref tag) = self.tag {
+            if !ctx.tags.iter().any(|t| eq_ignore_case(t, tag)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn select_route(rules: &[RoutingRule], ctx: &JobContext, default: Route) -> Route {
+    rules
+        .iter()
+        .find(|rule| !rule.model.trim().is_empty() && rule.matches(ctx))
+        .map(|rule| Route {
+            provider: rule.provider.clone(),
+            model: rule.model.trim().to_string(),
+            rule: Some(rule.name.clone()),
+        })
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rule(name: &str, model: &str) -> RoutingRule {
+        RoutingRule {
+            name: name.to_string(),
+            min_duration_secs: None,
+            max_duration_secs: None,
+            language: None,
+            profile: None,
+            tag: None,
+            provider: TranscriptionProvider::Groq,
+            model: model.to_string(),
+        }
+    }
+
+    fn default_route() -> Route {
+        Route {
+            provider: TranscriptionProvider::Groq,
+            model: DEFAULT_TRANSCRIPTION_MODEL.to_string(),
+            rule: None,
+        }
+    }
+
+    fn example_rules() -> Vec<RoutingRule> {
+        vec![
+            RoutingRule {
+                tag: Some("confidential".to_string()),
+                provider: TranscriptionProvider::Local,
+                ..rule("confidential", "large-v3")
+            },
+            RoutingRule {
+                max_duration_secs: Some(15.0),
+                ..rule("short clips", "whisper-large-v3-turbo")
+            },
+            RoutingRule {
+                min_duration_secs: Some(600.0),
+                ..rule("meetings", "whisper-large-v3")
+            },
+        ]
+    }
+
+    #[test]
+    fn short_clip_goes_to_turbo_model() {
+        let ctx = JobContext {
+            duration_secs: Some(4.2),
+            ..Default::default()
+        };
+        let route = select_route(&example_rules(), &ctx, default_route());
+        assert_eq!(route.model, "whisper-large-v3-turbo");
+        assert_eq!(route.rule.as_deref(), Some("short clips"));
+    }
+
+    #[test]
+    fn long_recording_goes_to_large_model() {
+        let ctx = JobContext {
+            duration_secs: Some(3600.0),
+            ..Default::default()
+        };
+        let route = select_route(&example_rules(), &ctx, default_route());
+        assert_eq!(route.rule.as_deref(), Some("meetings"));
+    }
+
+    #[test]
+    fn tagged_file_goes_to_local_backend_before_length_rules() {
+        let ctx = JobContext {
+            duration_secs: Some(3.0),
+            tags: vec!["confidential".to_string()],
+            ..Default::default()
+        };
+        let route = select_route(&example_rules(), &ctx, default_route());
+        assert_eq!(route.provider, TranscriptionProvider::Local);
+        assert_eq!(route.model, "large-v3");
+    }
+
+    #[test]
+    fn unknown_duration_never_matches_duration_rules() {
+        let ctx = JobContext::default();
+        let route = select_route(&example_rules(), &ctx, default_route());
+        assert_eq!(route, default_route());
+    }
+
+    #[test]
+    fn max_duration_is_exclusive_and_min_is_inclusive() {
+        let r = RoutingRule {
+            min_duration_secs: Some(10.0),
+            max_duration_secs: Some(20.0),
+            ..rule("window", "m")
+        };
+        let at = |d: f64| JobContext {
+            duration_secs: Some(d),
+            ..Default::default()
+        };
+        assert!(r.matches(&at(10.0)));
+        assert!(r.matches(&at(19.9)));
+        assert!(!r.matches(&at(20.0)));
+        assert!(!r.matches(&at(9.9)));
+    }
+
+    #[test]
+    fn language_and_profile_match_case_insensitively() {
+        let r = RoutingRule {
+            language: Some("DE".to_string()),
+            profile: Some("Work".to_string()),
+            ..rule("german work", "m")
+        };
+        let ctx = JobContext {
+            language: Some("de".to_string()),
+            profile: Some("work".to_string()),
+            ..Default::default()
+        };
+        assert!(r.matches(&ctx));
+
+        let other_profile = JobContext {
+            profile: Some("home".to_string()),
+            ..ctx.clone()
+        };
+        assert!(!r.matches(&other_profile));
+    }
+
+    #[test]
+    fn rule_with_blank_model_is_skipped() {
+        let rules = vec![rule("broken", "  "), rule("fallback", "turbo")];
+        let route = select_route(&rules, &JobContext::default(), default_route());
+        assert_eq!(route.rule.as_deref(), Some("fallback"));
+    }
+
+    #[test]
+    fn tags_come_from_file_stem_and_parent_folder() {
+        let tags = tags_from_path(&PathBuf::from("/data/Confidential/standup_2024-01-02.wav"));
+        assert!(tags.contains(&"confidential".to_string()));
+        assert!(tags.contains(&"standup".to_string()));
+        assert!(tags.contains(&"2024".to_string()));
+    }
+
+    #[test]
+    fn describe_mentions_rule_name() {
+        let route = Route {
+            provider: TranscriptionProvider::Local,
+            model: "large-v3".to_string(),
+            rule: Some("confidential".to_string()),
+        };
+        assert_eq!(route.describe(), "local/large-v3 (rule: confidential)");
+        assert_eq!(default_route().describe(), "groq/whisper-large-v3");
+    }
+
+    #[test]
+    fn rules_deserialize_with_only_required_fields() {
+        let rules: Vec<RoutingRule> =
+            serde_json::from_str(r#"[{"name":"short","max_duration_secs":15,"model":"turbo"}]"#)
+                .expect("parse");
+        assert_eq!(rules[0].provider, TranscriptionProvider::Groq);
+        assert_eq!(rules[0].max_duration_secs, Some(15.0));
+    }
+}
@@ -19,7 +19,11 @@ use std::sync::RwLock;
 #[derive(Debug, Clone)]
 pub enum HotkeyCommand {
     ToggleRecording,
+    /// Like `ToggleRecording`, but the result is translated.
+    ToggleTranslatedRecording,
     ShowWindow,
+    /// Save and transcribe the desktop replay buffer.
+    SaveReplay,
     StartHoldRecording,
     StopHoldRecording,
     HoldKeyCaptured(String),
@@ -67,6 +71,10 @@ pub struct HotkeyBindings {
     pub toggle_shortcut: String,
     pub show_window_shortcut: String,
     pub hold_to_record_key: Option<String>,
+    /// Empty when no translate shortcut is set.
+    pub translate_shortcut: String,
+    /// Empty when no replay shortcut is set.
+    pub replay_shortcut: String,
 }
 
 impl HotkeyBindings {
@@ -74,11 +82,15 @@ impl HotkeyBindings {
         toggle_shortcut: String,
         show_window_shortcut: String,
         hold_to_record_key: Option<String>,
+        translate_shortcut: String,
+        replay_shortcut: String,
     ) -> Self {
         Self {
             toggle_shortcut,
             show_window_shortcut,
             hold_to_record_key,
+            translate_shortcut,
+            replay_shortcut,
         }
     }
 }
@@ -208,6 +220,18 @@ impl ShortcutDetector {
                         return Some(HotkeyCommand::ShowWindow);
                     }
                 }
+
+                if let Some(parsed) = parse_shortcut(&bindings.translate_shortcut) {
+                    if parsed.matches_rdev(mods, key) {
+                        return Some(HotkeyCommand::ToggleTranslatedRecording);
+                    }
+                }
+
+                if let Some(parsed) = parse_shortcut(&bindings.replay_shortcut) {
+                    if parsed.matches_rdev(mods, key) {
+                        return Some(HotkeyCommand::SaveReplay);
+                    }
+                }
             }
             EventType::KeyRelease(key) => {
                 let bindings = match self.bindings.read() {
@@ -415,6 +439,8 @@ fn apply_bindings_macos(
     registered: &mut Vec<HotKey>,
     toggle_id: &mut Option<u32>,
     show_id: &mut Option<u32>,
+    translate_id: &mut Option<u32>,
+    replay_id: &mut Option<u32>,
     bindings: &HotkeyBindings,
 ) {
     for hotkey in registered.drain(..) {
@@ -423,6 +449,8 @@ fn apply_bindings_macos(
 
     *toggle_id = None;
     *show_id = None;
+    *translate_id = None;
+    *replay_id = None;
 
     if let Some(toggle) = parse_shortcut(&bindings.toggle_shortcut).and_then(|s| s.to_hotkey()) {
         *toggle_id = Some(toggle.id());
@@ -454,6 +482,38 @@ fn apply_bindings_macos(
             bindings.show_window_shortcut
         );
     }
+
+    *translate_id = register_optional_macos(
+        manager,
+        registered,
+        &bindings.translate_shortcut,
+        "translate",
+    );
+    *replay_id = register_optional_macos(manager, registered, &bindings.replay_shortcut, "replay");
+}
+
+/// Registers a shortcut that may be left empty; returns its id when set.
+#[cfg(target_os = "macos")]
+fn register_optional_macos(
+    manager: &GlobalHotKeyManager,
+    registered: &mut Vec<HotKey>,
+    shortcut: &str,
+    name: &str,
+) -> Option<u32> {
+    if shortcut.trim().is_empty() {
+        return None;
+    }
+    let Some(hotkey) = parse_shortcut(shortcut).and_then(|s| s.to_hotkey()) else {
+        eprintln!("Invalid {name} shortcut '{shortcut}'.");
+        return None;
+    };
+    let id = hotkey.id();
+    if let Err(err) = manager.register(hotkey.clone()) {
+        eprintln!("Failed to register {name} shortcut '{shortcut}': {err}");
+    } else {
+        registered.push(hotkey);
+    }
+    Some(id)
 }
 
 #[cfg(not(target_os = "macos"))]
@@ -540,12 +600,16 @@ pub fn start_global_hotkeys(
             let mut registered: Vec<HotKey> = Vec::new();
             let mut toggle_id: Option<u32> = None;
             let mut show_id: Option<u32> = None;
+            let mut translate_id: Option<u32> = None;
+            let mut replay_id: Option<u32> = None;
 
             apply_bindings_macos(
                 &manager,
                 &mut registered,
                 &mut toggle_id,
                 &mut show_id,
+                &mut translate_id,
+                &mut replay_id,
                 &initial,
             );
 
@@ -564,6 +628,8 @@ pub fn start_global_hotkeys(
                                 &mut registered,
                                 &mut toggle_id,
                                 &mut show_id,
+                                &mut translate_id,
+                                &mut replay_id,
                                 &bindings,
                             );
                         }
@@ -582,6 +648,10 @@ pub fn start_global_hotkeys(
                         Some(HotkeyCommand::ToggleRecording)
                     } else if show_id.is_some() && Some(event.id) == show_id {
                         Some(HotkeyCommand::ShowWindow)
+                    } else if translate_id.is_some() && Some(event.id) == translate_id {
+                        Some(HotkeyCommand::ToggleTranslatedRecording)
+                    } else if replay_id.is_some() && Some(event.id) == replay_id {
+                        Some(HotkeyCommand::SaveReplay)
                     } else {
                         None
                     };
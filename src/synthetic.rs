@@ -0,0 +1,264 @@
+use crate::backend::{
+    AudioBackend, DataCallback, DeviceRole, ErrorCallback, InputConfig, InputStream,
+};
+use crate::capture::{shape_to_mono, InputShaping};
+use std::path::Path;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Length of the blocks [`SyntheticBackend::advance`] delivers.
+const BLOCK_SECS: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Tone {
+        frequency: f32,
+        amplitude: f32,
+    },
+    /// Played once, then silence.
+    Samples(Vec<f32>),
+}
+
+impl Signal {
+    fn sample(&self, index: u64, sample_rate: u32) -> f32 {
+        match self {
+            Signal::Tone {
+                frequency,
+                amplitude,
+            } => {
+                let t = index as f64 / sample_rate as f64;
+                (2.0 * std::f64::consts::PI * *frequency as f64 * t).sin() as f32 * amplitude
+            }
+            Signal::Samples(samples) => samples.get(index as usize).copied().unwrap_or(0.0),
+        }
+    }
+}
+
+struct Device {
+    name: String,
+    sample_rate: u32,
+    signal: Signal,
+}
+
+struct Stream {
+    device: String,
+    sample_rate: u32,
+    signal: Signal,
+    position: u64,
+    shaping: InputShaping,
+    on_data: DataCallback,
+    on_error: ErrorCallback,
+}
+
+#[derive(Default)]
+struct State {
+    devices: Vec<Device>,
+    /// Owned by the handles `open_input` returns, so dropping one stops it
+    /// and frees its callbacks like a cpal stream.
+    streams: Vec<Weak<Mutex<Stream>>>,
+    /// Fractional blocks carried over between calls to `advance`.
+    elapsed_secs: f64,
+}
+
+/// Inputs that play tones or WAV files on demand, for running the recorder
+/// without a sound card. Nothing plays until [`SyntheticBackend::advance`],
+/// so tests decide exactly how much audio each stream gets.
+#[derive(Default)]
+pub struct SyntheticBackend {
+    state: Mutex<State>,
+}
+
+impl SyntheticBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an input; the first one added is the default.
+    pub fn add_device(&self, name: &str, sample_rate: u32, signal: Signal) {
+        if let Ok(mut state) = self.state.lock() {
+            state.devices.push(Device {
+                name: name.to_string(),
+                sample_rate,
+                signal,
+            });
+        }
+    }
+
+    pub fn add_tone(&self, name: &str, sample_rate: u32, frequency: f32, amplitude: f32) {
+        self.add_device(
+            name,
+            sample_rate,
+            Signal::Tone {
+                frequency,
+                amplitude,
+            },
+        );
+    }
+
+    pub fn add_wav(&self, name: &str, path: &Path) -> Result<(), String> {
+        let (samples, rate) = crate::encoder::read_mono(path)
+            .ok_or_else(|| format!("Failed to read {}", path.display()))?;
+        self.add_device(name, rate, Signal::Samples(samples));
+        Ok(())
+    }
+
+    /// Feeds `secs` of audio to every open stream, in 10 ms blocks.
+    pub fn advance(&self, secs: f64) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.streams.retain(|s| s.strong_count() > 0);
+        state.elapsed_secs += secs;
+        let mut block = Vec::new();
+        let mut mono = Vec::new();
+        while state.elapsed_secs >= BLOCK_SECS - 1e-9 {
+            state.elapsed_secs -= BLOCK_SECS;
+            for stream in state.streams.iter().filter_map(Weak::upgrade) {
+                let Ok(mut stream) = stream.lock() else {
+                    continue;
+                };
+                let frames = (stream.sample_rate as f64 * BLOCK_SECS).round() as u64;
+                block.clear();
+                let start = stream.position;
+                block.extend(
+                    (start..start + frames).map(|i| stream.signal.sample(i, stream.sample_rate)),
+                );
+                stream.position += frames;
+                shape_to_mono(&block, 1, stream.shaping, &mut mono);
+                (stream.on_data)(&mono);
+            }
+        }
+    }
+
+    /// Unplugs `name`: its streams report the device as gone and go quiet.
+    pub fn unplug(&self, name: &str) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.devices.retain(|d| d.name != name);
+        state.streams.retain(|stream| {
+            let Some(stream) = stream.upgrade() else {
+                return false;
+            };
+            let Ok(mut stream) = stream.lock() else {
+                return false;
+            };
+            if stream.device != name {
+                return true;
+            }
+            (stream.on_error)(cpal::StreamError::DeviceNotAvailable);
+            false
+        });
+    }
+
+    /// Streams that have not been dropped yet.
+    pub fn open_streams(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .map(|state| {
+                state
+                    .streams
+                    .iter()
+                    .filter_map(Weak::upgrade)
+                    .filter_map(|s| s.lock().ok().map(|s| s.device.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl AudioBackend for SyntheticBackend {
+    fn input_device_names(&self) -> Result<Vec<String>, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        Ok(state.devices.iter().map(|d| d.name.clone()).collect())
+    }
+
+    fn default_input_name(&self) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        state.devices.first().map(|d| d.name.clone())
+    }
+
+    fn input_config(
+        &self,
+        role: DeviceRole,
+        name: &Option<String>,
+        _preferred_rate: Option<u32>,
+    ) -> Result<InputConfig, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        let device = name
+            .as_ref()
+            .and_then(|name| state.devices.iter().find(|d| d.name == *name))
+            .or(state.devices.first())
+            .ok_or_else(|| "No input device available".to_string())?;
+        Ok(InputConfig {
+            role,
+            device_name: device.name.clone(),
+            sample_rate: device.sample_rate,
+            monitor: None,
+        })
+    }
+
+    fn open_input(
+        &self,
+        config: &InputConfig,
+        shaping: InputShaping,
+        on_data: DataCallback,
+        on_error: ErrorCallback,
+    ) -> Result<InputStream, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        let device = state
+            .devices
+            .iter()
+            .find(|d| d.name == config.device_name)
+            .ok_or_else(|| format!("'{}' is not available", config.device_name))?;
+        let stream = Arc::new(Mutex::new(Stream {
+            device: device.name.clone(),
+            sample_rate: device.sample_rate,
+            signal: device.signal.clone(),
+            position: 0,
+            shaping,
+            on_data,
+            on_error,
+        }));
+        state.streams.push(Arc::downgrade(&stream));
+        Ok(Box::new(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_get_audio_only_while_open() {
+        let backend = SyntheticBackend::new();
+        backend.add_tone("Mic", 48_000, 440.0, 0.5);
+        let config = backend
+            .input_config(DeviceRole::Microphone, &None, Some(16_000))
+            .unwrap();
+        assert_eq!(config.sample_rate, 48_000);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let stream = backend
+            .open_input(
+                &config,
+                InputShaping::default(),
+                Box::new(move |data: &[f32]| sink.lock().unwrap().extend_from_slice(data)),
+                Box::new(|_| {}),
+            )
+            .unwrap();
+        backend.advance(0.25);
+        assert_eq!(received.lock().unwrap().len(), 12_000);
+        let peak = received
+            .lock()
+            .unwrap()
+            .iter()
+            .fold(0.0f32, |p, s| p.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.01);
+
+        drop(stream);
+        backend.advance(0.25);
+        assert_eq!(received.lock().unwrap().len(), 12_000);
+        assert!(backend.open_streams().is_empty());
+    }
+}
@@ -0,0 +1,1300 @@
+//! Inverse text normalization: rewrites spoken forms in a transcript into
+//! their written forms ("twenty five dollars" → "$25"). Pure and local, so the
+//! rules can be checked offline.
+
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    En,
+    De,
+}
+
+impl Lang {
+    /// Accepts ISO codes (`en`, `de-AT`) and the language names Whisper reports
+    /// in `verbose_json` (`english`, `german`).
+    fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        let base = code.split(['-', '_']).next().unwrap_or("");
+        match base {
+            "en" | "english" => Some(Lang::En),
+            "de" | "german" | "deutsch" => Some(Lang::De),
+            _ => None,
+        }
+    }
+
+    /// Smaller numbers stay spelled out unless they carry a unit or currency.
+    fn min_standalone(self) -> u64 {
+        match self {
+            Lang::En => 10,
+            Lang::De => 13,
+        }
+    }
+
+    fn decimal_sep(self) -> char {
+        match self {
+            Lang::En => '.',
+            Lang::De => ',',
+        }
+    }
+
+    fn group_sep(self) -> char {
+        match self {
+            Lang::En => ',',
+            Lang::De => '.',
+        }
+    }
+}
+
+const EN_UNITS: [(&str, u64); 10] = [
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+const EN_TEENS: [(&str, u64); 10] = [
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+];
+
+const EN_TENS: [(&str, u64); 8] = [
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+const EN_SCALES: [(&str, u64); 3] = [
+    ("thousand", 1_000),
+    ("million", 1_000_000),
+    ("billion", 1_000_000_000),
+];
+
+const EN_ORDINALS: [(&str, u32); 21] = [
+    ("first", 1),
+    ("second", 2),
+    ("third", 3),
+    ("fourth", 4),
+    ("fifth", 5),
+    ("sixth", 6),
+    ("seventh", 7),
+    ("eighth", 8),
+    ("ninth", 9),
+    ("tenth", 10),
+    ("eleventh", 11),
+    ("twelfth", 12),
+    ("thirteenth", 13),
+    ("fourteenth", 14),
+    ("fifteenth", 15),
+    ("sixteenth", 16),
+    ("seventeenth", 17),
+    ("eighteenth", 18),
+    ("nineteenth", 19),
+    ("twentieth", 20),
+    ("thirtieth", 30),
+];
+
+const EN_MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const DE_SMALL: [(&str, u64); 20] = [
+    ("null", 0),
+    ("eins", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+    ("zehn", 10),
+    ("elf", 11),
+    ("zwölf", 12),
+    ("dreizehn", 13),
+    ("vierzehn", 14),
+    ("fünfzehn", 15),
+    ("sechzehn", 16),
+    ("siebzehn", 17),
+    ("achtzehn", 18),
+    ("neunzehn", 19),
+];
+
+/// Forms used in front of "und", "hundert" and "tausend".
+const DE_COMPOUND_UNITS: [(&str, u64); 10] = [
+    ("ein", 1),
+    ("eine", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+];
+
+const DE_TENS: [(&str, u64); 8] = [
+    ("zwanzig", 20),
+    ("dreissig", 30),
+    ("vierzig", 40),
+    ("fünfzig", 50),
+    ("sechzig", 60),
+    ("siebzig", 70),
+    ("achtzig", 80),
+    ("neunzig", 90),
+];
+
+const DE_SCALES: [(&str, u64); 4] = [
+    ("million", 1_000_000),
+    ("millionen", 1_000_000),
+    ("milliarde", 1_000_000_000),
+    ("milliarden", 1_000_000_000),
+];
+
+const DE_MONTHS: [(&str, u32); 13] = [
+    ("januar", 1),
+    ("jänner", 1),
+    ("februar", 2),
+    ("märz", 3),
+    ("april", 4),
+    ("mai", 5),
+    ("juni", 6),
+    ("juli", 7),
+    ("august", 8),
+    ("september", 9),
+    ("oktober", 10),
+    ("november", 11),
+    ("dezember", 12),
+];
+
+const DE_MONTH_NAMES: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+
+struct Currency {
+    words: &'static [&'static str],
+    symbol: &'static str,
+}
+
+const EN_CURRENCIES: [Currency; 3] = [
+    Currency {
+        words: &["dollar", "dollars", "buck", "bucks"],
+        symbol: "$",
+    },
+    Currency {
+        words: &["euro", "euros"],
+        symbol: "€",
+    },
+    Currency {
+        words: &["yen"],
+        symbol: "¥",
+    },
+];
+
+const DE_CURRENCIES: [Currency; 5] = [
+    Currency {
+        words: &["euro", "euros"],
+        symbol: "€",
+    },
+    Currency {
+        words: &["dollar", "dollars"],
+        symbol: "$",
+    },
+    Currency {
+        words: &["pfund"],
+        symbol: "£",
+    },
+    Currency {
+        words: &["franken"],
+        symbol: "CHF",
+    },
+    Currency {
+        words: &["yen"],
+        symbol: "¥",
+    },
+];
+
+const EN_MINOR_CURRENCY: [&str; 4] = ["cent", "cents", "penny", "pence"];
+const DE_MINOR_CURRENCY: [&str; 3] = ["cent", "cents", "rappen"];
+
+/// A spoken unit and its symbol. `spaced` puts a space between number and
+/// symbol.
+struct Unit {
+    words: &'static [&'static str],
+    symbol: &'static str,
+    spaced: bool,
+}
+
+const fn unit(words: &'static [&'static str], symbol: &'static str, spaced: bool) -> Unit {
+    Unit {
+        words,
+        symbol,
+        spaced,
+    }
+}
+
+// Longer spellings first so "kilometers per hour" wins over "kilometers".
+const EN_UNIT_TABLE: [Unit; 49] = [
+    unit(&["kilometers", "per", "hour"], "km/h", true),
+    unit(&["kilometres", "per", "hour"], "km/h", true),
+    unit(&["k", "m", "h"], "km/h", true),
+    unit(&["miles", "per", "hour"], "mph", true),
+    unit(&["m", "p", "h"], "mph", true),
+    unit(&["degrees", "celsius"], "°C", false),
+    unit(&["degrees", "fahrenheit"], "°F", false),
+    unit(&["degree", "celsius"], "°C", false),
+    unit(&["per", "cent"], "%", false),
+    unit(&["kilometer"], "km", true),
+    unit(&["kilometers"], "km", true),
+    unit(&["kilometre"], "km", true),
+    unit(&["kilometres"], "km", true),
+    unit(&["k", "m"], "km", true),
+    unit(&["meter"], "m", true),
+    unit(&["meters"], "m", true),
+    unit(&["metre"], "m", true),
+    unit(&["metres"], "m", true),
+    unit(&["centimeters"], "cm", true),
+    unit(&["centimetres"], "cm", true),
+    unit(&["c", "m"], "cm", true),
+    unit(&["millimeters"], "mm", true),
+    unit(&["millimetres"], "mm", true),
+    unit(&["m", "m"], "mm", true),
+    unit(&["kilogram"], "kg", true),
+    unit(&["kilograms"], "kg", true),
+    unit(&["kilos"], "kg", true),
+    unit(&["k", "g"], "kg", true),
+    unit(&["grams"], "g", true),
+    unit(&["liter"], "l", true),
+    unit(&["liters"], "l", true),
+    unit(&["litres"], "l", true),
+    unit(&["milliliters"], "ml", true),
+    unit(&["m", "l"], "ml", true),
+    unit(&["percent"], "%", false),
+    unit(&["degrees"], "°", false),
+    unit(&["kilobytes"], "KB", true),
+    unit(&["k", "b"], "KB", true),
+    unit(&["megabytes"], "MB", true),
+    unit(&["m", "b"], "MB", true),
+    unit(&["gigabytes"], "GB", true),
+    unit(&["g", "b"], "GB", true),
+    unit(&["terabytes"], "TB", true),
+    unit(&["t", "b"], "TB", true),
+    unit(&["hertz"], "Hz", true),
+    unit(&["kilohertz"], "kHz", true),
+    unit(&["k", "h", "z"], "kHz", true),
+    unit(&["megahertz"], "MHz", true),
+    unit(&["gigahertz"], "GHz", true),
+];
+
+const DE_UNIT_TABLE: [Unit; 23] = [
+    unit(&["kilometer", "pro", "stunde"], "km/h", true),
+    unit(&["stundenkilometer"], "km/h", true),
+    unit(&["k", "m", "h"], "km/h", true),
+    unit(&["grad", "celsius"], "°C", true),
+    unit(&["kilometer"], "km", true),
+    unit(&["k", "m"], "km", true),
+    unit(&["meter"], "m", true),
+    unit(&["zentimeter"], "cm", true),
+    unit(&["millimeter"], "mm", true),
+    unit(&["kilogramm"], "kg", true),
+    unit(&["kilo"], "kg", true),
+    unit(&["gramm"], "g", true),
+    unit(&["liter"], "l", true),
+    unit(&["milliliter"], "ml", true),
+    unit(&["prozent"], "%", true),
+    unit(&["grad"], "°", false),
+    unit(&["kilobyte"], "KB", true),
+    unit(&["megabyte"], "MB", true),
+    unit(&["gigabyte"], "GB", true),
+    unit(&["terabyte"], "TB", true),
+    unit(&["hertz"], "Hz", true),
+    unit(&["kilohertz"], "kHz", true),
+    unit(&["gigahertz"], "GHz", true),
+];
+
+#[derive(Debug, Clone)]
+struct Token {
+    /// Byte range of `core` in the normalized text.
+    span: std::ops::Range<usize>,
+    lead: String,
+    core: String,
+    trail: String,
+    /// Lowercased core used for matching; German also folds "ß" to "ss".
+    key: String,
+}
+
+impl Token {
+    /// A token for `raw`, which starts at byte `offset` of the text.
+    fn new(raw: &str, offset: usize, lang: Lang) -> Self {
+        let start = raw.find(char::is_alphanumeric);
+        let end = raw
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_alphanumeric())
+            .map(|(idx, c)| idx + c.len_utf8());
+        let (lead, core, trail) = match (start, end) {
+            (Some(start), Some(end)) => (&raw[..start], &raw[start..end], &raw[end..]),
+            _ => (raw, "", ""),
+        };
+        let mut key = core.to_lowercase();
+        if lang == Lang::De {
+            key = key.replace('ß', "ss");
+        }
+        Self {
+            span: offset + lead.len()..offset + lead.len() + core.len(),
+            lead: lead.to_string(),
+            core: core.to_string(),
+            trail: trail.to_string(),
+            key,
+        }
+    }
+
+    fn is_capitalized(&self) -> bool {
+        self.core.chars().next().is_some_and(char::is_uppercase)
+    }
+
+    fn is_digits(&self) -> bool {
+        !self.core.is_empty() && self.core.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+fn lookup<T: Copy>(table: &[(&str, T)], key: &str) -> Option<T> {
+    table.iter().find(|(word, _)| *word == key).map(|(_, v)| *v)
+}
+
+fn is_en_number_word(key: &str) -> bool {
+    lookup(&EN_UNITS, key).is_some()
+        || lookup(&EN_TEENS, key).is_some()
+        || lookup(&EN_TENS, key).is_some()
+        || lookup(&EN_ORDINALS, key).is_some()
+}
+
+fn tokenize(text: &str, lang: Lang) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for raw in text.split_whitespace() {
+        // `raw` borrows from `text`, so the pointers give its byte offset.
+        let offset = raw.as_ptr() as usize - text.as_ptr() as usize;
+        let token = Token::new(raw, offset, lang);
+        // "twenty-five" is read as two number words.
+        let parts: Vec<&str> = token.core.split('-').collect();
+        if lang == Lang::En
+            && parts.len() > 1
+            && parts.iter().all(|p| is_en_number_word(&p.to_lowercase()))
+        {
+            let last = parts.len() - 1;
+            let mut part_start = token.span.start;
+            for (idx, part) in parts.iter().enumerate() {
+                let lead = if idx == 0 { token.lead.as_str() } else { "" };
+                let trail = if idx == last {
+                    token.trail.as_str()
+                } else {
+                    ""
+                };
+                let raw = format!("{lead}{part}{trail}");
+                tokens.push(Token::new(&raw, part_start - lead.len(), lang));
+                part_start += part.len() + 1;
+            }
+            continue;
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// The tokens from `start` that read as one phrase: punctuation ends it.
+fn run(tokens: &[Token], start: usize) -> &[Token] {
+    let mut end = start;
+    while end < tokens.len() {
+        if end > start && !tokens[end].lead.is_empty() {
+            break;
+        }
+        end += 1;
+        if !tokens[end - 1].trail.is_empty() {
+            break;
+        }
+    }
+    &tokens[start..end]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Start,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale(u64),
+    And,
+}
+
+/// Spoken English cardinal ("two thousand three hundred and five").
+fn en_cardinal(tokens: &[Token]) -> Option<(u64, usize)> {
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut last = Part::Start;
+    let mut last_scale = u64::MAX;
+    let mut used = 0usize;
+    let mut idx = 0usize;
+
+    while idx < tokens.len() {
+        let key = tokens[idx].key.as_str();
+        let after_break = matches!(
+            last,
+            Part::Start | Part::Hundred | Part::Scale(_) | Part::And
+        );
+        if let Some(v) = lookup(&EN_UNITS, key) {
+            if !(after_break || last == Part::Tens) || (v == 0 && last != Part::Start) {
+                break;
+            }
+            current += v;
+            last = Part::Unit;
+        } else if let Some(v) = lookup(&EN_TEENS, key) {
+            if !after_break {
+                break;
+            }
+            current += v;
+            last = Part::Teen;
+        } else if let Some(v) = lookup(&EN_TENS, key) {
+            if !after_break {
+                break;
+            }
+            current += v;
+            last = Part::Tens;
+        } else if key == "a" && idx == 0 {
+            // "a hundred", "a thousand"
+            let next_is_scale = tokens
+                .get(1)
+                .is_some_and(|t| t.key == "hundred" || lookup(&EN_SCALES, &t.key).is_some());
+            if !next_is_scale {
+                break;
+            }
+            current = 1;
+            last = Part::Unit;
+        } else if key == "hundred" {
+            if !matches!(last, Part::Unit | Part::Teen) || current == 0 || current >= 100 {
+                break;
+            }
+            current *= 100;
+            last = Part::Hundred;
+        } else if let Some(scale) = lookup(&EN_SCALES, key) {
+            if matches!(last, Part::Start | Part::And) || scale >= last_scale || current == 0 {
+                break;
+            }
+            total += current * scale;
+            current = 0;
+            last_scale = scale;
+            last = Part::Scale(scale);
+        } else if key == "and" {
+            let next_is_number = tokens.get(idx + 1).is_some_and(|t| {
+                is_en_number_word(&t.key) && lookup(&EN_ORDINALS, &t.key).is_none()
+            });
+            if !matches!(last, Part::Hundred | Part::Scale(_)) || !next_is_number {
+                break;
+            }
+            last = Part::And;
+            idx += 1;
+            continue;
+        } else {
+            break;
+        }
+        idx += 1;
+        used = idx;
+    }
+
+    if used == 0 {
+        return None;
+    }
+    Some((total + current, used))
+}
+
+/// Up to two tokens worth less than 100 ("twenty six", "nineteen").
+fn en_below_100(tokens: &[Token]) -> Option<(u64, usize)> {
+    let first = tokens.first()?;
+    if let Some(v) = lookup(&EN_TEENS, &first.key) {
+        return Some((v, 1));
+    }
+    if let Some(tens) = lookup(&EN_TENS, &first.key) {
+        let unit = tokens
+            .get(1)
+            .and_then(|t| lookup(&EN_UNITS, &t.key))
+            .filter(|v| *v > 0);
+        return Some(match unit {
+            Some(unit) => (tens + unit, 2),
+            None => (tens, 1),
+        });
+    }
+    lookup(&EN_UNITS, &first.key).map(|v| (v, 1))
+}
+
+/// Years read in pairs: "nineteen ninety nine", "twenty oh five",
+/// "nineteen hundred".
+fn en_pair_year(
+    tokens: &[Token],
+    centuries: std::ops::RangeInclusive<u64>,
+) -> Option<(u64, usize)> {
+    let (century, used) = en_below_100(tokens)?;
+    if !centuries.contains(&century) {
+        return None;
+    }
+    let rest = &tokens[used..];
+    let first = rest.first()?;
+    if first.key == "hundred" {
+        return Some(match en_below_100(&rest[1..]) {
+            Some((year, year_used)) => (century * 100 + year, used + 1 + year_used),
+            None => (century * 100, used + 1),
+        });
+    }
+    if first.key == "oh" || first.key == "o" {
+        let unit = rest.get(1).and_then(|t| lookup(&EN_UNITS, &t.key))?;
+        return Some((century * 100 + unit, used + 2));
+    }
+    let (year, year_used) = en_below_100(rest)?;
+    if year < 10 {
+        return None;
+    }
+    Some((century * 100 + year, used + year_used))
+}
+
+fn en_ordinal(tokens: &[Token]) -> Option<(u32, usize)> {
+    let first = tokens.first()?;
+    if let Some(v) = digit_ordinal(&first.key) {
+        return Some((v, 1));
+    }
+    if let Some(v) = lookup(&EN_ORDINALS, &first.key) {
+        return Some((v, 1));
+    }
+    let tens = lookup(&EN_TENS, &first.key)?;
+    let unit = tokens.get(1).and_then(|t| lookup(&EN_ORDINALS, &t.key))?;
+    (unit < 10).then_some((tens as u32 + unit, 2))
+}
+
+/// "3rd", "21st", "2nd", "4th".
+fn digit_ordinal(key: &str) -> Option<u32> {
+    let digits_end = key.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, suffix) = key.split_at(digits_end);
+    if digits.is_empty() || !matches!(suffix, "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// A German number written as one word, up to 999 999 (also "neunzehnhundert").
+fn de_compound(word: &str) -> Option<u64> {
+    if word.is_empty() {
+        return None;
+    }
+    if let Some((left, right)) = word.split_once("tausend") {
+        let left = if left.is_empty() {
+            1
+        } else {
+            de_below_1000(left)?
+        };
+        let right = if right.is_empty() {
+            0
+        } else {
+            de_below_1000(right)?
+        };
+        return Some(left * 1000 + right);
+    }
+    de_below_1000(word)
+}
+
+fn de_below_1000(word: &str) -> Option<u64> {
+    if let Some((left, right)) = word.split_once("hundert") {
+        let left = if left.is_empty() {
+            1
+        } else {
+            lookup(&DE_COMPOUND_UNITS, left)
+                .or_else(|| lookup(&DE_SMALL, left).filter(|v| *v > 9))?
+        };
+        let right = right.strip_prefix("und").unwrap_or(right);
+        let right = if right.is_empty() {
+            0
+        } else {
+            de_below_100(right)?
+        };
+        return Some(left * 100 + right);
+    }
+    de_below_100(word)
+}
+
+fn de_below_100(word: &str) -> Option<u64> {
+    if let Some(v) = lookup(&DE_SMALL, word)
+        .or_else(|| lookup(&DE_TENS, word))
+        .or_else(|| lookup(&DE_COMPOUND_UNITS, word))
+    {
+        return Some(v);
+    }
+    let (unit, tens) = word.split_once("und")?;
+    Some(lookup(&DE_COMPOUND_UNITS, unit)? + lookup(&DE_TENS, tens)?)
+}
+
+/// German cardinal, optionally followed by "Millionen"/"Milliarden".
+fn de_cardinal(tokens: &[Token]) -> Option<(u64, usize)> {
+    let first = tokens.first()?;
+    let value = de_compound(&first.key)?;
+    let scale = tokens.get(1).and_then(|t| lookup(&DE_SCALES, &t.key));
+    // A bare "ein"/"eine" is an article, not a number.
+    let is_article = matches!(first.key.as_str(), "ein" | "eine");
+    match scale {
+        Some(scale) => {
+            let rest = tokens
+                .get(2)
+                .and_then(|t| de_compound(&t.key))
+                .filter(|v| *v < scale && !matches!(tokens[2].key.as_str(), "ein" | "eine"));
+            match rest {
+                Some(rest) => Some((value * scale + rest, 3)),
+                None => Some((value * scale, 2)),
+            }
+        }
+        None if is_article => None,
+        None => Some((value, 1)),
+    }
+}
+
+fn de_ordinal_word(key: &str) -> Option<u32> {
+    for suffix in ["sten", "ster", "stes", "stem", "ste"] {
+        if let Some(base) = key.strip_suffix(suffix) {
+            if base == "er" {
+                return Some(1);
+            }
+            if let Some(v) = de_compound(base).filter(|v| *v >= 20) {
+                return u32::try_from(v).ok();
+            }
+        }
+    }
+    for suffix in ["ten", "ter", "tes", "tem", "te"] {
+        if let Some(base) = key.strip_suffix(suffix) {
+            let value = match base {
+                "drit" => Some(3),
+                "sieb" => Some(7),
+                "ach" => Some(8),
+                _ => de_compound(base).filter(|v| (2..20).contains(v)),
+            };
+            if let Some(v) = value {
+                return u32::try_from(v).ok();
+            }
+        }
+    }
+    None
+}
+
+fn cardinal(lang: Lang, tokens: &[Token]) -> Option<(u64, usize)> {
+    match lang {
+        Lang::En => en_cardinal(tokens),
+        Lang::De => de_cardinal(tokens),
+    }
+}
+
+fn digit_word(lang: Lang, key: &str) -> Option<u64> {
+    match lang {
+        Lang::En if key == "oh" => Some(0),
+        Lang::En => lookup(&EN_UNITS, key),
+        Lang::De => lookup(&DE_SMALL, key).filter(|v| *v < 10),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Number {
+    negative: bool,
+    int: u64,
+    frac: Option<String>,
+    /// False when every token was already written in digits.
+    spelled: bool,
+    len: usize,
+}
+
+fn parse_number(lang: Lang, tokens: &[Token]) -> Option<Number> {
+    let first = tokens.first()?;
+    let negative = matches!(first.key.as_str(), "minus" | "negative");
+    let start = usize::from(negative);
+    let body = &tokens[start..];
+    let head = body.first()?;
+
+    let (int, mut frac, mut used, spelled) = if head.is_digits() {
+        (head.core.parse::<u64>().ok()?, None, 1, false)
+    } else if let Some((int, frac)) = head.core.split_once(lang.decimal_sep()).filter(|(i, f)| {
+        !i.is_empty()
+            && !f.is_empty()
+            && i.chars().all(|c| c.is_ascii_digit())
+            && f.chars().all(|c| c.is_ascii_digit())
+    }) {
+        (int.parse::<u64>().ok()?, Some(frac.to_string()), 1, false)
+    } else {
+        let (int, used) = cardinal(lang, body)?;
+        (int, None, used, true)
+    };
+
+    let decimal_word = match lang {
+        Lang::En => "point",
+        Lang::De => "komma",
+    };
+    if frac.is_none() && body.get(used).is_some_and(|t| t.key == decimal_word) {
+        let after = &body[used + 1..];
+        let digits: String = after
+            .iter()
+            .map_while(|t| digit_word(lang, &t.key))
+            .map(|d| d.to_string())
+            .collect();
+        if !digits.is_empty() {
+            used += 1 + digits.len();
+            frac = Some(digits);
+        } else if let Some((v, n)) = cardinal(lang, after).filter(|(v, _)| *v < 100) {
+            used += 1 + n;
+            frac = Some(v.to_string());
+        }
+    }
+
+    Some(Number {
+        negative,
+        int,
+        frac,
+        spelled: spelled || negative,
+        len: start + used,
+    })
+}
+
+fn group_digits(value: u64, sep: char) -> String {
+    let digits = value.to_string();
+    if value < 10_000 {
+        return digits;
+    }
+    let mut out = String::new();
+    for (idx, c) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            out.push(sep);
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn format_number(lang: Lang, number: &Number) -> String {
+    let mut out = String::new();
+    if number.negative {
+        out.push('-');
+    }
+    out.push_str(&group_digits(number.int, lang.group_sep()));
+    if let Some(ref frac) = number.frac {
+        out.push(lang.decimal_sep());
+        out.push_str(frac);
+    }
+    out
+}
+
+fn match_currency(lang: Lang, tokens: &[Token], number: &Number) -> Option<(usize, String)> {
+    let (currencies, minor): (&[Currency], &[&str]) = match lang {
+        Lang::En => (&EN_CURRENCIES, &EN_MINOR_CURRENCY),
+        Lang::De => (&DE_CURRENCIES, &DE_MINOR_CURRENCY),
+    };
+    let word = tokens.get(number.len)?;
+    let currency = currencies
+        .iter()
+        .find(|c| c.words.contains(&word.key.as_str()))?;
+    let mut used = number.len + 1;
+
+    // "five dollars and fifty cents", "fünf Euro fünfzig"
+    let mut cents = None;
+    if number.frac.is_none() && !number.negative {
+        let mut at = used;
+        if tokens
+            .get(at)
+            .is_some_and(|t| matches!(t.key.as_str(), "and" | "und"))
+        {
+            at += 1;
+        }
+        if let Some((value, n)) = cardinal(lang, &tokens[at.min(tokens.len())..]) {
+            let has_minor = tokens
+                .get(at + n)
+                .is_some_and(|t| minor.contains(&t.key.as_str()));
+            if value < 100 && (has_minor || lang == Lang::De) {
+                cents = Some(value);
+                used = at + n + usize::from(has_minor);
+            }
+        }
+    }
+
+    let amount = match cents {
+        Some(cents) => Number {
+            frac: Some(format!("{cents:02}")),
+            ..number.clone()
+        },
+        None => number.clone(),
+    };
+    let amount = format_number(lang, &amount);
+    let text = match lang {
+        Lang::En if amount.starts_with('-') => format!("-{}{}", currency.symbol, &amount[1..]),
+        Lang::En => format!("{}{amount}", currency.symbol),
+        Lang::De => format!("{amount} {}", currency.symbol),
+    };
+    Some((used, text))
+}
+
+fn match_unit(lang: Lang, tokens: &[Token], number: &Number) -> Option<(usize, String)> {
+    let table: &[Unit] = match lang {
+        Lang::En => &EN_UNIT_TABLE,
+        Lang::De => &DE_UNIT_TABLE,
+    };
+    let rest = &tokens[number.len..];
+    let unit = table.iter().find(|u| {
+        u.words.len() <= rest.len() && u.words.iter().zip(rest).all(|(w, t)| *w == t.key)
+    })?;
+    let amount = format_number(lang, number);
+    let text = if unit.spaced {
+        format!("{amount} {}", unit.symbol)
+    } else {
+        format!("{amount}{}", unit.symbol)
+    };
+    Some((number.len + unit.words.len(), text))
+}
+
+fn en_month(token: &Token) -> Option<u32> {
+    EN_MONTHS
+        .iter()
+        .position(|m| *m == token.key)
+        .map(|idx| idx as u32 + 1)
+}
+
+fn en_year(tokens: &[Token]) -> Option<(i32, usize)> {
+    let first = tokens.first()?;
+    if first.is_digits() && first.core.len() == 4 {
+        return Some((first.core.parse().ok()?, 1));
+    }
+    let (year, used) = en_pair_year(tokens, 10..=29)
+        .or_else(|| en_cardinal(tokens).filter(|(v, _)| *v >= 1000))?;
+    (1000..=2999).contains(&year).then_some((year as i32, used))
+}
+
+fn de_year(tokens: &[Token]) -> Option<(i32, usize)> {
+    let first = tokens.first()?;
+    let year = if first.is_digits() && first.core.len() == 4 {
+        first.core.parse().ok()?
+    } else {
+        de_compound(&first.key)?
+    };
+    (1000..=2999).contains(&year).then_some((year as i32, 1))
+}
+
+/// Tokens of the year following a date at `at`: a comma may separate them.
+fn year_run(tokens: &[Token], at: usize) -> &[Token] {
+    if at == 0 || at >= tokens.len() {
+        return &[];
+    }
+    let before = &tokens[at - 1];
+    if !matches!(before.trail.as_str(), "" | ",") || !tokens[at].lead.is_empty() {
+        return &[];
+    }
+    run(tokens, at)
+}
+
+fn format_date(lang: Lang, year: Option<i32>, month: u32, day: u32) -> Option<String> {
+    match year {
+        Some(year) => {
+            let date = NaiveDate::from_ymd_opt(year, month, day)?;
+            Some(match lang {
+                Lang::En => date.format("%Y-%m-%d").to_string(),
+                Lang::De => date.format("%d.%m.%Y").to_string(),
+            })
+        }
+        None => {
+            // Any leap year accepts every valid day/month pair.
+            NaiveDate::from_ymd_opt(2024, month, day)?;
+            Some(match lang {
+                Lang::En => {
+                    let name = EN_MONTHS[month as usize - 1];
+                    format!("{}{} {day}", name[..1].to_uppercase(), &name[1..])
+                }
+                Lang::De => format!("{day}. {}", DE_MONTH_NAMES[month as usize - 1]),
+            })
+        }
+    }
+}
+
+/// "march third twenty twenty six", "the third of March", "March 3, 2026".
+fn match_en_date(tokens: &[Token], start: usize) -> Option<(usize, String)> {
+    let phrase = run(tokens, start);
+    let first = phrase.first()?;
+
+    let (month, day, ordinal, mut end) = if let Some(month) = en_month(first) {
+        let rest = &phrase[1..];
+        if let Some((day, used)) = en_ordinal(rest) {
+            (month, day, true, start + 1 + used)
+        } else {
+            // Lowercase "may"/"march" are usually verbs.
+            if !first.is_capitalized() {
+                return None;
+            }
+            let (day, used) = en_below_100(rest).or_else(|| {
+                let t = rest.first().filter(|t| t.is_digits())?;
+                Some((t.core.parse::<u64>().ok()?, 1))
+            })?;
+            (month, u32::try_from(day).ok()?, false, start + 1 + used)
+        }
+    } else {
+        let skip = usize::from(first.key == "the");
+        let (day, used) = en_ordinal(&phrase[skip..])?;
+        phrase.get(skip + used).filter(|t| t.key == "of")?;
+        let month = phrase.get(skip + used + 1).and_then(en_month)?;
+        (month, day, true, start + skip + used + 2)
+    };
+
+    let year = en_year(year_run(tokens, end)).map(|(year, used)| {
+        end += used;
+        year
+    });
+    // "May five people came" is not a date; a plain day number needs a year.
+    if !ordinal && year.is_none() {
+        return None;
+    }
+
+    let spelled = tokens[start..end].iter().any(|t| {
+        !t.is_digits() && en_month(t).is_none() && !matches!(t.key.as_str(), "the" | "of")
+    });
+    if !spelled {
+        return None;
+    }
+    Some((end - start, format_date(Lang::En, year, month, day)?))
+}
+
+/// "dritter März zweitausendsechsundzwanzig", "3. März 2026".
+fn match_de_date(tokens: &[Token], start: usize) -> Option<(usize, String)> {
+    let day_token = tokens.get(start)?;
+    let day = if day_token.is_digits() && day_token.trail == "." {
+        day_token.core.parse().ok()?
+    } else if day_token.trail.is_empty() {
+        de_ordinal_word(&day_token.key)?
+    } else {
+        return None;
+    };
+
+    let month_token = tokens.get(start + 1).filter(|t| t.lead.is_empty())?;
+    let month = lookup(&DE_MONTHS, &month_token.key)?;
+    let mut end = start + 2;
+
+    let year = de_year(year_run(tokens, end)).map(|(year, used)| {
+        end += used;
+        year
+    });
+
+    let spelled = tokens[start..end]
+        .iter()
+        .any(|t| !t.is_digits() && lookup(&DE_MONTHS, &t.key).is_none());
+    if !spelled {
+        return None;
+    }
+    Some((end - start, format_date(Lang::De, year, month, day)?))
+}
+
+/// Spoken numbers side by side ("twelve fifteen", "twenty, five") may be a
+/// time, a score or a list; writing only some of them would garble it.
+fn next_to_number(lang: Lang, tokens: &[Token], start: usize, len: usize) -> bool {
+    let spelled = |token: &Token| cardinal(lang, std::slice::from_ref(token)).is_some();
+    start
+        .checked_sub(1)
+        .is_some_and(|idx| spelled(&tokens[idx]))
+        || tokens.get(start + len).is_some_and(spelled)
+}
+
+/// Tries every rule at `start` and returns how many tokens were replaced and by what.
+fn match_at(lang: Lang, tokens: &[Token], start: usize) -> Option<(usize, String)> {
+    let date = match lang {
+        Lang::En => match_en_date(tokens, start),
+        Lang::De => match_de_date(tokens, start),
+    };
+    if date.is_some() {
+        return date;
+    }
+
+    let phrase = run(tokens, start);
+    if lang == Lang::En {
+        // "nineteen eighty four"; earlier centuries read like clock times.
+        if let Some((year, used)) = en_pair_year(phrase, 19..=20) {
+            if phrase
+                .get(used)
+                .is_none_or(|t| lookup(&EN_SCALES, &t.key).is_none())
+            {
+                return Some((used, year.to_string()));
+            }
+        }
+    }
+
+    let number = parse_number(lang, phrase)?;
+    if let Some(found) = match_currency(lang, phrase, &number) {
+        return Some(found);
+    }
+    if let Some(found) = match_unit(lang, phrase, &number) {
+        return Some(found);
+    }
+
+    let worth_writing =
+        number.int >= lang.min_standalone() || number.frac.is_some() || number.negative;
+    if number.spelled && worth_writing && !next_to_number(lang, tokens, start, number.len) {
+        return Some((number.len, format_number(lang, &number)));
+    }
+    None
+}
+
+/// Rewrites spoken numbers, dates, currencies and units in `text` into their
+/// written forms using the rules for `language`. Text in languages without
+/// rules is returned unchanged.
+pub fn normalize(text: &str, language: &str) -> String {
+    let Some(lang) = Lang::from_code(language) else {
+        return text.to_string();
+    };
+
+    // Replacements are spliced into the original text, so the whitespace and
+    // line breaks around them survive.
+    let tokens = tokenize(text, lang);
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match match_at(lang, &tokens, idx) {
+            Some((used, written)) if used > 0 => {
+                let span = tokens[idx].span.start..tokens[idx + used - 1].span.end;
+                out.push_str(&text[copied..span.start]);
+                out.push_str(&written);
+                copied = span.end;
+                idx += used;
+            }
+            _ => idx += 1,
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGLISH: &[(&str, &str)] = &[
+        // Currencies
+        ("twenty five dollars", "$25"),
+        ("It costs twenty-five dollars.", "It costs $25."),
+        ("five dollars and fifty cents", "$5.50"),
+        ("one hundred euros", "€100"),
+        ("three pounds", "three pounds"),
+        ("25 dollars", "$25"),
+        ("two thousand five hundred dollars", "$2500"),
+        ("twenty five thousand dollars", "$25,000"),
+        ("three point five million", "3.5 million"),
+        // Dates
+        ("march third twenty twenty six", "2026-03-03"),
+        (
+            "on March third, twenty twenty six we ship",
+            "on 2026-03-03 we ship",
+        ),
+        ("the third of march twenty twenty six", "2026-03-03"),
+        ("July fourth nineteen seventy six", "1976-07-04"),
+        ("December twenty first two thousand and five", "2005-12-21"),
+        ("january first twenty oh nine", "2009-01-01"),
+        ("see you march twenty first", "see you March 21"),
+        ("June 3rd 2026", "2026-06-03"),
+        (
+            "February thirtieth twenty twenty four",
+            "February thirtieth 2024",
+        ),
+        ("In May five people came", "In May five people came"),
+        ("May 5, 2026", "May 5, 2026"),
+        ("May five twenty twenty six", "2026-05-05"),
+        // Units
+        ("five k m", "5 km"),
+        ("five kilometers", "5 km"),
+        ("one kilometer", "1 km"),
+        ("a hundred and twenty kilometers per hour", "120 km/h"),
+        ("twelve kilograms", "12 kg"),
+        ("fifty percent", "50%"),
+        ("ninety nine per cent", "99%"),
+        ("minus five degrees celsius", "-5°C"),
+        ("sixteen gigabytes of RAM", "16 GB of RAM"),
+        ("two point four gigahertz", "2.4 GHz"),
+        ("3.5 kilometers", "3.5 km"),
+        // Plain numbers
+        ("twenty three people", "23 people"),
+        ("one hundred and five", "105"),
+        ("nineteen hundred eighty four", "1984"),
+        ("two thousand three hundred forty", "2340"),
+        ("three million", "3,000,000"),
+        ("three point one four", "3.14"),
+        ("in twenty twenty six", "in 2026"),
+        ("twenty five hundred", "2500"),
+        ("ten", "10"),
+        // Left alone
+        ("I have one question", "I have one question"),
+        ("one of the five", "one of the five"),
+        ("you may be right", "you may be right"),
+        ("we march on", "we march on"),
+        ("the first time", "the first time"),
+        ("second thoughts", "second thoughts"),
+        ("meet at twelve fifteen", "meet at twelve fifteen"),
+        ("twenty, five", "twenty, five"),
+        ("five, twenty", "five, twenty"),
+        ("Call me at 5.", "Call me at 5."),
+        ("It's 2026 already", "It's 2026 already"),
+        ("", ""),
+    ];
+
+    const GERMAN: &[(&str, &str)] = &[
+        // Currencies
+        ("fünfundzwanzig Euro", "25 €"),
+        ("Das kostet fünfundzwanzig Euro.", "Das kostet 25 €."),
+        ("fünf Euro fünfzig", "5,50 €"),
+        ("zwei Euro und zwanzig Cent", "2,20 €"),
+        ("hundert Dollar", "100 $"),
+        ("dreitausend Franken", "3000 CHF"),
+        ("fünfundzwanzigtausend Euro", "25.000 €"),
+        // Dates
+        ("dritter März zweitausendsechsundzwanzig", "03.03.2026"),
+        ("am dritten März", "am 3. März"),
+        (
+            "am einunddreißigsten Dezember neunzehnhundertneunundneunzig",
+            "am 31.12.1999",
+        ),
+        ("der erste Mai", "der 1. Mai"),
+        ("3. März zweitausendsechsundzwanzig", "03.03.2026"),
+        ("am zwanzigsten Juli 2026", "am 20.07.2026"),
+        ("3. März 2026", "3. März 2026"),
+        // Units
+        ("fünf Kilometer", "5 km"),
+        ("fünf k m", "5 km"),
+        ("hundertzwanzig Kilometer pro Stunde", "120 km/h"),
+        ("fünfzig Prozent", "50 %"),
+        ("minus fünf Grad Celsius", "-5 °C"),
+        ("drei Komma fünf Liter", "3,5 l"),
+        ("zwei Kilo Mehl", "2 kg Mehl"),
+        ("Hundert Prozent meiner Zeit", "100 % meiner Zeit"),
+        // Plain numbers
+        ("dreiundzwanzig Leute", "23 Leute"),
+        ("zweitausenddreihundertvierzig", "2340"),
+        ("drei Millionen", "3.000.000"),
+        ("zwei Millionen fünfhunderttausend", "2.500.000"),
+        ("dreißig", "30"),
+        ("hundertdrei", "103"),
+        // Left alone
+        ("ein Mann", "ein Mann"),
+        ("eine Frau und zwei Kinder", "eine Frau und zwei Kinder"),
+        ("Elf Freunde", "Elf Freunde"),
+        ("der dritte Versuch", "der dritte Versuch"),
+        ("Hundertschaft", "Hundertschaft"),
+        ("Tausendfüßler", "Tausendfüßler"),
+    ];
+
+    #[test]
+    fn english_table() {
+        for (input, expected) in ENGLISH {
+            assert_eq!(normalize(input, "en"), *expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn german_table() {
+        for (input, expected) in GERMAN {
+            assert_eq!(normalize(input, "de"), *expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn language_names_and_regions_are_accepted() {
+        assert_eq!(normalize("twenty dollars", "english"), "$20");
+        assert_eq!(normalize("twenty dollars", "en-US"), "$20");
+        assert_eq!(normalize("zwanzig Euro", "German"), "20 €");
+        assert_eq!(normalize("zwanzig Euro", "de_AT"), "20 €");
+    }
+
+    #[test]
+    fn unsupported_language_is_left_untouched() {
+        assert_eq!(normalize("vingt-cinq euros", "fr"), "vingt-cinq euros");
+    }
+
+    #[test]
+    fn unchanged_text_keeps_original_whitespace() {
+        let text = "Line one\nline  two";
+        assert_eq!(normalize(text, "en"), text);
+    }
+
+    #[test]
+    fn changed_text_keeps_original_whitespace() {
+        let text =
+            "It costs twenty-five dollars.\n\nWe leave  March third, twenty twenty six\n\tat noon.";
+        assert_eq!(
+            normalize(text, "en"),
+            "It costs $25.\n\nWe leave  2026-03-03\n\tat noon."
+        );
+    }
+
+    #[test]
+    fn german_compounds_parse() {
+        assert_eq!(de_compound("einundzwanzig"), Some(21));
+        assert_eq!(
+            de_compound("neunhundertneunundneunzigtausend"),
+            Some(999_000)
+        );
+        assert_eq!(de_compound("eintausendeins"), Some(1001));
+        assert_eq!(de_compound("neunzehnhundertachtzig"), Some(1980));
+        assert_eq!(de_compound("hundertundfünf"), Some(105));
+        assert_eq!(de_compound("schaft"), None);
+    }
+
+    #[test]
+    fn german_ordinals_parse() {
+        let cases = [
+            ("erste", 1),
+            ("zweiten", 2),
+            ("dritter", 3),
+            ("siebten", 7),
+            ("achte", 8),
+            ("sechsten", 6),
+            ("neunzehnten", 19),
+            ("zwanzigsten", 20),
+            ("einunddreissigster", 31),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(de_ordinal_word(word), Some(expected), "{word}");
+        }
+        assert_eq!(de_ordinal_word("leute"), None);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::groq_request::{Transcript, TranscriptSegment, TranscriptionOptions};
+use hound::{WavReader, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MIC_TRACK_LABEL: &str = "Me";
+pub const DESKTOP_TRACK_LABEL: &str = "Remote";
+
+/// How microphone and desktop audio are stored when both are recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum TrackLayout {
+    /// One mono track with both sources mixed.
+    #[default]
+    Mixed,
+    /// One stereo file: microphone left, desktop right.
+    Stereo,
+    /// Two mono files of equal length; the desktop one ends in `_desktop`.
+    SeparateFiles,
+}
+
+impl TrackLayout {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrackLayout::Mixed => "Mixed (mono)",
+            TrackLayout::Stereo => "Stereo file (mic left, desktop right)",
+            TrackLayout::SeparateFiles => "Two files",
+        }
+    }
+
+    pub fn is_split(&self) -> bool {
+        !matches!(self, TrackLayout::Mixed)
+    }
+}
+
+/// `recording_1.wav` → `recording_1_desktop.wav`.
+pub fn desktop_track_path(mic_path: &Path) -> PathBuf {
+    let stem = mic_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = match mic_path.extension() {
+        Some(ext) => format!("{stem}_desktop.{}", ext.to_string_lossy()),
+        None => format!("{stem}_desktop"),
+    };
+    mic_path.with_file_name(file_name)
+}
+
+/// Writes the two channels of a stereo WAV into two mono WAVs in `dir`.
+fn split_stereo_wav(path: &Path, dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let mut reader =
+        WavReader::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let spec = reader.spec();
+    if spec.channels != 2 {
+        return Err(format!(
+            "Expected a stereo recording, {} has {} channel(s)",
+            path.display(),
+            spec.channels
+        ));
+    }
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let pid = std::process::id();
+    let mic_path = dir.join(format!("wgo_track_mic_{pid}_{ts}.wav"));
+    let desktop_path = dir.join(format!("wgo_track_desktop_{pid}_{ts}.wav"));
+    let mono = WavSpec {
+        channels: 1,
+        ..spec
+    };
+    let mut mic = WavWriter::create(&mic_path, mono).map_err(|e| e.to_string())?;
+    let mut desktop = WavWriter::create(&desktop_path, mono).map_err(|e| e.to_string())?;
+
+    for (i, sample) in reader.samples::<i16>().enumerate() {
+        let sample = sample.map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let writer = if i % 2 == 0 { &mut mic } else { &mut desktop };
+        writer.write_sample(sample).map_err(|e| e.to_string())?;
+    }
+    mic.finalize().map_err(|e| e.to_string())?;
+    desktop.finalize().map_err(|e| e.to_string())?;
+    Ok((mic_path, desktop_path))
+}
+
+/// Interleaves the segments of both tracks by start time into one transcript
+/// with a speaker label per turn.
+pub fn label_segments(mic: &Transcript, desktop: &Transcript) -> Transcript {
+    fn turns(
+        transcript: &Transcript,
+        label: &'static str,
+    ) -> Vec<(&'static str, TranscriptSegment)> {
+        if transcript.segments.is_empty() {
+            // Providers without segment timestamps: one turn for the whole track.
+            if transcript.text.trim().is_empty() {
+                return Vec::new();
+            }
+            return vec![(
+                label,
+                TranscriptSegment {
+                    start: 0.0,
+                    end: 0.0,
+                    text: transcript.text.clone(),
+                    no_speech_prob: None,
+                    compression_ratio: None,
+                    avg_logprob: None,
+                },
+            )];
+        }
+        transcript
+            .segments
+            .iter()
+            .cloned()
+            .map(|s| (label, s))
+            .collect()
+    }
+
+    let mut all = turns(mic, MIC_TRACK_LABEL);
+    all.extend(turns(desktop, DESKTOP_TRACK_LABEL));
+    // Stable, so the microphone wins ties.
+    all.sort_by(|a, b| a.1.start.total_cmp(&b.1.start));
+
+    let mut lines: Vec<(&str, String)> = Vec::new();
+    for (label, segment) in &all {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match lines.last_mut() {
+            Some((last, line)) if last == label => {
+                line.push(' ');
+                line.push_str(text);
+            }
+            _ => lines.push((label, text.to_string())),
+        }
+    }
+
+    Transcript {
+        text: lines
+            .iter()
+            .map(|(label, line)| format!("{label}: {line}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        segments: all.into_iter().map(|(_, s)| s).collect(),
+        language: mic.language.clone().or_else(|| desktop.language.clone()),
+    }
+}
+
+/// Transcribes each track of a two-track recording on its own and labels the
+/// turns. A track judged to be a hallucination (e.g. silent desktop audio)
+/// is left out.
+pub fn transcribe_tracks(
+    audio_path: &str,
+    layout: TrackLayout,
+    options: &TranscriptionOptions,
+    suppress_hallucinations: bool,
+    compress_pauses: bool,
+) -> Result<Transcript, String> {
+    let path = Path::new(audio_path);
+    let (mic_path, desktop_path, temporary) = match layout {
+        TrackLayout::Mixed => return Err("Recording has a single track".to_string()),
+        TrackLayout::Stereo => {
+            let (mic, desktop) = split_stereo_wav(path, &std::env::temp_dir())?;
+            (mic, desktop, true)
+        }
+        TrackLayout::SeparateFiles => (path.to_path_buf(), desktop_track_path(path), false),
+    };
+
+    let transcribe = |track: &Path| -> Result<Transcript, String> {
+        let transcript =
+            crate::pauses::transcribe(&track.to_string_lossy(), options, compress_pauses)?;
+        if suppress_hallucinations {
+            let energy = crate::hallucination::audio_energy(track);
+            if let Some(reason) = crate::hallucination::assess(&transcript, energy) {
+                println!("Dropping track {}: {reason}", track.display());
+                return Ok(Transcript::default());
+            }
+        }
+        Ok(transcript)
+    };
+    let result = transcribe(&mic_path).and_then(|mic| Ok((mic, transcribe(&desktop_path)?)));
+
+    if temporary {
+        let _ = std::fs::remove_file(&mic_path);
+        let _ = std::fs::remove_file(&desktop_path);
+    }
+
+    let (mic, desktop) = result?;
+    Ok(label_segments(&mic, &desktop))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn segment(start: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start,
+            end: start + 1.0,
+            text: text.to_string(),
+            no_speech_prob: None,
+            compression_ratio: None,
+            avg_logprob: None,
+        }
+    }
+
+    fn transcript(segments: Vec<TranscriptSegment>) -> Transcript {
+        Transcript {
+            text: segments
+                .iter()
+                .map(|s| s.text.trim())
+                .collect::<Vec<_>>()
+                .join(" "),
+            segments,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn turns_are_interleaved_by_start_time() {
+        let mic = transcript(vec![
+            segment(0.0, " Hi, can you hear me?"),
+            segment(6.0, " Great."),
+        ]);
+        let desktop = transcript(vec![
+            segment(2.5, " Yes, loud and clear."),
+            segment(4.0, " Let's start."),
+        ]);
+        let labeled = label_segments(&mic, &desktop);
+        assert_eq!(
+            labeled.text,
+            "Me: Hi, can you hear me?\nRemote: Yes, loud and clear. Let's start.\nMe: Great."
+        );
+        assert_eq!(labeled.segments.len(), 4);
+    }
+
+    #[test]
+    fn empty_track_leaves_only_the_other_speaker() {
+        let mic = transcript(vec![segment(0.0, "Just me talking.")]);
+        let labeled = label_segments(&mic, &Transcript::default());
+        assert_eq!(labeled.text, "Me: Just me talking.");
+    }
+
+    #[test]
+    fn transcripts_without_segments_become_one_turn() {
+        let mic = Transcript {
+            text: "Hello there".to_string(),
+            ..Transcript::default()
+        };
+        let desktop = Transcript {
+            text: "General Kenobi".to_string(),
+            ..Transcript::default()
+        };
+        assert_eq!(
+            label_segments(&mic, &desktop).text,
+            "Me: Hello there\nRemote: General Kenobi"
+        );
+    }
+
+    #[test]
+    fn desktop_track_path_keeps_extension() {
+        assert_eq!(
+            desktop_track_path(Path::new("/rec/recording_1.wav")),
+            PathBuf::from("/rec/recording_1_desktop.wav")
+        );
+    }
+
+    #[test]
+    fn stereo_wav_splits_into_mic_and_desktop() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("stereo.wav");
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..100i16 {
+            writer.write_sample(i).unwrap();
+            writer.write_sample(-i).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (mic, desktop) = split_stereo_wav(&path, tmp.path()).expect("split");
+        let read = |p: &Path| -> Vec<i16> {
+            WavReader::open(p)
+                .unwrap()
+                .samples::<i16>()
+                .map(Result::unwrap)
+                .collect()
+        };
+        assert_eq!(read(&mic), (0..100).collect::<Vec<i16>>());
+        assert_eq!(read(&desktop), (0..100).map(|i| -i).collect::<Vec<i16>>());
+        assert_eq!(WavReader::open(&mic).unwrap().spec().channels, 1);
+    }
+
+    #[test]
+    fn mono_file_is_not_split() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("mono.wav");
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        WavWriter::create(&path, spec).unwrap().finalize().unwrap();
+        assert!(split_stereo_wav(&path, tmp.path()).is_err());
+    }
+}
@@ -1,3 +1,7 @@
+use crate::groq_request::TranscriptSegment;
+use crate::quality::AudioQuality;
+use crate::routing::Route;
+use crate::tracks::TrackLayout;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -9,6 +13,26 @@ pub struct TranscriptionRecord {
     pub timestamp: u64,
     #[serde(default)]
     pub audio_path: Option<String>,
+    #[serde(default)]
+    pub route: Option<Route>,
+    /// Why the output looked like a Whisper hallucination; such records were
+    /// not copied to the clipboard.
+    #[serde(default)]
+    pub suspicious: Option<String>,
+    /// `transcription` rendered into `translation_language`.
+    #[serde(default)]
+    pub translation: Option<String>,
+    #[serde(default)]
+    pub translation_language: Option<String>,
+    /// How mic and desktop audio were stored; split layouts are labelled by speaker.
+    #[serde(default)]
+    pub track_layout: TrackLayout,
+    /// Levels measured while recording; `None` for imported or older files.
+    #[serde(default)]
+    pub quality: Option<AudioQuality>,
+    /// Timed sentences from the provider, used to seek in the player.
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -105,6 +129,13 @@ mod tests {
             transcription: text.to_string(),
             timestamp: 1_700_000_000,
             audio_path: None,
+            route: None,
+            suspicious: None,
+            translation: None,
+            translation_language: None,
+            track_layout: TrackLayout::Mixed,
+            quality: None,
+            segments: Vec::new(),
         }
     }
 
@@ -188,6 +219,13 @@ mod tests {
             transcription: "spoken words".to_string(),
             timestamp: 1_700_000_000,
             audio_path: Some("/tmp/recording.m4a".to_string()),
+            route: None,
+            suspicious: None,
+            translation: None,
+            translation_language: None,
+            track_layout: TrackLayout::Mixed,
+            quality: None,
+            segments: Vec::new(),
         });
         save_to_dir(&h, tmp.path());
 
@@ -216,6 +254,52 @@ mod tests {
         assert_eq!(loaded.records.len(), 1);
         assert_eq!(loaded.records[0].transcription, "old text");
         assert!(loaded.records[0].audio_path.is_none());
+        assert!(loaded.records[0].route.is_none());
+        assert!(loaded.records[0].translation.is_none());
+        assert_eq!(loaded.records[0].track_layout, TrackLayout::Mixed);
+    }
+
+    #[test]
+    fn translation_is_stored_next_to_original() {
+        let tmp = tempdir().expect("tempdir");
+        let mut h = TranscriptionHistory::new();
+        let mut record = make_record("good morning");
+        record.translation = Some("guten Morgen".to_string());
+        record.translation_language = Some("German".to_string());
+        h.records.push(record);
+        save_to_dir(&h, tmp.path());
+
+        let loaded = load_from_dir(tmp.path());
+        assert_eq!(loaded.records[0].transcription, "good morning");
+        assert_eq!(
+            loaded.records[0].translation.as_deref(),
+            Some("guten Morgen")
+        );
+        assert_eq!(
+            loaded.records[0].translation_language.as_deref(),
+            Some("German")
+        );
+    }
+
+    #[test]
+    fn route_round_trips_through_history_file() {
+        use crate::routing::TranscriptionProvider;
+
+        let tmp = tempdir().expect("tempdir");
+        let mut h = TranscriptionHistory::new();
+        let mut record = make_record("routed");
+        record.route = Some(Route {
+            provider: TranscriptionProvider::Local,
+            model: "large-v3".to_string(),
+            rule: Some("confidential".to_string()),
+        });
+        h.records.push(record);
+        save_to_dir(&h, tmp.path());
+
+        let loaded = load_from_dir(tmp.path());
+        let route = loaded.records[0].route.as_ref().expect("route");
+        assert_eq!(route.provider, TranscriptionProvider::Local);
+        assert_eq!(route.rule.as_deref(), Some("confidential"));
     }
 
     // ── corrupt JSON falls back to empty ────────────────────────────────────
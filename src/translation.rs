@@ -0,0 +1,250 @@
+use crate::config::AppConfig;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const GROQ_CHAT_COMPLETIONS_URL: &str = "https://api.groq.com/openai/v1/chat/completions";
+pub const DEFAULT_TRANSLATION_MODEL: &str = "llama-3.3-70b-versatile";
+
+/// How the markdown export shows a transcript together with its translation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum TranslationLayout {
+    /// Original first, translation under its own heading.
+    #[default]
+    Stacked,
+    /// Two-column table, one row per paragraph.
+    SideBySide,
+}
+
+impl TranslationLayout {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TranslationLayout::Stacked => "Stacked",
+            TranslationLayout::SideBySide => "Side by side",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub language: String,
+    pub text: String,
+}
+
+fn request_body(model: &str, text: &str, target_language: &str) -> Value {
+    let instructions = format!(
+        "You are a translation engine. Translate the user's message into {target_language}. \
+         Keep the meaning, tone, names, numbers and line breaks. \
+         Reply with the translation only, without notes or quotes."
+    );
+    json!({
+        "model": model,
+        "temperature": 0,
+        "messages": [
+            { "role": "system", "content": instructions },
+            { "role": "user", "content": text },
+        ],
+    })
+}
+
+fn parse_response(json: &Value) -> Result<String, String> {
+    let content = json
+        .pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .ok_or_else(|| "Translation response has no message content".to_string())?;
+    if content.is_empty() {
+        return Err("Translation response is empty".to_string());
+    }
+    Ok(content.to_string())
+}
+
+/// The bearer token for the translation endpoint. The Groq key only ever goes
+/// to Groq; any other server gets `translation_api_key` or no auth at all.
+fn api_key(config: &AppConfig) -> Option<&str> {
+    let key = config.translation_api_key.trim();
+    if !key.is_empty() {
+        return Some(key);
+    }
+    let endpoint = config.translation_endpoint.trim().trim_end_matches('/');
+    let key = config.groq_api_key.trim();
+    (endpoint == GROQ_CHAT_COMPLETIONS_URL && !key.is_empty()).then_some(key)
+}
+
+/// Translates `text` with the chat-completions endpoint from the config.
+pub fn translate(
+    config: &AppConfig,
+    text: &str,
+    target_language: &str,
+) -> Result<Translation, String> {
+    let target_language = target_language.trim();
+    if target_language.is_empty() {
+        return Err("Translation target language is empty".to_string());
+    }
+
+    let api_key = api_key(config);
+    let mut request = Client::new()
+        .post(config.translation_endpoint.trim())
+        .json(&request_body(
+            config.translation_model.trim(),
+            text,
+            target_language,
+        ));
+    if let Some(api_key) = api_key {
+        request = request.header("Authorization", format!("Bearer {api_key}"));
+    }
+
+    let response = request
+        .send()
+        .map_err(|e| format!("Translation request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response
+            .text()
+            .unwrap_or_else(|_| "Unable to read response body".to_string());
+        return Err(format!("Translation failed with status {status}: {body}"));
+    }
+
+    let json: Value = response
+        .json()
+        .map_err(|e| format!("Invalid translation response: {e}"))?;
+    Ok(Translation {
+        language: target_language.to_string(),
+        text: parse_response(&json)?,
+    })
+}
+
+fn table_cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Markdown body with the original and its translation.
+pub fn markdown_body(
+    original: &str,
+    translation: &Translation,
+    layout: TranslationLayout,
+) -> String {
+    match layout {
+        TranslationLayout::Stacked => format!(
+            "{}\n\n## Translation ({})\n\n{}",
+            original.trim(),
+            translation.language,
+            translation.text.trim()
+        ),
+        TranslationLayout::SideBySide => {
+            let mut table = format!(
+                "| Original | {} |\n| --- | --- |\n",
+                table_cell(&translation.language)
+            );
+            let left = paragraphs(original);
+            let right = paragraphs(&translation.text);
+            // Line breaks usually survive translation; if they did not, one row
+            // keeps the two texts aligned.
+            if left.len() == right.len() && !left.is_empty() {
+                for (l, r) in left.iter().zip(&right) {
+                    table.push_str(&format!("| {} | {} |\n", table_cell(l), table_cell(r)));
+                }
+            } else {
+                table.push_str(&format!(
+                    "| {} | {} |\n",
+                    table_cell(original),
+                    table_cell(&translation.text)
+                ));
+            }
+            table.trim_end().to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn german(text: &str) -> Translation {
+        Translation {
+            language: "German".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn request_names_target_language_and_passes_text_verbatim() {
+        let body = request_body("llama", "Hello | world", "German");
+        assert_eq!(body["model"], "llama");
+        let system = body["messages"][0]["content"].as_str().unwrap();
+        assert!(system.contains("into German"));
+        assert_eq!(body["messages"][1]["content"], "Hello | world");
+    }
+
+    #[test]
+    fn parse_response_reads_first_choice() {
+        let json: Value = serde_json::from_str(
+            r#"{"choices":[{"message":{"role":"assistant","content":"  Hallo Welt \n"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(parse_response(&json).unwrap(), "Hallo Welt");
+    }
+
+    #[test]
+    fn parse_response_rejects_missing_or_empty_content() {
+        let missing: Value = serde_json::from_str(r#"{"choices":[]}"#).unwrap();
+        assert!(parse_response(&missing).is_err());
+        let empty: Value =
+            serde_json::from_str(r#"{"choices":[{"message":{"content":"  "}}]}"#).unwrap();
+        assert!(parse_response(&empty).is_err());
+    }
+
+    #[test]
+    fn groq_key_is_only_sent_to_groq() {
+        let mut config = AppConfig {
+            groq_api_key: "gsk-secret".to_string(),
+            ..AppConfig::default()
+        };
+        config.translation_endpoint = GROQ_CHAT_COMPLETIONS_URL.to_string();
+        assert_eq!(api_key(&config), Some("gsk-secret"));
+
+        config.translation_endpoint = "http://localhost:11434/v1/chat/completions".to_string();
+        assert_eq!(api_key(&config), None);
+
+        config.translation_api_key = " local-key ".to_string();
+        assert_eq!(api_key(&config), Some("local-key"));
+    }
+
+    #[test]
+    fn stacked_layout_puts_translation_under_heading() {
+        let body = markdown_body("Hello", &german("Hallo"), TranslationLayout::Stacked);
+        assert_eq!(body, "Hello\n\n## Translation (German)\n\nHallo");
+    }
+
+    #[test]
+    fn side_by_side_pairs_paragraphs() {
+        let body = markdown_body(
+            "First line.\n\nSecond | line.",
+            &german("Erste Zeile.\nZweite | Zeile."),
+            TranslationLayout::SideBySide,
+        );
+        assert_eq!(
+            body,
+            "| Original | German |\n| --- | --- |\n\
+             | First line. | Erste Zeile. |\n\
+             | Second \\| line. | Zweite \\| Zeile. |"
+        );
+    }
+
+    #[test]
+    fn side_by_side_falls_back_to_single_row_when_paragraphs_differ() {
+        let body = markdown_body(
+            "One.\nTwo.",
+            &german("Eins. Zwei."),
+            TranslationLayout::SideBySide,
+        );
+        assert!(body.ends_with("| One.<br>Two. | Eins. Zwei. |"));
+    }
+}
@@ -16,6 +16,22 @@ pub fn copy_to_clipboard(text: &str) {
     }
 }
 
+/// Returns the clipboard's text, or `None` when it is empty or holds something else.
+pub fn read_clipboard_text() -> Option<String> {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            eprintln!("Failed to access clipboard: {}", e);
+            return None;
+        }
+    };
+
+    clipboard
+        .get_text()
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
 pub fn open_folder_in_file_explorer(path: &str) -> Result<(), std::io::Error> {
     #[cfg(target_os = "windows")]
     std::process::Command::new("explorer").arg(path).spawn()?;
@@ -0,0 +1,341 @@
+use hound::{WavReader, WavWriter};
+use std::path::{Path, PathBuf};
+
+/// Analysis frame length.
+const FRAME_SECS: f32 = 0.02;
+/// Speech keeps counting this long after the last voiced frame, so short
+/// pauses between words don't count as silence.
+const HANGOVER_SECS: f32 = 0.3;
+/// Audio kept before the first and after the last speech when trimming.
+const TRIM_PADDING_SECS: f32 = 0.3;
+/// Quieter frames are never speech, however quiet the room is.
+const MIN_SPEECH_DBFS: f32 = -50.0;
+/// Speech has to stand this far above the noise floor.
+const SPEECH_OVER_NOISE_DB: f32 = 10.0;
+/// Frames crossing zero this often are hiss (fans, breath), not voice.
+const NOISE_ZCR: f32 = 0.45;
+const INITIAL_NOISE_FLOOR_DBFS: f32 = -60.0;
+
+fn frame_dbfs(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    if mean_square <= 0.0 {
+        -120.0
+    } else {
+        (10.0 * mean_square.log10()).max(-120.0)
+    }
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f32 / frame.len().max(1) as f32
+}
+
+/// Energy and zero-crossing voice activity detector for mono audio.
+pub struct Vad {
+    sample_rate: u32,
+    frame_len: usize,
+    frame: Vec<f32>,
+    noise_floor_dbfs: f32,
+    hangover_frames: usize,
+    hangover_left: usize,
+    silent_samples: u64,
+}
+
+impl Vad {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = frame_len(sample_rate);
+        Self {
+            sample_rate: sample_rate.max(1),
+            frame_len,
+            frame: Vec::with_capacity(frame_len),
+            noise_floor_dbfs: INITIAL_NOISE_FLOOR_DBFS,
+            hangover_frames: (HANGOVER_SECS / FRAME_SECS) as usize,
+            hangover_left: 0,
+            silent_samples: 0,
+        }
+    }
+
+    /// Classifies one frame and follows the background noise level.
+    fn is_speech_frame(&mut self, frame: &[f32]) -> bool {
+        let dbfs = frame_dbfs(frame);
+        let threshold = (self.noise_floor_dbfs + SPEECH_OVER_NOISE_DB).max(MIN_SPEECH_DBFS);
+        let speech = dbfs > threshold && zero_crossing_rate(frame) < NOISE_ZCR;
+
+        if dbfs < self.noise_floor_dbfs {
+            self.noise_floor_dbfs = dbfs;
+        } else if speech {
+            // Creep up slowly so a noise floor that rises mid-recording is
+            // eventually picked up.
+            self.noise_floor_dbfs += 0.002 * (dbfs - self.noise_floor_dbfs);
+        } else {
+            self.noise_floor_dbfs += 0.1 * (dbfs - self.noise_floor_dbfs);
+        }
+        speech
+    }
+
+    /// Feeds captured samples and updates the running silence length.
+    pub fn process(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() < self.frame_len {
+                continue;
+            }
+            let frame = std::mem::take(&mut self.frame);
+            if self.is_speech_frame(&frame) {
+                self.hangover_left = self.hangover_frames;
+                self.silent_samples = 0;
+            } else if self.hangover_left > 0 {
+                self.hangover_left -= 1;
+            } else {
+                self.silent_samples += frame.len() as u64;
+            }
+            self.frame = frame;
+            self.frame.clear();
+        }
+    }
+
+    /// Seconds since the last speech (or since the start, if nobody spoke).
+    pub fn silence_secs(&self) -> f32 {
+        self.silent_samples as f32 / self.sample_rate as f32
+    }
+}
+
+/// Speech decision for each `frame_len()` chunk of a finished clip.
+pub fn speech_frames(samples: &[f32], sample_rate: u32) -> Vec<bool> {
+    let mut vad = Vad::new(sample_rate);
+    // Start from the quieter end of the clip instead of a guess.
+    let mut levels: Vec<f32> = samples.chunks(vad.frame_len).map(frame_dbfs).collect();
+    levels.sort_by(f32::total_cmp);
+    if let Some(&quiet) = levels.get(levels.len() / 10) {
+        vad.noise_floor_dbfs = quiet;
+    }
+    samples
+        .chunks(vad.frame_len)
+        .map(|frame| vad.is_speech_frame(frame))
+        .collect()
+}
+
+/// Samples per analysis frame at `sample_rate`.
+pub fn frame_len(sample_rate: u32) -> usize {
+    ((sample_rate as f32 * FRAME_SECS) as usize).max(1)
+}
+
+/// Sample range from just before the first to just after the last speech, or
+/// `None` when the clip holds no speech at all.
+pub fn speech_bounds(samples: &[f32], sample_rate: u32) -> Option<(usize, usize)> {
+    let frames = speech_frames(samples, sample_rate);
+    let frame_len = frame_len(sample_rate);
+    let first = frames.iter().position(|&speech| speech)? * frame_len;
+    let last = frames.iter().rposition(|&speech| speech)?;
+    let last = ((last + 1) * frame_len).min(samples.len());
+
+    let padding = (sample_rate as f32 * TRIM_PADDING_SECS) as usize;
+    Some((
+        first.saturating_sub(padding),
+        (last + padding).min(samples.len()),
+    ))
+}
+
+type Track = (hound::WavSpec, Vec<i16>);
+
+fn read_track(path: &Path) -> Result<Track, String> {
+    let mut reader =
+        WavReader::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let spec = reader.spec();
+    let samples = reader
+        .samples::<i16>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    Ok((spec, samples))
+}
+
+/// Cuts leading and trailing silence from WAV files recorded together, using
+/// the same range for all of them so they stay in sync. Returns the seconds
+/// removed; files without any speech are left alone.
+pub fn trim_silence(paths: &[PathBuf]) -> Result<f32, String> {
+    let tracks = paths
+        .iter()
+        .map(|p| read_track(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some((first_spec, _)) = tracks.first() else {
+        return Ok(0.0);
+    };
+    let rate = first_spec.sample_rate;
+
+    // Detect on everything that was recorded, mixed down to mono.
+    let frames = tracks
+        .iter()
+        .map(|(spec, s)| s.len() / spec.channels.max(1) as usize)
+        .min()
+        .unwrap_or(0);
+    let mut mono = vec![0.0f32; frames];
+    for (spec, samples) in &tracks {
+        let channels = spec.channels.max(1) as usize;
+        for (i, frame) in samples.chunks(channels).take(frames).enumerate() {
+            mono[i] += frame.iter().map(|&s| s as f32 / 32768.0).sum::<f32>();
+        }
+    }
+
+    let Some((start, end)) = speech_bounds(&mono, rate) else {
+        return Ok(0.0);
+    };
+    if start == 0 && end == frames {
+        return Ok(0.0);
+    }
+
+    for (path, (spec, samples)) in paths.iter().zip(&tracks) {
+        let channels = spec.channels.max(1) as usize;
+        let tmp = path.with_extension("trim.wav");
+        let mut writer = WavWriter::create(&tmp, *spec).map_err(|e| e.to_string())?;
+        for &sample in &samples[start * channels..end * channels] {
+            writer.write_sample(sample).map_err(|e| e.to_string())?;
+        }
+        writer.finalize().map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path)
+            .map_err(|e| format!("Failed to replace {}: {e}", path.display()))?;
+    }
+    Ok((frames - (end - start)) as f32 / rate as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const RATE: u32 = 16_000;
+
+    fn silence(secs: f32) -> Vec<f32> {
+        vec![0.0; (RATE as f32 * secs) as usize]
+    }
+
+    /// A voiced, speech-like tone (a 150 Hz buzz with harmonics).
+    fn voice(secs: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let w = 2.0 * std::f32::consts::PI * 150.0 * t;
+                0.2 * w.sin() + 0.1 * (2.0 * w).sin() + 0.05 * (3.0 * w).sin()
+            })
+            .collect()
+    }
+
+    /// Deterministic white noise at roughly `amplitude` RMS.
+    fn hiss(secs: f32, amplitude: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..(RATE as f32 * secs) as usize)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude * 1.7
+            })
+            .collect()
+    }
+
+    fn concat(parts: &[Vec<f32>]) -> Vec<f32> {
+        parts.concat()
+    }
+
+    #[test]
+    fn silence_counts_up_and_resets_on_speech() {
+        let mut vad = Vad::new(RATE);
+        vad.process(&silence(2.0));
+        assert!((vad.silence_secs() - 2.0).abs() < 0.05);
+
+        vad.process(&voice(0.5));
+        assert_eq!(vad.silence_secs(), 0.0);
+
+        // The hangover bridges short pauses.
+        vad.process(&silence(0.2));
+        assert_eq!(vad.silence_secs(), 0.0);
+        vad.process(&silence(1.0));
+        assert!((vad.silence_secs() - 0.9).abs() < 0.05);
+    }
+
+    #[test]
+    fn steady_fan_noise_is_not_speech() {
+        let mut vad = Vad::new(RATE);
+        vad.process(&hiss(3.0, 0.02));
+        assert!(vad.silence_secs() > 2.5, "silence {}", vad.silence_secs());
+
+        vad.process(&concat(&[voice(0.5), hiss(0.1, 0.02)]));
+        assert_eq!(vad.silence_secs(), 0.0);
+    }
+
+    #[test]
+    fn bounds_cover_speech_plus_padding() {
+        let samples = concat(&[silence(2.0), voice(1.0), silence(3.0)]);
+        let (start, end) = speech_bounds(&samples, RATE).expect("speech");
+        let secs = |n: usize| n as f32 / RATE as f32;
+        assert!((secs(start) - 1.7).abs() < 0.05, "start {}", secs(start));
+        assert!((secs(end) - 3.3).abs() < 0.05, "end {}", secs(end));
+    }
+
+    #[test]
+    fn clip_without_speech_has_no_bounds() {
+        assert_eq!(speech_bounds(&silence(1.0), RATE), None);
+        assert_eq!(speech_bounds(&hiss(1.0, 0.01), RATE), None);
+    }
+
+    fn write_wav(path: &Path, channels: u16, samples: &[f32]) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample((s * 32767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn duration(path: &Path) -> f32 {
+        let reader = WavReader::open(path).unwrap();
+        reader.duration() as f32 / reader.spec().sample_rate as f32
+    }
+
+    #[test]
+    fn trim_keeps_tracks_in_sync() {
+        let tmp = tempdir().expect("tempdir");
+        let mic = tmp.path().join("rec.wav");
+        let desktop = tmp.path().join("rec_desktop.wav");
+        // Mic speaks early, desktop late: the kept range must cover both.
+        write_wav(&mic, 1, &concat(&[silence(1.0), voice(1.0), silence(4.0)]));
+        write_wav(
+            &desktop,
+            1,
+            &concat(&[silence(3.0), voice(1.0), silence(2.0)]),
+        );
+
+        let removed = trim_silence(&[mic.clone(), desktop.clone()]).expect("trim");
+        assert!((removed - 2.4).abs() < 0.05, "removed {removed}");
+        assert!((duration(&mic) - 3.6).abs() < 0.05);
+        assert!((duration(&desktop) - 3.6).abs() < 0.05);
+    }
+
+    #[test]
+    fn trim_handles_stereo_and_leaves_silent_files_alone() {
+        let tmp = tempdir().expect("tempdir");
+        let stereo = tmp.path().join("stereo.wav");
+        let interleaved: Vec<f32> = concat(&[silence(2.0), voice(1.0), silence(2.0)])
+            .into_iter()
+            .flat_map(|s| [s, 0.0])
+            .collect();
+        write_wav(&stereo, 2, &interleaved);
+        trim_silence(std::slice::from_ref(&stereo)).expect("trim");
+        assert!((duration(&stereo) - 1.6).abs() < 0.05);
+
+        let quiet = tmp.path().join("quiet.wav");
+        write_wav(&quiet, 1, &silence(2.0));
+        assert_eq!(
+            trim_silence(std::slice::from_ref(&quiet)).expect("trim"),
+            0.0
+        );
+        assert!((duration(&quiet) - 2.0).abs() < 0.01);
+    }
+}